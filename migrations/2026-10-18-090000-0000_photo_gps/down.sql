@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE photos
+    DROP INDEX idx_photo_location,
+    DROP COLUMN altitude,
+    DROP COLUMN longitude,
+    DROP COLUMN latitude;
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+-- GPS position of the photo, in WGS84 decimal degrees / metres above sea level
+ALTER TABLE photos
+    ADD COLUMN latitude DOUBLE NULL,
+    ADD COLUMN longitude DOUBLE NULL,
+    ADD COLUMN altitude FLOAT NULL,
+    ADD INDEX idx_photo_location (latitude, longitude);
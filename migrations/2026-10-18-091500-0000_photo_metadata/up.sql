@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE photo_metadata (
+    id BIGINT NOT NULL,
+    -- Full exiftool dump of the original, as a JSON object keyed by `Group:Tag`
+    metadata LONGTEXT NOT NULL,
+    PRIMARY KEY (id),
+    CONSTRAINT metadata_is_json CHECK (JSON_VALID(metadata)),
+    CONSTRAINT fk_metadata_photo
+        FOREIGN KEY (id) REFERENCES photos(id)
+            ON DELETE CASCADE
+);
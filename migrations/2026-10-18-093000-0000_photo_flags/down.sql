@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE photos
+    DROP INDEX idx_photo_rating,
+    DROP CONSTRAINT pick_flag_in_range,
+    DROP CONSTRAINT rating_in_range,
+    DROP COLUMN pick_flag,
+    DROP COLUMN color_label,
+    DROP COLUMN rating;
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+ALTER TABLE photos
+    -- Star rating, 0 (unrated) to 5
+    ADD COLUMN rating TINYINT NOT NULL DEFAULT 0,
+    -- One of 'red', 'yellow', 'green', 'blue', 'purple', or NULL if unlabeled
+    ADD COLUMN color_label VARCHAR(16) NULL,
+    -- -1 (rejected), 0 (unflagged) or 1 (picked)
+    ADD COLUMN pick_flag TINYINT NOT NULL DEFAULT 0,
+    ADD CONSTRAINT rating_in_range CHECK (rating BETWEEN 0 AND 5),
+    ADD CONSTRAINT pick_flag_in_range CHECK (pick_flag BETWEEN -1 AND 1),
+    ADD INDEX idx_photo_rating (rating);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS photo_tag_join;
+DROP TABLE IF EXISTS tags;
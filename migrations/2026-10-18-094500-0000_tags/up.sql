@@ -0,0 +1,25 @@
+-- Your SQL goes here
+CREATE TABLE tags (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    tag_name VARCHAR(255) NOT NULL,
+    -- Parent keyword in the hierarchy (e.g. `Japan` for `Places|Japan|Kyoto`), NULL for root keywords
+    parent_id INT NULL,
+    CONSTRAINT tag_name_valid CHECK (tag_name != '' AND tag_name NOT LIKE '%|%'),
+    CONSTRAINT fk_tag_parent
+        FOREIGN KEY (parent_id) REFERENCES tags(id)
+            ON DELETE CASCADE,
+    UNIQUE KEY uq_tag_sibling (parent_id, tag_name)
+);
+
+CREATE TABLE photo_tag_join (
+    tag_id INT NOT NULL,
+    photo_id BIGINT NOT NULL,
+    PRIMARY KEY (tag_id, photo_id),
+    CONSTRAINT fk_photo_tag_tag
+        FOREIGN KEY (tag_id) REFERENCES tags(id)
+            ON DELETE CASCADE,
+    CONSTRAINT fk_photo_tag_photo
+        FOREIGN KEY (photo_id) REFERENCES photos(id)
+            ON DELETE CASCADE,
+    INDEX idx_tag_photo_id (photo_id)
+);
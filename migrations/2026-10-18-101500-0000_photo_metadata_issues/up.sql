@@ -0,0 +1,34 @@
+-- Your SQL goes here
+CREATE TABLE photo_metadata_issues (
+    photo_id BIGINT NOT NULL,
+    -- Which metadata field fell back to a default at ingest, e.g. 'missing_date'
+    issue VARCHAR(32) NOT NULL,
+    PRIMARY KEY (photo_id, issue),
+    CONSTRAINT fk_metadata_issue_photo
+        FOREIGN KEY (photo_id) REFERENCES photos(id)
+            ON DELETE CASCADE,
+    INDEX idx_metadata_issue (issue)
+);
+
+-- Flag photos ingested before issues were recorded, based on the fallback values stored for them.
+-- A fallback timezone cannot be told apart from a real one, so `missing_timezone` is not backfilled.
+INSERT INTO photo_metadata_issues (photo_id, issue)
+    SELECT id, 'missing_date' FROM photos WHERE photo_date < '1970-01-02';
+INSERT INTO photo_metadata_issues (photo_id, issue)
+    SELECT id, 'missing_resolution' FROM photos WHERE resolution_width = 0 OR resolution_height = 0;
+INSERT INTO photo_metadata_issues (photo_id, issue)
+    SELECT id, 'unknown_mime_type' FROM photos WHERE mime_type = 'application/octet-stream';
+INSERT INTO photo_metadata_issues (photo_id, issue)
+    SELECT id, 'unknown_camera' FROM photos WHERE camera_model IN ('', 'Unknown Camera');
+INSERT INTO photo_metadata_issues (photo_id, issue)
+    SELECT id, 'unknown_lens' FROM photos WHERE lens_model IN ('', 'Unknown Lens');
+INSERT INTO photo_metadata_issues (photo_id, issue)
+    SELECT id, 'missing_shutter_count' FROM photos WHERE shutter_count = 0;
+INSERT INTO photo_metadata_issues (photo_id, issue)
+    SELECT id, 'missing_focal_length' FROM photos WHERE focal_length = 0;
+INSERT INTO photo_metadata_issues (photo_id, issue)
+    SELECT id, 'missing_iso' FROM photos WHERE iso = 0;
+INSERT INTO photo_metadata_issues (photo_id, issue)
+    SELECT id, 'missing_shutter_speed' FROM photos WHERE shutter_speed IN ('', 'Unknown');
+INSERT INTO photo_metadata_issues (photo_id, issue)
+    SELECT id, 'missing_aperture' FROM photos WHERE aperture = 0;
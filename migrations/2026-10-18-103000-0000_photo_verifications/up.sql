@@ -0,0 +1,14 @@
+-- Your SQL goes here
+CREATE TABLE photo_verifications (
+    id BIGINT NOT NULL PRIMARY KEY,
+    -- When the original was last re-hashed
+    verified_at DATETIME NOT NULL,
+    -- Outcome of the last check: 'ok', 'mismatch' or 'missing'
+    status VARCHAR(16) NOT NULL,
+    CONSTRAINT fk_verification_photo
+        FOREIGN KEY (id) REFERENCES photos(id)
+            ON DELETE CASCADE,
+    CONSTRAINT chk_verification_status CHECK (status IN ('ok', 'mismatch', 'missing')),
+    INDEX idx_verification_verified_at (verified_at),
+    INDEX idx_verification_status (status)
+);
@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE albums
+    DROP INDEX idx_album_deleted_at,
+    DROP COLUMN deleted_at;
+
+ALTER TABLE photos
+    DROP INDEX idx_photo_deleted_at,
+    DROP COLUMN deleted_at;
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+-- Set when a photo or album is moved to `$STORAGE_ROOT/.trash`; NULL otherwise
+ALTER TABLE photos
+    ADD COLUMN deleted_at DATETIME NULL,
+    ADD INDEX idx_photo_deleted_at (deleted_at);
+
+ALTER TABLE albums
+    ADD COLUMN deleted_at DATETIME NULL,
+    ADD INDEX idx_album_deleted_at (deleted_at);
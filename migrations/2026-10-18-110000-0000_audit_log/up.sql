@@ -0,0 +1,27 @@
+-- Your SQL goes here
+CREATE TABLE audit_log (
+    id BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    -- When the operation finished (local time of the server)
+    created_at DATETIME NOT NULL,
+    -- Who performed the operation: the `X-Suisai-User` header, or `cli:<user>` for subcommands
+    actor VARCHAR(255) NOT NULL,
+    -- Kind of operation, e.g. 'photo_reassign'
+    action VARCHAR(32) NOT NULL,
+    -- IDs of the affected photos and albums, as JSON arrays
+    photo_ids LONGTEXT NOT NULL,
+    album_ids LONGTEXT NOT NULL,
+    -- Names, paths and album links of the affected photos and albums before and after the operation, as JSON objects
+    before_state LONGTEXT NULL,
+    after_state LONGTEXT NULL,
+    -- 'success' or 'failure', with the error message of a failure
+    result VARCHAR(16) NOT NULL,
+    error TEXT NULL,
+    CONSTRAINT audit_photo_ids_is_json CHECK (JSON_VALID(photo_ids)),
+    CONSTRAINT audit_album_ids_is_json CHECK (JSON_VALID(album_ids)),
+    CONSTRAINT audit_before_is_json CHECK (before_state IS NULL OR JSON_VALID(before_state)),
+    CONSTRAINT audit_after_is_json CHECK (after_state IS NULL OR JSON_VALID(after_state)),
+    CONSTRAINT chk_audit_result CHECK (result IN ('success', 'failure')),
+    INDEX idx_audit_created_at (created_at),
+    INDEX idx_audit_actor (actor),
+    INDEX idx_audit_action (action)
+);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE audit_log
+    DROP FOREIGN KEY fk_audit_undone_by,
+    DROP COLUMN undone_by;
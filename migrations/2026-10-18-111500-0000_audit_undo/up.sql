@@ -0,0 +1,7 @@
+-- Your SQL goes here
+-- The audit log entry of the undo that reversed this operation; NULL if it was not undone
+ALTER TABLE audit_log
+    ADD COLUMN undone_by BIGINT NULL,
+    ADD CONSTRAINT fk_audit_undone_by
+        FOREIGN KEY (undone_by) REFERENCES audit_log(id)
+            ON DELETE SET NULL;
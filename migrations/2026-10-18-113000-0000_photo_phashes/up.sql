@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE photo_phashes (
+    id BIGINT NOT NULL PRIMARY KEY,
+    -- 64-bit difference hash (dHash) of the photo's thumbnail; similar images differ in few bits
+    phash BIGINT NOT NULL,
+    CONSTRAINT fk_phash_photo
+        FOREIGN KEY (id) REFERENCES photos(id)
+            ON DELETE CASCADE
+);
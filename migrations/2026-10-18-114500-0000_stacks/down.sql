@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE photos
+    DROP FOREIGN KEY fk_photo_stack,
+    DROP COLUMN stack_id;
+
+DROP TABLE stacks;
@@ -0,0 +1,18 @@
+-- Your SQL goes here
+-- A burst or exposure bracket, shown as its cover photo when listings collapse stacks
+CREATE TABLE stacks (
+    id INT NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    -- `burst` or `bracket`
+    kind VARCHAR(16) NOT NULL,
+    cover_id BIGINT NULL,
+    created_at DATETIME NOT NULL,
+    CONSTRAINT fk_stack_cover
+        FOREIGN KEY (cover_id) REFERENCES photos(id)
+            ON DELETE SET NULL
+);
+
+ALTER TABLE photos
+    ADD COLUMN stack_id INT NULL,
+    ADD CONSTRAINT fk_photo_stack
+        FOREIGN KEY (stack_id) REFERENCES stacks(id)
+            ON DELETE SET NULL;
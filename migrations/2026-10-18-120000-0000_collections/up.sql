@@ -0,0 +1,24 @@
+-- Your SQL goes here
+-- Groupings of photos that exist only in the database, independent of the album directories
+CREATE TABLE collections (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    collection_name VARCHAR(255) NOT NULL,
+    created_at DATETIME NOT NULL,
+    CONSTRAINT collection_name_valid CHECK (collection_name != ''),
+    UNIQUE KEY uq_collection_name (collection_name)
+);
+
+CREATE TABLE collection_photo_join (
+    collection_id INT NOT NULL,
+    photo_id BIGINT NOT NULL,
+    -- Place of the photo in the collection, starting at 0
+    position INT NOT NULL,
+    PRIMARY KEY (collection_id, photo_id),
+    CONSTRAINT fk_collection_photo_collection
+        FOREIGN KEY (collection_id) REFERENCES collections(id)
+            ON DELETE CASCADE,
+    CONSTRAINT fk_collection_photo_photo
+        FOREIGN KEY (photo_id) REFERENCES photos(id)
+            ON DELETE CASCADE,
+    INDEX idx_collection_photo_id (photo_id)
+);
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+-- Albums defined by a saved query over photo metadata, evaluated whenever they are listed
+CREATE TABLE smart_albums (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    album_name VARCHAR(255) NOT NULL,
+    -- JSON `SmartQuery`: `{"match": "all" | "any", "rules": [{"field", "op", "value"}, ...]}`
+    query LONGTEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    CONSTRAINT smart_album_name_valid CHECK (album_name != '')
+);
@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE albums
+    DROP FOREIGN KEY fk_album_cover,
+    DROP COLUMN description,
+    DROP COLUMN cover_id,
+    DROP COLUMN created_at,
+    DROP COLUMN updated_at,
+    DROP COLUMN sort_position;
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- Descriptive metadata of albums. The date range of an album is derived from its photos and not stored.
+ALTER TABLE albums
+    ADD COLUMN description TEXT NULL,
+    -- Chosen cover photo; NULL (or a photo that left the album) falls back to the album's earliest photo
+    ADD COLUMN cover_id BIGINT NULL,
+    ADD COLUMN created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ADD COLUMN updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    -- Position among sibling albums; albums without one are listed after the others, by name
+    ADD COLUMN sort_position INT NULL,
+    ADD CONSTRAINT fk_album_cover
+        FOREIGN KEY (cover_id) REFERENCES photos(id)
+            ON DELETE SET NULL;
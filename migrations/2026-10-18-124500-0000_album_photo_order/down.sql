@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE album_photo_join
+    DROP COLUMN position;
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+-- Place of the photo in the album, starting at 0. New photos are placed according to $ALBUM_INSERT_ORDER.
+ALTER TABLE album_photo_join
+    ADD COLUMN position INT NOT NULL DEFAULT 0;
+
+-- Existing albums start out in capture order
+UPDATE album_photo_join
+    INNER JOIN (
+        SELECT album_photo_join.parent_id, album_photo_join.photo_id,
+               ROW_NUMBER() OVER (PARTITION BY album_photo_join.parent_id ORDER BY photos.photo_date, photos.id) - 1 AS ordinal
+        FROM album_photo_join
+            INNER JOIN photos ON photos.id = album_photo_join.photo_id
+    ) AS ordered
+        ON ordered.parent_id = album_photo_join.parent_id AND ordered.photo_id = album_photo_join.photo_id
+SET album_photo_join.position = ordered.ordinal;
@@ -0,0 +1,16 @@
+-- This file should undo anything in `up.sql`
+-- Copies cannot satisfy the old uniqueness constraints, so they are dropped from the library (their files stay on disk)
+DELETE FROM photos WHERE original_id IS NOT NULL;
+
+ALTER TABLE photos
+    DROP INDEX uq_hash,
+    DROP INDEX uq_file_name,
+    DROP COLUMN original_hash,
+    DROP COLUMN original_file_name,
+    DROP INDEX idx_photo_hash,
+    ADD UNIQUE KEY uq_hash (hash),
+    ADD UNIQUE KEY uq_file_name (file_name(64));
+
+ALTER TABLE photos
+    DROP FOREIGN KEY fk_photo_original,
+    DROP COLUMN original_id;
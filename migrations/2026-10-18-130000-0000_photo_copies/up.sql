@@ -0,0 +1,19 @@
+-- Your SQL goes here
+-- Set on a physical copy of a photo in another album directory, pointing at the photo it was copied from.
+-- Copies share the hash (and usually the file name) of their original, so only originals must be unique.
+-- MySQL refuses SET NULL and CASCADE actions on the base column of an indexed generated column, so
+-- deleting an original is left to the application, which promotes a surviving copy first.
+ALTER TABLE photos
+    ADD COLUMN original_id BIGINT NULL,
+    ADD CONSTRAINT fk_photo_original
+        FOREIGN KEY (original_id) REFERENCES photos(id);
+
+-- The hash and file name of originals only (NULL for copies), which the unique indexes are put on
+ALTER TABLE photos
+    DROP INDEX uq_hash,
+    DROP INDEX uq_file_name,
+    ADD INDEX idx_photo_hash (hash),
+    ADD COLUMN original_hash CHAR(32) AS (IF(original_id IS NULL, hash, NULL)) VIRTUAL,
+    ADD COLUMN original_file_name VARCHAR(64) AS (IF(original_id IS NULL, file_name, NULL)) VIRTUAL,
+    ADD UNIQUE KEY uq_hash (original_hash),
+    ADD UNIQUE KEY uq_file_name (original_file_name);
@@ -0,0 +1,12 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE tags
+    DROP FOREIGN KEY fk_tag_parent;
+
+ALTER TABLE tags
+    DROP INDEX uq_tag_sibling,
+    DROP INDEX idx_tag_parent,
+    DROP COLUMN parent_key,
+    ADD UNIQUE KEY uq_tag_sibling (parent_id, tag_name),
+    ADD CONSTRAINT fk_tag_parent
+        FOREIGN KEY (parent_id) REFERENCES tags(id)
+            ON DELETE CASCADE;
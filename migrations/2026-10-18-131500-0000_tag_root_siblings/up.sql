@@ -0,0 +1,29 @@
+-- Your SQL goes here
+-- A unique key treats NULLs as distinct, so `uq_tag_sibling` never kept root keywords apart. It is
+-- put on `parent_key` instead, which is 0 (no tag ID) for root keywords.
+
+-- Root keywords that were created twice keep the oldest one's name; the others get their ID appended
+-- and can be merged with `POST /tag/merge`
+UPDATE tags
+    INNER JOIN (
+        SELECT tag_name, MIN(id) AS keep_id
+        FROM tags
+        WHERE parent_id IS NULL
+        GROUP BY tag_name
+        HAVING COUNT(*) > 1
+    ) AS duplicates
+        ON duplicates.tag_name = tags.tag_name AND tags.parent_id IS NULL AND tags.id != duplicates.keep_id
+SET tags.tag_name = CONCAT(tags.tag_name, ' (', tags.id, ')');
+
+-- MySQL refuses CASCADE actions on the base column of an indexed generated column, so subtrees are
+-- deleted by the application from now on
+ALTER TABLE tags
+    DROP FOREIGN KEY fk_tag_parent;
+
+ALTER TABLE tags
+    DROP INDEX uq_tag_sibling,
+    ADD COLUMN parent_key INT AS (COALESCE(parent_id, 0)) VIRTUAL,
+    ADD UNIQUE KEY uq_tag_sibling (parent_key, tag_name),
+    ADD INDEX idx_tag_parent (parent_id),
+    ADD CONSTRAINT fk_tag_parent
+        FOREIGN KEY (parent_id) REFERENCES tags(id);
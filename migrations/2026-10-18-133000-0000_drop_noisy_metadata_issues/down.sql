@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+INSERT INTO photo_metadata_issues (photo_id, issue)
+    SELECT id, 'missing_shutter_count' FROM photos WHERE shutter_count = 0;
+INSERT INTO photo_metadata_issues (photo_id, issue)
+    SELECT id, 'missing_focal_length' FROM photos WHERE focal_length = 0;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+-- Most cameras don't record a shutter count and manual lenses don't report a focal length, so these
+-- flagged nearly every photo and are no longer checked
+DELETE FROM photo_metadata_issues WHERE issue IN ('missing_shutter_count', 'missing_focal_length');
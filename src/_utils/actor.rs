@@ -0,0 +1,35 @@
+use rocket::request::{FromRequest, Outcome, Request};
+use std::convert::Infallible;
+use std::env;
+
+/// Request header naming the user behind a request, set by the frontend or an authenticating reverse proxy
+pub const ACTOR_HEADER: &str = "X-Suisai-User";
+
+/// Who performed an operation, as recorded in the audit log
+///
+/// As a request guard, it is taken from the `X-Suisai-User` header. Requests without it are recorded
+/// as `anonymous`. Subcommands run as `cli:<$USER>` (see `Actor::cli`).
+#[derive(Clone, Debug)]
+pub struct Actor(pub String);
+
+impl Actor {
+    /// The actor of a subcommand run from the shell
+    pub fn cli() -> Self {
+        Actor(format!("cli:{}", env::var("USER").unwrap_or_else(|_| "unknown".into())))
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Actor {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let name = request.headers()
+            .get_one(ACTOR_HEADER)
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or("anonymous");
+
+        Outcome::Success(Actor(name.to_string()))
+    }
+}
@@ -8,6 +8,7 @@ use serde::de::DeserializeOwned;
 
 pub trait JsonMap {
     fn get_value<T>(&self, key: &str) -> anyhow::Result<T> where T: DeserializeOwned;
+    fn get_optional<T>(&self, key: &str) -> anyhow::Result<Option<T>> where T: DeserializeOwned;
 }
 
 impl JsonMap for Json<Value> {
@@ -37,4 +38,24 @@ impl JsonMap for Json<Value> {
 
         Err(anyhow::anyhow!("Key \"{}\" not found in JSON", camel_key))
     }
+
+    /// Same as `get_value`, but returns `Ok(None)` if the key is absent instead of an error.
+    ///
+    /// A key that is present with a value of the wrong type is still an error. To accept an
+    /// explicit `null`, request an `Option<T>`, which yields `Some(None)` for `null`.
+    ///
+    /// # Example
+    /// ```
+    /// let rating: Option<i8> = json.get_optional("rating")?;
+    /// let label: Option<Option<String>> = json.get_optional("color_label")?;
+    /// ```
+    fn get_optional<T>(&self, key: &str) -> anyhow::Result<Option<T>> where T: DeserializeOwned {
+
+        let camel_key = key.to_camel_case();
+
+        match self.get(&camel_key) {
+            Some(value) => Ok(Some(serde_json::from_value(value.clone())?)),
+            None => Ok(None),
+        }
+    }
 }
\ No newline at end of file
@@ -1,5 +1,7 @@
 pub mod run_command;
 pub mod json_map;
 pub mod path_prefix;
+pub mod time_shift;
+pub mod actor;
 mod unwrap_ret;
 mod msg;
\ No newline at end of file
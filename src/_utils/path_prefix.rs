@@ -51,6 +51,24 @@ pub trait PathPrefix {
     /// assert_eq!(rel_path.prefix(&base), PathBuf::from("/var/data/subdir/file.txt"));
     /// ```
     fn prefix<P: AsRef<Path>>(&self, base: P) -> PathBuf;
+
+    /// Same as `prefix`, but fails if the result would end up outside of `base`.
+    ///
+    /// `..` components are resolved lexically (without following symlinks), so a path like
+    /// `/album/../../etc` is rejected, while `/album/../other` resolves to `base/other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::{Path, PathBuf};
+    /// # use path_prefix::PathPrefix;
+    ///
+    /// let base = PathBuf::from("/var/data");
+    ///
+    /// assert_eq!(Path::new("/album/file.txt").prefix_within(&base).unwrap(), PathBuf::from("/var/data/album/file.txt"));
+    /// assert!(Path::new("/album/../../etc").prefix_within(&base).is_err());
+    /// ```
+    fn prefix_within<P: AsRef<Path>>(&self, base: P) -> Result<PathBuf, std::io::Error>;
 }
 
 impl PathPrefix for Path {
@@ -75,6 +93,24 @@ impl PathPrefix for Path {
 
         result
     }
+
+    fn prefix_within<P: AsRef<Path>>(&self, base: P) -> Result<PathBuf, std::io::Error> {
+        use std::path::Component;
+
+        let mut depth = 0usize;
+        for component in self.components() {
+            match component {
+                Component::Normal(_) => depth += 1,
+                Component::ParentDir if depth > 0 => depth -= 1,
+                Component::ParentDir => {
+                    return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("{} escapes {}", self.display(), base.as_ref().display())));
+                }
+                Component::RootDir | Component::Prefix(_) | Component::CurDir => continue,
+            }
+        }
+
+        Ok(self.prefix(base))
+    }
 }
 
 impl PathPrefix for PathBuf {
@@ -96,4 +132,8 @@ impl PathPrefix for PathBuf {
     fn prefix<P: AsRef<Path>>(&self, base: P) -> PathBuf {
         self.as_path().prefix(base)
     }
+
+    fn prefix_within<P: AsRef<Path>>(&self, base: P) -> Result<PathBuf, std::io::Error> {
+        self.as_path().prefix_within(base)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,53 @@
+use chrono::TimeDelta;
+
+/// Parses a human-readable time offset such as `+1h 3m 12s`, `-30s` or `2d 4h` into a `TimeDelta`.
+///
+/// The offset is an optional sign (`+` or `-`, applying to the whole offset) followed by one or more
+/// `<number><unit>` components, where the unit is one of `d`, `h`, `m` or `s`. Whitespace between
+/// components is optional.
+///
+/// # Example
+/// ```
+/// let shift = parse_time_shift("+1h 3m 12s")?;
+/// assert_eq!(shift, TimeDelta::seconds(3792));
+/// ```
+///
+/// # Errors
+/// Returns an `anyhow::Error` if the offset is empty, has an unknown unit, or overflows.
+pub fn parse_time_shift(input: &str) -> anyhow::Result<TimeDelta> {
+    let input = input.trim();
+    let (sign, rest) = match input.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, input.strip_prefix('+').unwrap_or(input)),
+    };
+
+    let mut total: i64 = 0;
+    let mut number = String::new();
+    let mut components = 0;
+
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'd' | 'h' | 'm' | 's' => {
+                let value: i64 = number.parse().map_err(|_| anyhow::anyhow!("Expected a number before '{}' in \"{}\"", c, input))?;
+                let unit = match c {
+                    'd' => 86400,
+                    'h' => 3600,
+                    'm' => 60,
+                    _ => 1,
+                };
+                total = value.checked_mul(unit).and_then(|seconds| total.checked_add(seconds)).ok_or_else(|| anyhow::anyhow!("Time shift \"{}\" is too large", input))?;
+                number.clear();
+                components += 1;
+            }
+            c if c.is_whitespace() && number.is_empty() => continue,
+            _ => return Err(anyhow::anyhow!("Invalid time shift \"{}\": use e.g. \"+1h 3m 12s\"", input)),
+        }
+    }
+
+    if components == 0 || !number.is_empty() {
+        return Err(anyhow::anyhow!("Invalid time shift \"{}\": use e.g. \"+1h 3m 12s\"", input));
+    }
+
+    TimeDelta::try_seconds(sign * total).ok_or_else(|| anyhow::anyhow!("Time shift \"{}\" is too large", input))
+}
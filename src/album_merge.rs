@@ -0,0 +1,165 @@
+use crate::_utils::path_prefix::PathPrefix;
+use crate::db::operations::album::{rename_album, set_album_trashed};
+use crate::db::operations::join_album_album::{add_album_to_album, get_album_links, remove_album_link};
+use crate::db::operations::join_album_photo::{add_photo_to_album, get_album_photo_ids, remove_photo_links};
+use crate::db::operations::paths::{get_album_path, get_photo_path};
+use crate::db::operations::photo::{file_name_exists, get_photo, set_photo_file_name};
+use crate::db::operations::query::get_albums_in_album;
+use crate::fs_operations::album::{move_album_fs, trash_album_fs};
+use crate::fs_operations::journal::FsJournal;
+use crate::fs_operations::photo::move_photo_as_fs;
+use crate::models::album::Album;
+use crate::models::photo::Photo;
+use chrono::Local;
+use diesel::MysqlConnection;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Longest file name the `photos` table can hold
+const MAX_FILE_NAME_LENGTH: usize = 64;
+
+/// Totals of an album merge
+///
+/// # Fields
+/// * `photos`: Number of photos moved into the target album
+/// * `albums`: Number of subalbums moved into the target album
+/// * `renamed_photos`: New file names of photos renamed to avoid a collision, by photo ID
+/// * `renamed_albums`: New names of subalbums renamed to avoid a collision, by album ID
+#[derive(Serialize, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeSummary {
+    pub photos: usize,
+    pub albums: usize,
+    pub renamed_photos: BTreeMap<i64, String>,
+    pub renamed_albums: BTreeMap<i32, String>,
+}
+
+/// Moves all photos and subalbums of `source` into `target`, then moves the empty `source` to the trash.
+///
+/// Photos whose base name is already used in the target directory are renamed to `<base>_2`, `<base>_3`, ...,
+/// together with their associated files and the other photos sharing the base name. Subalbums whose name is taken become `<name> (2)`, `<name> (3)`, ...
+/// Trashed photos and subalbums of `source` are re-linked to `target`, so they are restored there.
+/// Anything else left in the source directory is trashed with it.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `journal` - Journal of the current unit of work
+/// * `source` - The album to merge, which must not contain `target`
+/// * `target` - The album to merge into
+///
+/// # Returns
+/// What was moved and renamed, or an error if something failed
+pub fn merge_albums(conn: &mut MysqlConnection, journal: &mut FsJournal, source: &Album, target: &Album) -> anyhow::Result<MergeSummary> {
+    let storage_root = PathBuf::from(std::env::var("STORAGE_ROOT").unwrap());
+    let source_path = get_album_path(conn, source.id)?;
+    let target_path = get_album_path(conn, target.id)?;
+    let mut summary = MergeSummary::default();
+
+    // Names already used in the target directory
+    let mut taken_names: HashSet<String> = HashSet::new();
+    let mut taken_bases: HashSet<String> = HashSet::new();
+    for entry in fs::read_dir(target_path.prefix_within(&storage_root)?)?.flatten() {
+        let path = entry.path();
+        taken_names.insert(path.file_name().unwrap_or_default().to_string_lossy().into_owned());
+        if entry.file_type().is_ok_and(|file_type| file_type.is_file()) {
+            taken_bases.insert(path.file_prefix().unwrap_or_default().to_string_lossy().into_owned());
+        }
+    }
+
+    // Group the photos stored in the source directory by base name: a RAW+JPEG pair shares its
+    // associated files, so each group is moved (and renamed) as one
+    let photo_ids = get_album_photo_ids(conn, source.id)?;
+    let mut groups: BTreeMap<String, Vec<(Photo, PathBuf)>> = BTreeMap::new();
+    for photo in get_photo(conn, &photo_ids)? {
+        let photo_path = get_photo_path(conn, photo.id)?;
+        if photo_path.parent() != Some(source_path.as_path()) {
+            // Also linked to another album, which holds the file
+            continue;
+        }
+
+        let base = Path::new(&photo.file_name).file_prefix().unwrap_or_default().to_string_lossy().into_owned();
+        groups.entry(base).or_default().push((photo, photo_path));
+    }
+
+    // Move the groups, renaming them where needed
+    for (base, group) in groups {
+        let mut new_base = base.clone();
+        let mut counter = 2;
+        loop {
+            let mut taken = taken_bases.contains(&new_base);
+            if !taken && new_base != base {
+                for (photo, _) in &group {
+                    if file_name_exists(conn, &format!("{new_base}{}", &photo.file_name[base.len()..]))? {
+                        taken = true;
+                        break;
+                    }
+                }
+            }
+            if !taken {
+                break;
+            }
+            new_base = format!("{base}_{counter}");
+            counter += 1;
+        }
+
+        let renames: Vec<(i64, String)> = group
+            .iter()
+            .map(|(photo, _)| (photo.id, format!("{new_base}{}", &photo.file_name[base.len()..])))
+            .collect();
+        if let Some((_, new_file_name)) = renames.iter().find(|(_, new_file_name)| new_file_name.len() > MAX_FILE_NAME_LENGTH) {
+            return Err(anyhow::anyhow!("Cannot rename {base} to {new_file_name}: file names are limited to {MAX_FILE_NAME_LENGTH} bytes"));
+        }
+
+        // Moving one photo of the group takes the others along
+        move_photo_as_fs(journal, &group[0].1, &target_path, &new_base)?;
+        if new_base != base {
+            for (photo_id, new_file_name) in renames {
+                set_photo_file_name(conn, photo_id, &new_file_name)?;
+                summary.renamed_photos.insert(photo_id, new_file_name);
+            }
+        }
+        taken_bases.insert(new_base);
+        summary.photos += group.len();
+    }
+
+    // Re-point the photo links, keeping the source order
+    let target_photo_ids = get_album_photo_ids(conn, target.id)?;
+    remove_photo_links(conn, source.id, &photo_ids)?;
+    let new_photo_ids: Vec<i64> = photo_ids.into_iter().filter(|photo_id| !target_photo_ids.contains(photo_id)).collect();
+    add_photo_to_album(conn, target.id, &new_photo_ids)?;
+
+    // Move the subalbum directories, renaming them where needed
+    for child in get_albums_in_album(conn, source.id)? {
+        let mut album_name = child.album_name.clone();
+        let mut counter = 2;
+        while taken_names.contains(&album_name) {
+            album_name = format!("{} ({counter})", child.album_name);
+            counter += 1;
+        }
+
+        move_album_fs(journal, &source_path.join(&child.album_name), &target_path.join(&album_name))?;
+        if album_name != child.album_name {
+            rename_album(conn, child.id, &album_name)?;
+            summary.renamed_albums.insert(child.id, album_name.clone());
+        }
+        taken_names.insert(album_name);
+        summary.albums += 1;
+    }
+
+    // Re-point the album links, including those of trashed subalbums
+    let links = get_album_links(conn)?;
+    for link in links.iter().filter(|link| link.parent_id == source.id) {
+        remove_album_link(conn, source.id, link.album_id)?;
+        if !links.iter().any(|other| other.parent_id == target.id && other.album_id == link.album_id) {
+            add_album_to_album(conn, target.id, &[link.album_id])?;
+        }
+    }
+
+    // The source is empty now, so trash it like `DELETE /album/<id>/delete` would
+    trash_album_fs(journal, source, &source_path, &[], &[])?;
+    set_album_trashed(conn, source.id, Some(Local::now().naive_local()))?;
+
+    Ok(summary)
+}
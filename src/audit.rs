@@ -0,0 +1,196 @@
+use crate::_utils::actor::Actor;
+use crate::_utils::path_prefix::PathPrefix;
+use crate::db::operations::album::{get_album, rename_album};
+use crate::db::operations::audit::{add_audit_entry, get_audit_snapshot};
+use crate::db::operations::join_album_album::{add_album_to_album, remove_album_from_album};
+use crate::db::operations::join_album_photo::{add_photo_to_album, remove_photo_from_album};
+use crate::fs_operations::album::move_album_fs;
+use crate::fs_operations::journal::FsJournal;
+use crate::fs_operations::photo::move_photo_fs;
+use crate::models::audit::{AuditAction, AuditResult, AuditSnapshot, NewAuditEntry};
+use chrono::Local;
+use diesel::result::Error;
+use diesel::MysqlConnection;
+use std::env;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+/// Operations that can be reversed with `undo_changes`
+pub const UNDOABLE_ACTIONS: [AuditAction; 5] = [
+    AuditAction::PhotoUnfile,
+    AuditAction::PhotoReassign,
+    AuditAction::AlbumUnfile,
+    AuditAction::AlbumReassign,
+    AuditAction::AlbumRename,
+];
+
+/// Takes a snapshot of where the given photos and albums are, to record with an operation.
+///
+/// Auditing never gets in the way of the operation itself, so errors are printed and yield `None`.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `photo_ids` - Photos to include
+/// * `album_ids` - Albums to include
+pub fn take_snapshot(conn: &mut MysqlConnection, photo_ids: &[i64], album_ids: &[i32]) -> Option<AuditSnapshot> {
+    get_audit_snapshot(conn, photo_ids, album_ids)
+        .inspect_err(|e| println!("Error taking audit snapshot: {e}"))
+        .ok()
+}
+
+/// Appends a finished operation to the audit log.
+///
+/// Call this after the operation has been committed (or rolled back). If it succeeded, the "after"
+/// snapshot is taken here. Failing to write the entry does not fail the operation; the error is printed.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `actor` - Who performed the operation
+/// * `action` - Kind of operation
+/// * `photo_ids` - Affected photos
+/// * `album_ids` - Affected albums
+/// * `before` - Snapshot taken with `take_snapshot` before the operation ran, if any
+/// * `result` - Outcome of the operation
+///
+/// # Returns
+/// The ID of the log entry, which identifies the operation, or `None` if it could not be written
+pub fn record_operation<T, E: Display>(
+    conn: &mut MysqlConnection,
+    actor: &Actor,
+    action: AuditAction,
+    photo_ids: &[i64],
+    album_ids: &[i32],
+    before: Option<AuditSnapshot>,
+    result: &Result<T, E>,
+) -> Option<i64> {
+    let (after, audit_result, error) = match result {
+        Ok(_) => (take_snapshot(conn, photo_ids, album_ids), AuditResult::Success, None),
+        Err(e) => (None, AuditResult::Failure, Some(e.to_string())),
+    };
+
+    let entry = NewAuditEntry {
+        created_at: Local::now().naive_local(),
+        actor: actor.0.clone(),
+        action: action.as_str().to_string(),
+        photo_ids: serde_json::to_string(photo_ids).unwrap_or_else(|_| "[]".into()),
+        album_ids: serde_json::to_string(album_ids).unwrap_or_else(|_| "[]".into()),
+        before_state: before.and_then(|snapshot| serde_json::to_string(&snapshot).ok()),
+        after_state: after.and_then(|snapshot| serde_json::to_string(&snapshot).ok()),
+        result: audit_result.as_str().to_string(),
+        error,
+    };
+
+    add_audit_entry(conn, entry)
+        .inspect_err(|e| println!("Error writing audit log entry for {}: {e}", action.as_str()))
+        .ok()
+}
+
+/// Finds everything that changed since an operation ran and would be clobbered by undoing it.
+///
+/// Every photo and album the operation changed must still be where the operation left it, the albums
+/// it is moved back into must still exist, and its old location must not have been reused since.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `before` - Snapshot taken before the operation
+/// * `after` - Snapshot taken after the operation
+///
+/// # Returns
+/// A description of every conflict, which is empty if the operation can be undone
+pub fn find_undo_conflicts(conn: &mut MysqlConnection, before: &AuditSnapshot, after: &AuditSnapshot) -> Result<Vec<String>, Error> {
+    let storage_root = PathBuf::from(env::var("STORAGE_ROOT").unwrap());
+    let photo_ids: Vec<i64> = before.photos.keys().copied().collect();
+    let album_ids: Vec<i32> = before.albums.keys().copied().collect();
+    let current = get_audit_snapshot(conn, &photo_ids, &album_ids)?;
+    let mut conflicts = Vec::new();
+
+    for (photo_id, old) in &before.photos {
+        let new = after.photos.get(photo_id);
+        if new == Some(old) {
+            continue;
+        }
+        if current.photos.get(photo_id) != new {
+            conflicts.push(format!("Photo {photo_id} was moved, deleted or reassigned since"));
+            continue;
+        }
+        for album_id in &old.album_ids {
+            if get_album(conn, &[*album_id])?.is_empty() {
+                conflicts.push(format!("Album {album_id}, which photo {photo_id} was in, no longer exists"));
+            }
+        }
+        if new.is_some_and(|new| new.path != old.path) && old.path.prefix_within(&storage_root).is_ok_and(|path| path.exists()) {
+            conflicts.push(format!("{} has been reused since", old.path.display()));
+        }
+    }
+
+    for (album_id, old) in &before.albums {
+        let new = after.albums.get(album_id);
+        if new == Some(old) {
+            continue;
+        }
+        if current.albums.get(album_id) != new {
+            conflicts.push(format!("Album {album_id} was moved, renamed or deleted since"));
+            continue;
+        }
+        if let Some(parent_id) = old.parent_id && get_album(conn, &[parent_id])?.is_empty() {
+            conflicts.push(format!("Album {parent_id}, which album {album_id} was in, no longer exists"));
+        }
+        if new.is_some_and(|new| new.path != old.path) && old.path.prefix_within(&storage_root).is_ok_and(|path| path.exists()) {
+            conflicts.push(format!("{} has been reused since", old.path.display()));
+        }
+    }
+
+    Ok(conflicts)
+}
+
+/// Puts every photo and album an operation changed back where it was before, on disk and in the
+/// album relations. Check `find_undo_conflicts` first; this overwrites album links without asking.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `journal` - Journal of the current unit of work
+/// * `before` - Snapshot taken before the operation
+/// * `after` - Snapshot taken after the operation
+///
+/// # Returns
+/// Ok if everything was put back, or an error if something failed
+pub fn undo_changes(conn: &mut MysqlConnection, journal: &mut FsJournal, before: &AuditSnapshot, after: &AuditSnapshot) -> anyhow::Result<()> {
+    for (album_id, old) in &before.albums {
+        let Some(new) = after.albums.get(album_id) else { continue };
+        if new == old {
+            continue;
+        }
+
+        if new.path != old.path {
+            move_album_fs(journal, &new.path, &old.path)?;
+        }
+        if new.album_name != old.album_name {
+            rename_album(conn, *album_id, &old.album_name)?;
+        }
+        if new.parent_id != old.parent_id {
+            remove_album_from_album(conn, &[*album_id])?;
+            if let Some(parent_id) = old.parent_id {
+                add_album_to_album(conn, parent_id, &[*album_id])?;
+            }
+        }
+    }
+
+    for (photo_id, old) in &before.photos {
+        let Some(new) = after.photos.get(photo_id) else { continue };
+        if new == old {
+            continue;
+        }
+
+        if new.path != old.path {
+            move_photo_fs(journal, &new.path, old.path.parent().unwrap_or(Path::new("")))?;
+        }
+        if new.album_ids != old.album_ids {
+            remove_photo_from_album(conn, &[*photo_id])?;
+            for album_id in &old.album_ids {
+                add_photo_to_album(conn, *album_id, &[*photo_id])?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,5 +1,12 @@
 use crate::endpoints::main::start_webserver;
 use crate::ingest::main::ingest;
+use crate::maintenance::backfill_metadata::backfill_metadata;
+use crate::maintenance::duplicates::backfill_phash;
+use crate::maintenance::purge_trash::purge;
+use crate::maintenance::rescan::rescan;
+use crate::maintenance::stacks::{stack, DEFAULT_STACK_GAP_SECONDS};
+use crate::maintenance::verify::verify;
+use crate::maintenance::fsck::{fsck, CycleStrategy, FsckOptions, MisplacedStrategy, MissingStrategy, OrphanStrategy, ThumbnailStrategy};
 use clap::{Parser, Subcommand};
 use rocket::tokio;
 
@@ -24,7 +31,54 @@ enum Commands {
         dry: bool,
         #[arg(long, help = "Move instead of move files to their new destination (default behavior is copy)")]
         no_preserve: bool,
-    }
+    },
+    #[command(about = "Store the full EXIF/XMP metadata of photos ingested without it")]
+    BackfillMetadata {
+        #[arg(long, help = "Run in dry mode (no actual changes to DB)")]
+        dry: bool,
+    },
+    #[command(about = "Compute the perceptual hashes used for duplicate detection for photos ingested without one")]
+    BackfillPhash {
+        #[arg(long, help = "Run in dry mode (no actual changes to DB)")]
+        dry: bool,
+    },
+    #[command(about = "Group bursts and exposure brackets among photos that are not stacked yet")]
+    Stack {
+        #[arg(long, default_value_t = DEFAULT_STACK_GAP_SECONDS, help = "Largest gap between two frames of a burst, in seconds")]
+        gap: i64,
+        #[arg(long, help = "Run in dry mode (no actual changes to DB)")]
+        dry: bool,
+    },
+    #[command(about = "Check the files on disk against the database, and optionally repair inconsistencies")]
+    Fsck {
+        #[arg(long, help = "Repair problems using the strategies below (default behavior is to only report them)")]
+        repair: bool,
+        #[arg(long, value_enum, default_value_t = MissingStrategy::Report, help = "Photos whose file is missing")]
+        missing: MissingStrategy,
+        #[arg(long, value_enum, default_value_t = OrphanStrategy::Quarantine, help = "Files that don't belong to any photo")]
+        orphans: OrphanStrategy,
+        #[arg(long, value_enum, default_value_t = MisplacedStrategy::MoveFile, help = "Photos found in a different directory than their album")]
+        misplaced: MisplacedStrategy,
+        #[arg(long, value_enum, default_value_t = ThumbnailStrategy::Regenerate, help = "Missing and unreferenced thumbnails")]
+        thumbnails: ThumbnailStrategy,
+        #[arg(long, value_enum, default_value_t = CycleStrategy::Detach, help = "Cycles in the album relation table")]
+        cycles: CycleStrategy,
+    },
+    #[command(about = "Re-hash originals and compare them with their stored hashes, least recently verified first")]
+    Verify {
+        #[arg(long, default_value_t = 10.0, help = "Stop after reading this many gigabytes")]
+        gb: f64,
+    },
+    #[command(about = "Adopt images that were copied into $STORAGE_ROOT directly, creating albums for their directories")]
+    Rescan {
+        #[arg(long, help = "Run in dry mode (no actual changes to DB)")]
+        dry: bool,
+    },
+    #[command(about = "Permanently delete photos and albums that have been in the trash for longer than $TRASH_RETENTION_DAYS")]
+    PurgeTrash {
+        #[arg(long, help = "Empty the whole trash, regardless of when things were deleted")]
+        all: bool,
+    },
 }
 
 pub async fn run_cli() {
@@ -41,5 +95,14 @@ pub async fn run_cli() {
             let _ = web_handle.await;
         }
         Commands::Ingest { source, dry, no_preserve } => ingest(source, dry, no_preserve),
+        Commands::BackfillMetadata { dry } => backfill_metadata(dry),
+        Commands::BackfillPhash { dry } => backfill_phash(dry),
+        Commands::Stack { gap, dry } => stack(gap, dry),
+        Commands::Fsck { repair, missing, orphans, misplaced, thumbnails, cycles } => {
+            fsck(FsckOptions { repair, missing, orphans, misplaced, thumbnails, cycles })
+        }
+        Commands::Verify { gb } => verify(gb),
+        Commands::Rescan { dry } => rescan(dry),
+        Commands::PurgeTrash { all } => purge(all),
     }
 }
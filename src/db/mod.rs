@@ -1,2 +1,4 @@
 pub mod schema;
-pub mod operations;
\ No newline at end of file
+pub mod operations;
+pub mod pool;
+pub mod unit_of_work;
\ No newline at end of file
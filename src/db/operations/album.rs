@@ -1,12 +1,16 @@
+use crate::db::operations::join_album_album::add_album_to_album;
 use crate::db::schema::album_album_join;
 use crate::db::schema::album_photo_join;
 use crate::db::schema::albums::dsl as albums_dsl;
 use crate::db::schema::albums::dsl::albums;
-use crate::models::album::{Album, NewAlbum};
+use crate::db::schema::photos;
+use crate::models::album::{Album, AlbumChanges, AlbumDetails, NewAlbum};
+use chrono::{Local, NaiveDateTime};
 use diesel::associations::HasTable;
 use diesel::insert_into;
 use diesel::prelude::*;
 use diesel::result::Error;
+use std::collections::HashMap;
 
 /// Creates a new album in the database
 ///
@@ -15,11 +19,14 @@ use diesel::result::Error;
 /// * `album` - Album details for creation
 ///
 /// # Returns
-/// The number of rows affected (1 if successful, 0 if failed).
-pub fn create_album(conn: &mut MysqlConnection, album: NewAlbum) -> Result<usize, Error> {
+/// Ok(id) with the ID of the new album, or an error if the insert fails
+pub fn create_album(conn: &mut MysqlConnection, album: NewAlbum) -> Result<i32, Error> {
     insert_into(albums)
         .values(&album)
-        .execute(conn)
+        .execute(conn)?;
+
+    diesel::select(diesel::dsl::sql::<diesel::sql_types::Integer>("LAST_INSERT_ID()"))
+        .get_result(conn)
 }
 
 /// Gets all albums from the database with their associated photos
@@ -28,7 +35,7 @@ pub fn create_album(conn: &mut MysqlConnection, album: NewAlbum) -> Result<usize
 /// * `conn` - Database connection
 ///
 /// # Returns
-/// All albums found in the database, or an error if query fails
+/// All root albums in their sort order, or an error if query fails
 pub fn get_root_albums(conn: &mut MysqlConnection) -> Result<Vec<Album>, Error> {
     albums::table()
         // LEFT JOIN album_album_join ON album_album_join.album_id = albums.id
@@ -37,26 +44,87 @@ pub fn get_root_albums(conn: &mut MysqlConnection) -> Result<Vec<Album>, Error>
         )
         // keep only rows where no parent entry exists → root albums
         .filter(album_album_join::parent_id.is_null())
+        .filter(albums_dsl::deleted_at.is_null())
+        // albums with a position first, the rest by name
+        .order((albums_dsl::sort_position.is_null().asc(), albums_dsl::sort_position.asc(), albums_dsl::album_name.asc()))
         // select only album columns
         .select(Album::as_select())
         .load(conn)
 }
 
-/// Updates an existing album's name in the database based on the provided album's `id`
+/// Updates an existing album's name in the database
 ///
 /// # Arguments
 /// * `conn` - Database connection
-/// * `album` - Album with updated fields and ID of record to update
+/// * `album_id` - ID of the album to rename
+/// * `album_name` - New name of the album
 ///
 /// # Returns
 /// The number of rows affected (1 if successful, 0 if failed).
-pub fn rename_album(conn: &mut MysqlConnection, album: Album) -> Result<usize, Error> {
-    diesel::update(albums.find(album.id))
-        .set(albums_dsl::album_name.eq(album.album_name))
+pub fn rename_album(conn: &mut MysqlConnection, album_id: i32, album_name: &str) -> Result<usize, Error> {
+    diesel::update(albums.find(album_id))
+        .set((albums_dsl::album_name.eq(album_name), albums_dsl::updated_at.eq(Local::now().naive_local())))
         .execute(conn)
 }
 
-/// Gets albums by their IDs
+/// Updates the description, cover and/or sort position of an album
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `album_id` - ID of the album to update
+/// * `changes` - The fields to change
+///
+/// # Returns
+/// The number of rows affected (1 if successful, 0 if the album doesn't exist)
+pub fn update_album_metadata(conn: &mut MysqlConnection, album_id: i32, changes: &AlbumChanges) -> Result<usize, Error> {
+    diesel::update(albums.find(album_id))
+        .set(changes)
+        .execute(conn)
+}
+
+/// Adds what is derived from their photos (effective cover and date range) to albums
+///
+/// Only photos directly in an album count, trashed ones are left out. The effective cover is the chosen
+/// cover while it is one of these photos, and the earliest of them otherwise.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `album_list` - Albums to add the details to
+///
+/// # Returns
+/// The albums with their details, in the same order
+pub fn get_album_details(conn: &mut MysqlConnection, album_list: Vec<Album>) -> Result<Vec<AlbumDetails>, Error> {
+    let album_ids: Vec<i32> = album_list.iter().map(|album| album.id).collect();
+    let album_photos = album_photo_join::table
+        .inner_join(photos::table.on(photos::id.eq(album_photo_join::photo_id)))
+        .filter(album_photo_join::parent_id.eq_any(&album_ids))
+        .filter(photos::deleted_at.is_null())
+        .order((photos::photo_date.asc(), photos::id.asc()))
+        .select((album_photo_join::parent_id, photos::id, photos::photo_date))
+        .load::<(i32, i64, NaiveDateTime)>(conn)?;
+
+    // Group the photos by album, keeping them in date order
+    let mut dated_by_album: HashMap<i32, Vec<(i64, NaiveDateTime)>> = HashMap::new();
+    for (album_id, photo_id, photo_date) in album_photos {
+        dated_by_album.entry(album_id).or_default().push((photo_id, photo_date));
+    }
+
+    Ok(album_list.into_iter().map(|album| {
+        let dated = dated_by_album.remove(&album.id).unwrap_or_default();
+        let effective_cover_id = album.cover_id
+            .filter(|cover_id| dated.iter().any(|(photo_id, _)| photo_id == cover_id))
+            .or(dated.first().map(|(photo_id, _)| *photo_id));
+
+        AlbumDetails {
+            effective_cover_id,
+            first_photo_date: dated.first().map(|(_, photo_date)| *photo_date),
+            last_photo_date: dated.last().map(|(_, photo_date)| *photo_date),
+            album,
+        }
+    }).collect())
+}
+
+/// Gets albums by their IDs. Trashed albums are skipped.
 ///
 /// # Arguments
 /// * `conn` - Database connection
@@ -67,6 +135,7 @@ pub fn rename_album(conn: &mut MysqlConnection, album: Album) -> Result<usize, E
 pub fn get_album(conn: &mut MysqlConnection, album_ids: &[i32]) -> Result<Vec<Album>, Error> {
     albums
         .filter(albums_dsl::id.eq_any(album_ids))
+        .filter(albums_dsl::deleted_at.is_null())
         .load::<Album>(conn)
 }
 
@@ -79,16 +148,18 @@ pub fn get_album(conn: &mut MysqlConnection, album_ids: &[i32]) -> Result<Vec<Al
 /// # Returns
 /// The album containing the photo, or an error if not found
 ///
-/// IMPORTANT: If the photo is unfiled, this function will return a `NotFound` error
+/// IMPORTANT: If the photo is unfiled (or only linked to trashed albums), this function will return a `NotFound` error
 pub fn get_album_by_photo(conn: &mut MysqlConnection, photo_id: i64) -> Result<Album, Error> {
     albums
         .inner_join(album_photo_join::table.on(album_photo_join::parent_id.eq(albums_dsl::id)))
         .filter(album_photo_join::photo_id.eq(photo_id))
+        .filter(albums_dsl::deleted_at.is_null())
+        .order(albums_dsl::id.asc())
         .select(Album::as_select())
         .first(conn)
 }
 
-/// Deletes the specified album from the database
+/// Permanently deletes the specified album from the database (see `set_album_trashed` for soft deletion)
 ///
 /// # Arguments
 /// * `conn` - Database connection
@@ -104,4 +175,109 @@ pub fn delete_album(conn: &mut MysqlConnection, album_id: i32) -> Result<Album,
     diesel::delete(albums.find(album_id)).execute(conn)?;
 
     Ok(album)
-}
\ No newline at end of file
+}
+
+/// Gets every album in the database, regardless of its position in the album tree, including trashed ones
+///
+/// # Arguments
+/// * `conn` - Database connection
+///
+/// # Returns
+/// All albums, or an error if the query fails
+pub fn get_all_albums(conn: &mut MysqlConnection) -> Result<Vec<Album>, Error> {
+    albums.load::<Album>(conn)
+}
+
+/// Looks up an album by name among the children of a parent album (or among the root albums).
+/// Trashed albums are skipped.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `parent_id` - ID of the parent album, or `None` for the root
+/// * `album_name` - Name to look for
+///
+/// # Returns
+/// The ID of the album if one exists with that name, otherwise `None`
+pub fn find_child_album(conn: &mut MysqlConnection, parent_id: Option<i32>, album_name: &str) -> Result<Option<i32>, Error> {
+    match parent_id {
+        None => albums
+            .left_outer_join(album_album_join::table.on(album_album_join::album_id.eq(albums_dsl::id)))
+            .filter(album_album_join::parent_id.is_null())
+            .filter(albums_dsl::deleted_at.is_null())
+            .filter(albums_dsl::album_name.eq(album_name))
+            .select(albums_dsl::id)
+            .first::<i32>(conn)
+            .optional(),
+        Some(parent_id) => albums
+            .inner_join(album_album_join::table.on(album_album_join::album_id.eq(albums_dsl::id)))
+            .filter(album_album_join::parent_id.eq(parent_id))
+            .filter(albums_dsl::deleted_at.is_null())
+            .filter(albums_dsl::album_name.eq(album_name))
+            .select(albums_dsl::id)
+            .first::<i32>(conn)
+            .optional(),
+    }
+}
+
+/// Resolves a directory path to an album, creating any albums along the path that don't exist yet.
+///
+/// Each level is looked up by name among the children of the previous level (or among the root albums
+/// for the first level), so the album tree ends up mirroring the directory tree.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `segments` - Album names from root to leaf (e.g. `["2024", "Kyoto"]`)
+///
+/// # Returns
+/// The ID of the leaf album, or `NotFound` if `segments` is empty
+pub fn find_or_create_album_path<S: AsRef<str>>(conn: &mut MysqlConnection, segments: &[S]) -> Result<i32, Error> {
+    let mut parent_id: Option<i32> = None;
+
+    for segment in segments {
+        let album_name = segment.as_ref();
+
+        let album_id = match find_child_album(conn, parent_id, album_name)? {
+            Some(album_id) => album_id,
+            None => {
+                let now = Local::now().naive_local();
+                let album_id = create_album(conn, NewAlbum { album_name: album_name.to_string(), created_at: now, updated_at: now })?;
+                if let Some(parent_id) = parent_id {
+                    add_album_to_album(conn, parent_id, &[album_id])?;
+                }
+                album_id
+            }
+        };
+        parent_id = Some(album_id);
+    }
+
+    parent_id.ok_or(Error::NotFound)
+}
+
+/// Moves an album to the trash (or restores it) by setting its `deleted_at` marker
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `album_id` - ID of the album to update
+/// * `trashed_at` - When the album was trashed, or `None` to restore it
+///
+/// # Returns
+/// The number of rows affected (1 if successful, 0 if the album doesn't exist)
+pub fn set_album_trashed(conn: &mut MysqlConnection, album_id: i32, trashed_at: Option<NaiveDateTime>) -> Result<usize, Error> {
+    diesel::update(albums.find(album_id))
+        .set(albums_dsl::deleted_at.eq(trashed_at))
+        .execute(conn)
+}
+
+/// Gets all albums in the trash
+///
+/// # Arguments
+/// * `conn` - Database connection
+///
+/// # Returns
+/// Trashed albums, most recently trashed first
+pub fn get_trashed_albums(conn: &mut MysqlConnection) -> Result<Vec<Album>, Error> {
+    albums
+        .filter(albums_dsl::deleted_at.is_not_null())
+        .order(albums_dsl::deleted_at.desc())
+        .load::<Album>(conn)
+}
@@ -0,0 +1,153 @@
+use crate::db::operations::join_album_album::get_parent_album_id;
+use crate::db::operations::paths::{get_album_path, get_photo_path};
+use crate::db::schema::{album_photo_join, albums, audit_log, photos};
+use crate::models::audit::{AlbumLocation, AuditEntry, AuditFilter, AuditSnapshot, NewAuditEntry, PhotoLocation};
+use diesel::dsl::sql;
+use diesel::mysql::Mysql;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::sql_types::{BigInt, Bool, Text};
+use diesel::MysqlConnection;
+
+/// Appends an entry to the audit log
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `entry` - The entry to append
+///
+/// # Returns
+/// The ID of the new entry, or an error if the insert fails
+pub fn add_audit_entry(conn: &mut MysqlConnection, entry: NewAuditEntry) -> Result<i64, Error> {
+    diesel::insert_into(audit_log::table)
+        .values(&entry)
+        .execute(conn)?;
+
+    diesel::select(sql::<BigInt>("LAST_INSERT_ID()"))
+        .get_result(conn)
+}
+
+/// Gets a single entry of the audit log
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `entry_id` - ID of the entry
+///
+/// # Returns
+/// The entry, or `NotFound` if it doesn't exist
+pub fn get_audit_entry(conn: &mut MysqlConnection, entry_id: i64) -> Result<AuditEntry, Error> {
+    audit_log::table
+        .find(entry_id)
+        .select(AuditEntry::as_select())
+        .first(conn)
+}
+
+/// Marks an operation in the audit log as undone
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `entry_id` - ID of the entry of the undone operation
+/// * `undo_id` - ID of the entry of the undo
+///
+/// # Returns
+/// The number of rows affected (1 if successful, 0 if the entry doesn't exist)
+pub fn set_audit_undone_by(conn: &mut MysqlConnection, entry_id: i64, undo_id: i64) -> Result<usize, Error> {
+    diesel::update(audit_log::table.find(entry_id))
+        .set(audit_log::undone_by.eq(undo_id))
+        .execute(conn)
+}
+
+/// Builds a query for the audit log entries matching all filters set in `filter`, ignoring pagination
+fn filtered_audit_entries(filter: &AuditFilter) -> audit_log::BoxedQuery<'_, Mysql> {
+    let mut query = audit_log::table.into_boxed();
+
+    if let Some(actor) = &filter.actor {
+        query = query.filter(audit_log::actor.eq(actor));
+    }
+    if let Some(action) = filter.action {
+        query = query.filter(audit_log::action.eq(action.as_str()));
+    }
+    if let Some(result) = filter.result {
+        query = query.filter(audit_log::result.eq(result.as_str()));
+    }
+    if let Some(photo_id) = filter.photo_id {
+        // The candidate is bound as JSON text, as MariaDB has no `CAST(... AS JSON)`
+        query = query.filter(sql::<Bool>("JSON_CONTAINS(photo_ids, ").bind::<Text, _>(photo_id.to_string()).sql(")"));
+    }
+    if let Some(album_id) = filter.album_id {
+        query = query.filter(sql::<Bool>("JSON_CONTAINS(album_ids, ").bind::<Text, _>(album_id.to_string()).sql(")"));
+    }
+    if let Some(since) = filter.since {
+        query = query.filter(audit_log::created_at.ge(since.0));
+    }
+    if let Some(until) = filter.until {
+        query = query.filter(audit_log::created_at.le(until.0));
+    }
+
+    query
+}
+
+/// Gets one page of the audit log, newest entries first
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `filter` - Filters to apply
+/// * `offset` - Number of matching entries to skip
+/// * `limit` - Maximum number of entries to return
+///
+/// # Returns
+/// The entries of the page and the total number of matching entries, or an error if the query fails
+pub fn get_audit_entries(conn: &mut MysqlConnection, filter: &AuditFilter, offset: i64, limit: i64) -> Result<(Vec<AuditEntry>, i64), Error> {
+    let total = filtered_audit_entries(filter)
+        .count()
+        .get_result::<i64>(conn)?;
+
+    let entries = filtered_audit_entries(filter)
+        .order(audit_log::id.desc())
+        .offset(offset)
+        .limit(limit)
+        .select(AuditEntry::as_select())
+        .load(conn)?;
+
+    Ok((entries, total))
+}
+
+/// Records where the given photos and albums currently are, for the audit log.
+/// Trashed photos and albums are included; IDs that don't exist are skipped.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `photo_ids` - Photos to include
+/// * `album_ids` - Albums to include
+///
+/// # Returns
+/// The snapshot, or an error if a query fails
+pub fn get_audit_snapshot(conn: &mut MysqlConnection, photo_ids: &[i64], album_ids: &[i32]) -> Result<AuditSnapshot, Error> {
+    let mut snapshot = AuditSnapshot::default();
+
+    let photo_names: Vec<(i64, String)> = photos::table
+        .filter(photos::id.eq_any(photo_ids))
+        .select((photos::id, photos::file_name))
+        .load(conn)?;
+    for (photo_id, file_name) in photo_names {
+        let album_ids = album_photo_join::table
+            .filter(album_photo_join::photo_id.eq(photo_id))
+            .select(album_photo_join::parent_id)
+            .order(album_photo_join::parent_id.asc())
+            .load::<i32>(conn)?;
+
+        let path = get_photo_path(conn, photo_id)?;
+        snapshot.photos.insert(photo_id, PhotoLocation { file_name, path, album_ids });
+    }
+
+    let album_names: Vec<(i32, String)> = albums::table
+        .filter(albums::id.eq_any(album_ids))
+        .select((albums::id, albums::album_name))
+        .load(conn)?;
+    for (album_id, album_name) in album_names {
+        let path = get_album_path(conn, album_id)?;
+        let parent_id = get_parent_album_id(conn, album_id)?;
+        snapshot.albums.insert(album_id, AlbumLocation { album_name, path, parent_id });
+    }
+
+    Ok(snapshot)
+}
@@ -0,0 +1,191 @@
+use crate::db::schema::{collection_photo_join, collections};
+use crate::models::collection::{Collection, NewCollection};
+use crate::models::join::CollectionPhoto;
+use diesel::dsl::max;
+use diesel::insert_into;
+use diesel::mysql::MysqlConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+
+/// Retrieves all collections, sorted by name
+///
+/// # Arguments
+/// * `conn` - Database connection
+///
+/// # Returns
+/// Vec<Collection> of all collections
+pub fn get_all_collections(conn: &mut MysqlConnection) -> Result<Vec<Collection>, Error> {
+    collections::table
+        .order(collections::collection_name.asc())
+        .load::<Collection>(conn)
+}
+
+/// Gets a single collection
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `collection_id` - ID of the collection
+///
+/// # Returns
+/// The collection, or `NotFound` if it doesn't exist
+pub fn get_collection(conn: &mut MysqlConnection, collection_id: i32) -> Result<Collection, Error> {
+    collections::table
+        .find(collection_id)
+        .first::<Collection>(conn)
+}
+
+/// Looks up a collection by its name
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `collection_name` - Name to look for
+///
+/// # Returns
+/// The collection if one exists with that name, otherwise `None`
+pub fn find_collection(conn: &mut MysqlConnection, collection_name: &str) -> Result<Option<Collection>, Error> {
+    collections::table
+        .filter(collections::collection_name.eq(collection_name))
+        .first::<Collection>(conn)
+        .optional()
+}
+
+/// Creates a new, empty collection
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `collection` - Name of the new collection
+///
+/// # Returns
+/// Ok(id) with the ID of the new collection, or an error if the insert fails (e.g. the name is taken)
+pub fn create_collection(conn: &mut MysqlConnection, collection: NewCollection) -> Result<i32, Error> {
+    insert_into(collections::table)
+        .values(&collection)
+        .execute(conn)?;
+
+    diesel::select(diesel::dsl::sql::<diesel::sql_types::Integer>("LAST_INSERT_ID()"))
+        .get_result(conn)
+}
+
+/// Renames a collection
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `collection_id` - ID of the collection to rename
+/// * `collection_name` - New name of the collection
+///
+/// # Returns
+/// The number of rows affected (1 if successful, 0 if the collection doesn't exist)
+pub fn rename_collection(conn: &mut MysqlConnection, collection_id: i32, collection_name: &str) -> Result<usize, Error> {
+    diesel::update(collections::table.find(collection_id))
+        .set(collections::collection_name.eq(collection_name))
+        .execute(conn)
+}
+
+/// Deletes a collection. Its photos are not affected.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `collection_id` - ID of the collection to delete
+///
+/// # Returns
+/// The number of rows affected (1 if successful, 0 if the collection doesn't exist)
+pub fn delete_collection(conn: &mut MysqlConnection, collection_id: i32) -> Result<usize, Error> {
+    // Photo associations are removed by `ON DELETE CASCADE`
+    diesel::delete(collections::table.find(collection_id))
+        .execute(conn)
+}
+
+/// Gets the IDs of all photos in a collection, including trashed ones
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `collection_id` - ID of the collection
+///
+/// # Returns
+/// Vec of photo IDs in collection order
+pub fn get_collection_photo_ids(conn: &mut MysqlConnection, collection_id: i32) -> Result<Vec<i64>, Error> {
+    collection_photo_join::table
+        .filter(collection_photo_join::collection_id.eq(collection_id))
+        .order((collection_photo_join::position.asc(), collection_photo_join::photo_id.asc()))
+        .select(collection_photo_join::photo_id)
+        .load::<i64>(conn)
+}
+
+/// Appends photos to the end of a collection, in the given order.
+/// Photos that are already in the collection keep their place.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `collection_id` - ID of the collection
+/// * `photo_ids` - Photos to add
+///
+/// # Returns
+/// Number of photos added, or an error if a photo doesn't exist
+pub fn add_photo_to_collection(conn: &mut MysqlConnection, collection_id: i32, photo_ids: &[i64]) -> Result<usize, Error> {
+    if photo_ids.is_empty() { return Ok(0); }
+
+    conn.transaction(|conn| {
+        let mut member_ids = get_collection_photo_ids(conn, collection_id)?;
+        let last_position = collection_photo_join::table
+            .filter(collection_photo_join::collection_id.eq(collection_id))
+            .select(max(collection_photo_join::position))
+            .first::<Option<i32>>(conn)?;
+
+        let mut new_photos = Vec::new();
+        let mut position = last_position.map_or(0, |position| position + 1);
+        for &photo_id in photo_ids {
+            if member_ids.contains(&photo_id) {
+                continue;
+            }
+            member_ids.push(photo_id);
+            new_photos.push(CollectionPhoto { collection_id, photo_id, position });
+            position += 1;
+        }
+
+        insert_into(collection_photo_join::table)
+            .values(&new_photos)
+            .execute(conn)
+    })
+}
+
+/// Removes photos from a collection
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `collection_id` - ID of the collection
+/// * `photo_ids` - Photos to remove
+///
+/// # Returns
+/// Number of photos removed
+pub fn remove_photo_from_collection(conn: &mut MysqlConnection, collection_id: i32, photo_ids: &[i64]) -> Result<usize, Error> {
+    if photo_ids.is_empty() { return Ok(0); }
+
+    let filter = collection_photo_join::table
+        .filter(collection_photo_join::collection_id.eq(collection_id))
+        .filter(collection_photo_join::photo_id.eq_any(photo_ids));
+
+    diesel::delete(filter)
+        .execute(conn)
+}
+
+/// Reorders the photos of a collection
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `collection_id` - ID of the collection
+/// * `photo_ids` - All photos of the collection, in their new order
+///
+/// # Returns
+/// Number of photos updated
+pub fn set_collection_order(conn: &mut MysqlConnection, collection_id: i32, photo_ids: &[i64]) -> Result<usize, Error> {
+    conn.transaction(|conn| {
+        let mut rows = 0;
+        for (position, photo_id) in photo_ids.iter().enumerate() {
+            let member = collection_photo_join::table.find((collection_id, photo_id));
+            rows += diesel::update(member)
+                .set(collection_photo_join::position.eq(position as i32))
+                .execute(conn)?;
+        }
+        Ok(rows)
+    })
+}
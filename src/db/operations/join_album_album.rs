@@ -41,4 +41,53 @@ pub fn remove_album_from_album(conn: &mut MysqlConnection, album_ids: &[i32]) ->
 
     diesel::delete(filter)
         .execute(conn)
-}
\ No newline at end of file
+}
+
+/// Gets every album-album association
+///
+/// # Arguments
+/// * `conn` - Database connection
+///
+/// # Returns
+/// All `(parent_id, album_id)` associations
+pub fn get_album_links(conn: &mut MysqlConnection) -> Result<Vec<AlbumAlbum>, Error> {
+    album_album_join::table
+        .select(AlbumAlbum::as_select())
+        .load(conn)
+}
+
+/// Removes a single album-album association, leaving any other parents of the album untouched
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `parent_id` - ID of the parent album
+/// * `album_id` - ID of the child album
+///
+/// # Returns
+/// Number of rows affected (1 if the association existed, 0 otherwise)
+pub fn remove_album_link(conn: &mut MysqlConnection, parent_id: i32, album_id: i32) -> Result<usize, Error> {
+    let filter = album_album_join::table
+        .filter(album_album_join::parent_id.eq(parent_id))
+        .filter(album_album_join::album_id.eq(album_id));
+
+    diesel::delete(filter)
+        .execute(conn)
+}
+
+/// Gets the parent of an album. If the album has several parents, the one with the lowest ID is
+/// returned, matching `get_album_path`.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `album_id` - ID of the child album
+///
+/// # Returns
+/// The ID of the parent album, or `None` for a root album
+pub fn get_parent_album_id(conn: &mut MysqlConnection, album_id: i32) -> Result<Option<i32>, Error> {
+    album_album_join::table
+        .filter(album_album_join::album_id.eq(album_id))
+        .select(album_album_join::parent_id)
+        .order(album_album_join::parent_id.asc())
+        .first::<i32>(conn)
+        .optional()
+}
@@ -1,11 +1,35 @@
-use crate::db::schema::album_photo_join;
+use crate::db::schema::{album_photo_join, photos};
 use crate::models::join::AlbumPhoto;
+use chrono::NaiveDateTime;
+use diesel::dsl::max;
 use diesel::insert_into;
 use diesel::mysql::MysqlConnection;
 use diesel::prelude::*;
 use diesel::result::Error;
+use std::collections::BTreeMap;
+use std::env;
 
-/// Adds photos to an album by creating new album-photo associations
+/// Where photos added to an album are placed in its manual order
+///
+/// # Variants
+/// * `Append`: After all photos already in the album
+/// * `CaptureDate`: Before the first photo of the album taken later, so an album in capture order stays that way
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AlbumInsertOrder {
+    Append,
+    CaptureDate,
+}
+
+/// Gets where new photos are placed in albums, from `$ALBUM_INSERT_ORDER` (`append` or `date`, defaults to `append`)
+pub fn album_insert_order() -> AlbumInsertOrder {
+    match env::var("ALBUM_INSERT_ORDER") {
+        Ok(value) if matches!(value.trim().to_lowercase().as_str(), "date" | "capture_date") => AlbumInsertOrder::CaptureDate,
+        _ => AlbumInsertOrder::Append,
+    }
+}
+
+/// Adds photos to an album by creating new album-photo associations.
+/// The photos are placed in the album's order according to `album_insert_order`.
 ///
 /// # Arguments
 /// * `conn` - Database connection
@@ -17,17 +41,133 @@ use diesel::result::Error;
 pub fn add_photo_to_album(conn: &mut MysqlConnection, album_id: i32, photo_ids: &[i64]) -> Result<usize, Error> {
     if photo_ids.is_empty() { return Ok(0); }
 
-    // Create a Vec<AlbumPhoto> to insert
-    let album_photos = photo_ids.iter().map(|photo_id| AlbumPhoto {
-        parent_id: album_id,
-        photo_id: *photo_id,
-    }).collect::<Vec<AlbumPhoto>>();
+    conn.transaction(|conn| match album_insert_order() {
+        AlbumInsertOrder::Append => {
+            let last_position = album_photo_join::table
+                .filter(album_photo_join::parent_id.eq(album_id))
+                .select(max(album_photo_join::position))
+                .first::<Option<i32>>(conn)?;
+            let first_position = last_position.map_or(0, |position| position + 1);
+
+            let album_photos = photo_ids.iter().zip(first_position..).map(|(photo_id, position)| AlbumPhoto {
+                parent_id: album_id,
+                photo_id: *photo_id,
+                position,
+            }).collect::<Vec<AlbumPhoto>>();
+
+            insert_into(album_photo_join::table)
+                .values(&album_photos)
+                .execute(conn)
+        }
+        AlbumInsertOrder::CaptureDate => {
+            // Current members in album order, along with their capture dates
+            let members: Vec<(i32, NaiveDateTime)> = album_photo_join::table
+                .inner_join(photos::table.on(photos::id.eq(album_photo_join::photo_id)))
+                .filter(album_photo_join::parent_id.eq(album_id))
+                .order((album_photo_join::position.asc(), album_photo_join::photo_id.asc()))
+                .select((album_photo_join::position, photos::photo_date))
+                .load(conn)?;
+            let mut new_photos: Vec<(i64, NaiveDateTime)> = photos::table
+                .filter(photos::id.eq_any(photo_ids))
+                .select((photos::id, photos::photo_date))
+                .load(conn)?;
+            if new_photos.len() != photo_ids.len() {
+                return Err(Error::NotFound);
+            }
+            new_photos.sort_by_key(|&(photo_id, photo_date)| (photo_date, photo_id));
+
+            // Group the new photos by the position they go to: that of the first member taken later,
+            // or after the last member
+            let end = members.last().map_or(0, |(position, _)| position + 1);
+            let mut groups: BTreeMap<i32, Vec<i64>> = BTreeMap::new();
+            for &(photo_id, photo_date) in &new_photos {
+                let position = members.iter().find(|(_, date)| *date > photo_date).map_or(end, |(position, _)| *position);
+                groups.entry(position).or_default().push(photo_id);
+            }
+
+            // Make room for each group with a single shift, starting from the back so the positions
+            // of the groups in front stay valid
+            let mut rows = 0;
+            for (position, group) in groups.into_iter().rev() {
+                let count = group.len() as i32;
+                diesel::update(album_photo_join::table
+                    .filter(album_photo_join::parent_id.eq(album_id))
+                    .filter(album_photo_join::position.ge(position)))
+                    .set(album_photo_join::position.eq(album_photo_join::position + count))
+                    .execute(conn)?;
+
+                let album_photos = group.iter().zip(position..).map(|(photo_id, position)| AlbumPhoto {
+                    parent_id: album_id,
+                    photo_id: *photo_id,
+                    position,
+                }).collect::<Vec<AlbumPhoto>>();
+                rows += insert_into(album_photo_join::table)
+                    .values(&album_photos)
+                    .execute(conn)?;
+            }
+            Ok(rows)
+        }
+    })
+}
+
+/// Removes photos from a single album, leaving their other album associations in place
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `album_id` - ID of the album
+/// * `photo_ids` - Photos to remove from the album
+///
+/// # Returns
+/// Number of associations removed
+pub fn remove_photo_links(conn: &mut MysqlConnection, album_id: i32, photo_ids: &[i64]) -> Result<usize, Error> {
+    if photo_ids.is_empty() { return Ok(0); }
+
+    let filter = album_photo_join::table
+        .filter(album_photo_join::parent_id.eq(album_id))
+        .filter(album_photo_join::photo_id.eq_any(photo_ids));
 
-    insert_into(album_photo_join::table)
-        .values(&album_photos)
+    diesel::delete(filter)
         .execute(conn)
 }
 
+/// Gets the IDs of all photos in an album, including trashed ones
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `album_id` - ID of the album
+///
+/// # Returns
+/// Vec of photo IDs in album order
+pub fn get_album_photo_ids(conn: &mut MysqlConnection, album_id: i32) -> Result<Vec<i64>, Error> {
+    album_photo_join::table
+        .filter(album_photo_join::parent_id.eq(album_id))
+        .order((album_photo_join::position.asc(), album_photo_join::photo_id.asc()))
+        .select(album_photo_join::photo_id)
+        .load::<i64>(conn)
+}
+
+/// Reorders the photos of an album
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `album_id` - ID of the album
+/// * `photo_ids` - All photos of the album, in their new order
+///
+/// # Returns
+/// Number of photos updated
+pub fn set_album_order(conn: &mut MysqlConnection, album_id: i32, photo_ids: &[i64]) -> Result<usize, Error> {
+    conn.transaction(|conn| {
+        let mut rows = 0;
+        for (position, photo_id) in photo_ids.iter().enumerate() {
+            let member = album_photo_join::table.find((album_id, photo_id));
+            rows += diesel::update(member)
+                .set(album_photo_join::position.eq(position as i32))
+                .execute(conn)?;
+        }
+        Ok(rows)
+    })
+}
+
 /// Removes all album associations for specified photos
 ///
 /// # Arguments
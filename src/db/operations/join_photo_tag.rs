@@ -0,0 +1,83 @@
+use crate::db::schema::photo_tag_join;
+use crate::models::join::PhotoTag;
+use diesel::insert_or_ignore_into;
+use diesel::mysql::MysqlConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+
+/// Tags photos by creating new photo-tag associations. Existing associations are left untouched.
+///
+/// Associations with tags or photos that do not exist are skipped as well (`INSERT IGNORE`), so callers
+/// must check the IDs first.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `tag_ids` - IDs of the tags to add
+/// * `photo_ids` - IDs of the photos to tag
+///
+/// # Returns
+/// Number of rows affected (number of new associations created)
+pub fn add_tag_to_photo(conn: &mut MysqlConnection, tag_ids: &[i32], photo_ids: &[i64]) -> Result<usize, Error> {
+    if tag_ids.is_empty() || photo_ids.is_empty() { return Ok(0); }
+
+    let photo_tags = tag_ids.iter()
+        .flat_map(|&tag_id| photo_ids.iter().map(move |&photo_id| PhotoTag { tag_id, photo_id }))
+        .collect::<Vec<PhotoTag>>();
+
+    insert_or_ignore_into(photo_tag_join::table)
+        .values(&photo_tags)
+        .execute(conn)
+}
+
+/// Removes tags from photos
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `tag_ids` - IDs of the tags to remove
+/// * `photo_ids` - IDs of the photos to untag
+///
+/// # Returns
+/// Number of rows affected (number of associations removed)
+pub fn remove_tag_from_photo(conn: &mut MysqlConnection, tag_ids: &[i32], photo_ids: &[i64]) -> Result<usize, Error> {
+    if tag_ids.is_empty() || photo_ids.is_empty() { return Ok(0); }
+
+    let filter = photo_tag_join::table
+        .filter(photo_tag_join::tag_id.eq_any(tag_ids))
+        .filter(photo_tag_join::photo_id.eq_any(photo_ids));
+
+    diesel::delete(filter)
+        .execute(conn)
+}
+
+/// Gets the IDs of all tags directly assigned to a photo
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `photo_id` - ID of the photo
+///
+/// # Returns
+/// IDs of the photo's tags
+pub fn get_photo_tag_ids(conn: &mut MysqlConnection, photo_id: i64) -> Result<Vec<i32>, Error> {
+    photo_tag_join::table
+        .filter(photo_tag_join::photo_id.eq(photo_id))
+        .select(photo_tag_join::tag_id)
+        .load::<i32>(conn)
+}
+
+/// Gets the IDs of all photos tagged with any of the given tags
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `tag_ids` - IDs of the tags
+///
+/// # Returns
+/// Deduplicated IDs of the tagged photos
+pub fn get_tagged_photo_ids(conn: &mut MysqlConnection, tag_ids: &[i32]) -> Result<Vec<i64>, Error> {
+    if tag_ids.is_empty() { return Ok(vec![]); }
+
+    photo_tag_join::table
+        .filter(photo_tag_join::tag_id.eq_any(tag_ids))
+        .select(photo_tag_join::photo_id)
+        .distinct()
+        .load::<i64>(conn)
+}
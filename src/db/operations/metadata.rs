@@ -0,0 +1,111 @@
+use crate::db::schema::{photo_metadata, photo_metadata_issues, photos};
+use crate::models::metadata::{MetadataIssue, PhotoMetadata, PhotoMetadataIssue};
+use crate::models::photo::Photo;
+use diesel::{insert_into, insert_or_ignore_into};
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::MysqlConnection;
+
+/// Get the stored metadata blob of a photo
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `photo_id` - ID of the photo to retrieve metadata for
+///
+/// # Returns
+/// `PhotoMetadata` of the photo, or `NotFound` if none has been stored
+pub fn get_photo_metadata(conn: &mut MysqlConnection, photo_id: i64) -> Result<PhotoMetadata, Error> {
+    photo_metadata::table
+        .find(photo_id)
+        .get_result::<PhotoMetadata>(conn)
+}
+
+/// Stores the metadata blob of a photo, replacing any existing entry
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `metadata` - Metadata to store, keyed by photo ID
+///
+/// # Returns
+/// Number of rows affected
+pub fn set_photo_metadata(conn: &mut MysqlConnection, metadata: &PhotoMetadata) -> Result<usize, Error> {
+    insert_into(photo_metadata::table)
+        .values(metadata)
+        .on_conflict(diesel::dsl::DuplicatedKeys)
+        .do_update()
+        .set(photo_metadata::metadata.eq(&metadata.metadata))
+        .execute(conn)
+}
+
+/// Gets the IDs of all photos that have no stored metadata blob
+///
+/// # Arguments
+/// * `conn` - Database connection
+///
+/// # Returns
+/// Vec of photo IDs without an entry in `photo_metadata`
+pub fn get_photos_without_metadata(conn: &mut MysqlConnection) -> Result<Vec<i64>, Error> {
+    photos::table
+        .left_outer_join(photo_metadata::table)
+        .filter(photo_metadata::id.is_null())
+        .select(photos::id)
+        .load::<i64>(conn)
+}
+
+/// Records metadata issues found for a photo. Issues that are already recorded are left untouched.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `photo_id` - ID of the photo
+/// * `issues` - Issues to record
+///
+/// # Returns
+/// Number of new issues recorded
+pub fn add_metadata_issues(conn: &mut MysqlConnection, photo_id: i64, issues: &[MetadataIssue]) -> Result<usize, Error> {
+    if issues.is_empty() { return Ok(0); }
+
+    let rows = issues.iter().map(|issue| PhotoMetadataIssue {
+        photo_id,
+        issue: issue.as_str().to_string(),
+    }).collect::<Vec<PhotoMetadataIssue>>();
+
+    insert_or_ignore_into(photo_metadata_issues::table)
+        .values(&rows)
+        .execute(conn)
+}
+
+/// Marks a metadata issue as resolved for the given photos, e.g. after their capture time was corrected
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `photo_ids` - IDs of the photos
+/// * `issue` - Issue to clear
+///
+/// # Returns
+/// Number of issues cleared
+pub fn resolve_metadata_issue(conn: &mut MysqlConnection, photo_ids: &[i64], issue: MetadataIssue) -> Result<usize, Error> {
+    if photo_ids.is_empty() { return Ok(0); }
+
+    let filter = photo_metadata_issues::table
+        .filter(photo_metadata_issues::photo_id.eq_any(photo_ids))
+        .filter(photo_metadata_issues::issue.eq(issue.as_str()));
+
+    diesel::delete(filter).execute(conn)
+}
+
+/// Gets every photo with at least one recorded metadata issue, paired with the issue.
+/// A photo with several issues appears once per issue. Trashed photos are skipped.
+///
+/// # Arguments
+/// * `conn` - Database connection
+///
+/// # Returns
+/// Vec of `(issue, photo)` pairs, ordered by issue
+pub fn get_photos_with_issues(conn: &mut MysqlConnection) -> Result<Vec<(String, Photo)>, Error> {
+    photo_metadata_issues::table
+        .inner_join(photos::table)
+        .filter(photos::deleted_at.is_null())
+        .order((photo_metadata_issues::issue.asc(), photos::photo_date.asc()))
+        .select((photo_metadata_issues::issue, photos::all_columns))
+        .load::<(String, Photo)>(conn)
+}
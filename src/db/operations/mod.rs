@@ -2,6 +2,15 @@ pub mod album;
 pub mod photo;
 pub mod join_album_album;
 pub mod join_album_photo;
+pub mod join_photo_tag;
 pub mod query;
 pub mod paths;
-pub mod thumbnail;
\ No newline at end of file
+pub mod thumbnail;
+pub mod metadata;
+pub mod tag;
+pub mod verification;
+pub mod audit;
+pub mod phash;
+pub mod stack;
+pub mod collection;
+pub mod smart_album;
@@ -1,4 +1,5 @@
 use crate::db::schema::{album_album_join, album_photo_join, albums, photos};
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 use diesel::result::Error::DatabaseError;
 use diesel::result::{DatabaseErrorKind, Error};
@@ -6,8 +7,23 @@ use diesel::MysqlConnection;
 use std::collections::HashSet;
 use std::path::PathBuf;
 
+/// Directory under $STORAGE_ROOT holding trashed photos and albums
+pub const TRASH_DIR: &str = ".trash";
+
+/// Gets the directory a trashed photo (and its associated files) is kept in, relative to $STORAGE_ROOT
+pub fn trashed_photo_dir(photo_id: i64) -> PathBuf {
+    PathBuf::from(TRASH_DIR).join("photos").join(photo_id.to_string())
+}
+
+/// Gets the directory a trashed album directory is kept in, relative to $STORAGE_ROOT
+pub fn trashed_album_dir(album_id: i32) -> PathBuf {
+    PathBuf::from(TRASH_DIR).join("albums").join(album_id.to_string())
+}
+
 /// Gets an album's path, relative to $STORAGE_ROOT
 ///
+/// Trashed albums resolve to their location under `trashed_album_dir`.
+///
 /// # Arguments
 /// * `conn` - Database connection
 /// * `album_id` - ID of the album to get path for
@@ -18,6 +34,7 @@ pub fn get_album_path(conn: &mut MysqlConnection, album_id: i32) -> Result<PathB
 
     // Collect the chain of album names from the current album up to root
     let mut segments: Vec<String> = Vec::new();
+    let mut base = PathBuf::new();
     let mut current_id: Option<i32> = Some(album_id);
     let mut seen: HashSet<i32> = HashSet::new();
 
@@ -28,12 +45,18 @@ pub fn get_album_path(conn: &mut MysqlConnection, album_id: i32) -> Result<PathB
         }
 
         // Fetch the album_name for this album id
-        let name: String = albums::table
+        let (name, deleted_at): (String, Option<NaiveDateTime>) = albums::table
             .find(aid)
-            .select(albums::album_name)
-            .first::<String>(conn)?;
+            .select((albums::album_name, albums::deleted_at))
+            .first(conn)?;
         segments.push(name);
 
+        // A trashed album is kept outside the album tree
+        if deleted_at.is_some() {
+            base = trashed_album_dir(aid);
+            break;
+        }
+
         // Find parent album, if any. If multiple parents exist, choose the one with the lowest parent_id for determinism.
         let parent: Option<i32> = album_album_join::table
             .filter(album_album_join::album_id.eq(aid))
@@ -47,7 +70,7 @@ pub fn get_album_path(conn: &mut MysqlConnection, album_id: i32) -> Result<PathB
 
     // Build the path from root to leaf: segments were collected leaf->root, so reverse
     segments.reverse();
-    let mut path = PathBuf::new();
+    let mut path = base;
     for seg in segments {
         path.push(seg);
     }
@@ -57,6 +80,8 @@ pub fn get_album_path(conn: &mut MysqlConnection, album_id: i32) -> Result<PathB
 
 /// Gets a photo's path, relative to $STORAGE_ROOT
 ///
+/// Trashed photos resolve to their location under `trashed_photo_dir`.
+///
 /// # Arguments
 /// * `conn` - Database connection
 /// * `photo_id` - ID of the photo to get path for
@@ -66,10 +91,14 @@ pub fn get_album_path(conn: &mut MysqlConnection, album_id: i32) -> Result<PathB
 pub fn get_photo_path(conn: &mut MysqlConnection, photo_id: i64) -> Result<PathBuf, Error> {
 
     // Get the photo file name (and confirm the photo exists)
-    let file_name: String = photos::table
+    let (file_name, deleted_at): (String, Option<NaiveDateTime>) = photos::table
         .find(photo_id)
-        .select(photos::file_name)
-        .first::<String>(conn)?;
+        .select((photos::file_name, photos::deleted_at))
+        .first(conn)?;
+
+    if deleted_at.is_some() {
+        return Ok(trashed_photo_dir(photo_id).join(file_name));
+    }
 
     // Resolve a parent album if any
     let parent_album: Option<i32> = album_photo_join::table
@@ -81,7 +110,7 @@ pub fn get_photo_path(conn: &mut MysqlConnection, photo_id: i64) -> Result<PathB
 
     let mut path = match parent_album {
         Some(album_id) => get_album_path(conn, album_id)?,
-        None => PathBuf::new(), // Unfiled photo: place at root of storage
+        None => PathBuf::from("unfiled"), // Unfiled photo: lives in `$STORAGE_ROOT/unfiled`
     };
     
     path.push(file_name);
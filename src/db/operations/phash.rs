@@ -0,0 +1,57 @@
+use crate::db::schema::{photo_phashes, photos, thumbnails};
+use crate::models::phash::PhotoPhash;
+use diesel::mysql::MysqlConnection;
+use diesel::prelude::*;
+use diesel::replace_into;
+use diesel::result::Error;
+
+/// Stores the perceptual hash of a photo, replacing any existing one
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `phash` - The hash to store
+///
+/// # Returns
+/// Number of rows affected
+pub fn set_photo_phash(conn: &mut MysqlConnection, phash: &PhotoPhash) -> Result<usize, Error> {
+    replace_into(photo_phashes::table)
+        .values(phash)
+        .execute(conn)
+}
+
+/// Gets the perceptual hashes of all original photos that are not in the trash. Copies are left out,
+/// as they are deliberate duplicates.
+///
+/// # Arguments
+/// * `conn` - Database connection
+///
+/// # Returns
+/// Vec of `PhotoPhash`, ordered by photo ID
+pub fn get_photo_phashes(conn: &mut MysqlConnection) -> Result<Vec<PhotoPhash>, Error> {
+    photo_phashes::table
+        .inner_join(photos::table)
+        .filter(photos::deleted_at.is_null())
+        .filter(photos::original_id.is_null())
+        .order(photo_phashes::id.asc())
+        .select(PhotoPhash::as_select())
+        .load(conn)
+}
+
+/// Gets the IDs of all original photos that have a thumbnail, but no perceptual hash yet
+///
+/// # Arguments
+/// * `conn` - Database connection
+///
+/// # Returns
+/// Vec of photo IDs
+pub fn get_photos_without_phash(conn: &mut MysqlConnection) -> Result<Vec<i64>, Error> {
+    let hashed_photo_ids = photo_phashes::table.select(photo_phashes::id);
+
+    thumbnails::table
+        .inner_join(photos::table)
+        .filter(photos::original_id.is_null())
+        .filter(thumbnails::id.ne_all(hashed_photo_ids))
+        .select(thumbnails::id)
+        .order(thumbnails::id.asc())
+        .load::<i64>(conn)
+}
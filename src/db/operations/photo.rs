@@ -1,10 +1,18 @@
+use crate::db::operations::stack::refresh_stack_covers;
 use crate::db::operations::thumbnail::delete_thumbnail;
-use crate::db::schema::photos::dsl::{id, photos};
-use crate::models::photo::{NewPhoto, Photo};
+use crate::db::operations::join_photo_tag::{add_tag_to_photo, get_photo_tag_ids};
+use crate::db::operations::metadata::{get_photo_metadata, set_photo_metadata};
+use crate::db::operations::thumbnail::{create_thumbnail, get_thumbnail};
+use crate::db::schema::photos::dsl::{deleted_at, file_name, id, original_id, photo_date, photo_timezone, photos, size_on_disk};
+use crate::models::metadata::PhotoMetadata;
+use crate::models::photo::{NewPhoto, Photo, PhotoFlags};
+use crate::models::thumbnail::Thumbnail;
+use chrono::NaiveDateTime;
 use diesel::insert_into;
 use diesel::mysql::MysqlConnection;
 use diesel::prelude::*;
 use diesel::result::Error;
+use std::path::Path;
 
 /// Creates a new photo entry in the database with associated metadata fields
 ///
@@ -25,7 +33,8 @@ pub fn create_photo(conn: &mut MysqlConnection, new_photo: NewPhoto) -> Result<i
         .get_result(conn)
 }
 
-/// Checks if a photo with the given hash value already exists in the database
+/// Checks if a photo with the given hash value already exists in the database. Copies made with
+/// `copy_photo` share their original's hash, so only the original is returned.
 ///
 /// # Arguments
 /// * `conn` - Database connection
@@ -38,6 +47,7 @@ pub fn create_photo(conn: &mut MysqlConnection, new_photo: NewPhoto) -> Result<i
 pub fn check_hash(conn: &mut MysqlConnection, hash: &str) -> Result<Option<Photo>, Error> {
     photos
         .filter(crate::db::schema::photos::dsl::hash.eq(hash))
+        .filter(original_id.is_null())
         .first::<Photo>(conn)
         .map(Some)
         .or_else(|e| match e {
@@ -46,7 +56,7 @@ pub fn check_hash(conn: &mut MysqlConnection, hash: &str) -> Result<Option<Photo
         })
 }
 
-/// Gets photos by ID from the database
+/// Gets photos by ID from the database. Trashed photos are skipped.
 ///
 /// # Arguments
 /// * `conn` - Database connection
@@ -59,10 +69,14 @@ pub fn get_photo(conn: &mut MysqlConnection, photo_ids: &[i64]) -> Result<Vec<Ph
 
     photos
         .filter(id.eq_any(photo_ids))
+        .filter(deleted_at.is_null())
         .load::<Photo>(conn)
 }
 
-/// Deletes a photo from the database by its ID
+/// Permanently deletes a photo from the database by its ID (see `set_photos_trashed` for soft deletion)
+///
+/// Copies of a deleted original are kept: the oldest remaining copy becomes the new original, and the
+/// other copies are re-pointed to it.
 ///
 /// # Arguments
 /// * `conn` - Database connection
@@ -78,12 +92,238 @@ pub fn delete_photo(conn: &mut MysqlConnection, photo_ids: &[i64]) -> Result<Vec
         .filter(id.eq_any(photo_ids))
         .load::<Photo>(conn)?;
 
-    // Then delete them
+    // Promote the oldest surviving copy of each deleted original to its heir. The foreign key doesn't allow
+    // deleting a photo that is still referenced, and the heir can't become an original while the old one
+    // holds the unique hash, so the heir points at itself until the old original is gone.
+    let copies: Vec<(i64, Option<i64>)> = photos
+        .filter(original_id.eq_any(photo_ids))
+        .filter(id.ne_all(photo_ids))
+        .order(id.asc())
+        .select((id, original_id))
+        .load(conn)?;
+    let mut heirs: Vec<i64> = Vec::new();
+    for &photo_id in photo_ids {
+        let mut copy_ids = copies.iter().filter(|(_, original)| *original == Some(photo_id)).map(|(copy_id, _)| *copy_id);
+        if let Some(heir) = copy_ids.next() {
+            let others: Vec<i64> = copy_ids.collect();
+            diesel::update(photos.filter(id.eq_any(&others)))
+                .set(original_id.eq(heir))
+                .execute(conn)?;
+            diesel::update(photos.find(heir))
+                .set(original_id.eq(heir))
+                .execute(conn)?;
+            heirs.push(heir);
+        }
+    }
+
+    // Then delete them, copies before originals, which they may still reference
+    diesel::delete(photos.filter(id.eq_any(photo_ids)).filter(original_id.is_not_null()))
+        .execute(conn)?;
     diesel::delete(photos.filter(id.eq_any(photo_ids)))
         .execute(conn)?;
 
+    if !heirs.is_empty() {
+        diesel::update(photos.filter(id.eq_any(&heirs)))
+            .set(original_id.eq(None::<i64>))
+            .execute(conn)?;
+    }
+
     // Also delete associated thumbnails
     delete_thumbnail(conn, photo_ids)?;
 
     Ok(deleted)
 }
+
+/// Updates the rating, color label and/or pick flag of multiple photos at once
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `photo_ids` - Slice of IDs to update
+/// * `flags` - Flags to set; fields left as `None` are not changed
+///
+/// # Returns
+/// Number of photos updated
+pub fn set_photo_flags(conn: &mut MysqlConnection, photo_ids: &[i64], flags: &PhotoFlags) -> Result<usize, Error> {
+    if photo_ids.is_empty() || flags.is_empty() { return Ok(0); }
+
+    diesel::update(photos.filter(id.eq_any(photo_ids)))
+        .set(flags)
+        .execute(conn)
+}
+
+/// Sets the capture time and timezone of a photo
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `photo_id` - ID of the photo to update
+/// * `date` - New capture time, in the photo's local time
+/// * `timezone` - New UTC offset, e.g. `+09:00`
+///
+/// # Returns
+/// The number of rows affected (1 if successful, 0 if the photo doesn't exist)
+pub fn set_photo_time(conn: &mut MysqlConnection, photo_id: i64, date: NaiveDateTime, timezone: &str) -> Result<usize, Error> {
+    diesel::update(photos.find(photo_id))
+        .set((photo_date.eq(date), photo_timezone.eq(timezone)))
+        .execute(conn)
+}
+
+/// Updates the stored hash and size of a photo after its original file has been rewritten
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `photo_id` - ID of the photo to update
+/// * `new_hash` - xxh3-128 hash of the rewritten file
+/// * `new_size` - Size of the rewritten file in KB
+///
+/// # Returns
+/// The number of rows affected (1 if successful, 0 if the photo doesn't exist)
+pub fn set_photo_hash(conn: &mut MysqlConnection, photo_id: i64, new_hash: &str, new_size: i32) -> Result<usize, Error> {
+    diesel::update(photos.find(photo_id))
+        .set((crate::db::schema::photos::dsl::hash.eq(new_hash), size_on_disk.eq(new_size)))
+        .execute(conn)
+}
+
+/// Changes the file name of a photo after its files have been renamed on disk
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `photo_id` - ID of the photo to update
+/// * `new_file_name` - New file name, including the extension
+///
+/// # Returns
+/// The number of rows affected (1 if successful, 0 if the photo doesn't exist)
+pub fn set_photo_file_name(conn: &mut MysqlConnection, photo_id: i64, new_file_name: &str) -> Result<usize, Error> {
+    diesel::update(photos.find(photo_id))
+        .set(file_name.eq(new_file_name))
+        .execute(conn)
+}
+
+/// Checks whether any photo, including trashed ones, already uses a file name
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `name` - File name to look for, including the extension
+///
+/// # Returns
+/// `true` if the file name is taken
+pub fn file_name_exists(conn: &mut MysqlConnection, name: &str) -> Result<bool, Error> {
+    diesel::select(diesel::dsl::exists(photos.filter(file_name.eq(name))))
+        .get_result(conn)
+}
+
+/// Gets all photos outside the trash whose file name has a given base name
+/// (e.g. `IMG_0001.CR3` and `IMG_0001.JPG` for `IMG_0001`), in any album
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `base_name` - Base name to look for, without extension
+///
+/// # Returns
+/// Vec<Photo> of the matching photos
+pub fn get_photos_by_base_name(conn: &mut MysqlConnection, base_name: &str) -> Result<Vec<Photo>, Error> {
+    // `_` and `%` in the base name act as wildcards, so the matches are checked again afterwards
+    let candidates = photos
+        .filter(deleted_at.is_null())
+        .filter(file_name.eq(base_name).or(file_name.like(format!("{base_name}.%"))))
+        .load::<Photo>(conn)?;
+
+    Ok(candidates
+        .into_iter()
+        .filter(|photo| Path::new(&photo.file_name).file_prefix().is_some_and(|prefix| prefix == base_name))
+        .collect())
+}
+
+/// Creates a copy of a photo record, for a physical copy of its files made with `copy_photo_fs`.
+///
+/// The copy shares the original's metadata, tags and thumbnail, and points back at it through
+/// `original_id`. Copies of copies point at the first original instead.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `photo` - The photo to copy
+///
+/// # Returns
+/// Ok(id) with the ID of the new photo, or error if an insert fails
+pub fn copy_photo(conn: &mut MysqlConnection, photo: &Photo) -> Result<i64, Error> {
+    let copy_id = create_photo(conn, NewPhoto {
+        hash: photo.hash.clone(),
+        file_name: photo.file_name.clone(),
+        size_on_disk: photo.size_on_disk,
+        photo_date: photo.photo_date,
+        photo_timezone: photo.photo_timezone.clone(),
+        resolution_width: photo.resolution_width,
+        resolution_height: photo.resolution_height,
+        mime_type: photo.mime_type.clone(),
+        camera_model: photo.camera_model.clone(),
+        lens_model: photo.lens_model.clone(),
+        shutter_count: photo.shutter_count,
+        focal_length: photo.focal_length,
+        iso: photo.iso,
+        shutter_speed: photo.shutter_speed.clone(),
+        aperture: photo.aperture,
+        latitude: photo.latitude,
+        longitude: photo.longitude,
+        altitude: photo.altitude,
+        rating: photo.rating,
+        color_label: photo.color_label.clone(),
+        pick_flag: photo.pick_flag,
+        original_id: Some(photo.original_id.unwrap_or(photo.id)),
+    })?;
+
+    if let Some(metadata) = get_photo_metadata(conn, photo.id).optional()? {
+        set_photo_metadata(conn, &PhotoMetadata { id: copy_id, metadata: metadata.metadata })?;
+    }
+    let tag_ids = get_photo_tag_ids(conn, photo.id)?;
+    add_tag_to_photo(conn, &tag_ids, &[copy_id])?;
+    if let Some(thumbnail) = get_thumbnail(conn, photo.id).optional()? {
+        create_thumbnail(conn, &Thumbnail { id: copy_id, thumbnail_path: thumbnail.thumbnail_path })?;
+    }
+
+    Ok(copy_id)
+}
+
+/// Gets every photo in the library, including trashed ones
+///
+/// # Arguments
+/// * `conn` - Database connection
+///
+/// # Returns
+/// Vec<Photo> of all photos
+pub fn get_all_photos(conn: &mut MysqlConnection) -> Result<Vec<Photo>, Error> {
+    photos.load::<Photo>(conn)
+}
+
+/// Moves photos to the trash (or restores them) by setting their `deleted_at` marker.
+/// Stacks whose cover ends up in the trash, or that had no cover left, get a new one.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `photo_ids` - Slice of IDs to update
+/// * `trashed_at` - When the photos were trashed, or `None` to restore them
+///
+/// # Returns
+/// Number of photos updated
+pub fn set_photos_trashed(conn: &mut MysqlConnection, photo_ids: &[i64], trashed_at: Option<NaiveDateTime>) -> Result<usize, Error> {
+    if photo_ids.is_empty() { return Ok(0); }
+
+    let rows = diesel::update(photos.filter(id.eq_any(photo_ids)))
+        .set(deleted_at.eq(trashed_at))
+        .execute(conn)?;
+
+    refresh_stack_covers(conn, photo_ids)?;
+    Ok(rows)
+}
+
+/// Gets all photos in the trash
+///
+/// # Arguments
+/// * `conn` - Database connection
+///
+/// # Returns
+/// Vec<Photo> of trashed photos, most recently trashed first
+pub fn get_trashed_photos(conn: &mut MysqlConnection) -> Result<Vec<Photo>, Error> {
+    photos
+        .filter(deleted_at.is_not_null())
+        .order(deleted_at.desc())
+        .load::<Photo>(conn)
+}
@@ -1,26 +1,84 @@
+use crate::db::operations::collection::get_collection_photo_ids;
+use crate::db::operations::join_album_photo::get_album_photo_ids;
 use crate::db::schema::album_album_join::dsl as join_dsl;
 use crate::db::schema::albums::dsl as albums_dsl;
 use crate::db::schema::albums::dsl::albums;
-use crate::db::schema::{album_photo_join, photos};
+use crate::db::schema::{album_photo_join, photo_tag_join, photos, stacks};
 use crate::models::album::Album;
+use crate::models::filter::{PhotoFilter, PhotoSort, SortOrder};
+use crate::models::geo::BoundingBox;
 use crate::models::photo::Photo;
+use crate::models::smart_album::{SmartField, SmartMatch, SmartOperator, SmartQuery, SmartRule};
+use diesel::mysql::Mysql;
 use diesel::prelude::*;
 use diesel::result::Error;
+use diesel::sql_types::Bool;
+use std::collections::HashMap;
+
+/// Builds a boxed query over `photos` restricted by the given listing filter.
+///
+/// Trashed photos are always excluded. Listing queries add their own restrictions (album membership, etc.) on top of this.
+fn filtered_photos(filter: &PhotoFilter) -> photos::BoxedQuery<'_, Mysql> {
+    let mut query = photos::table
+        .filter(photos::deleted_at.is_null())
+        .into_boxed();
+
+    if let Some(min_rating) = filter.min_rating {
+        query = query.filter(photos::rating.ge(min_rating));
+    }
+    if let Some(color_label) = &filter.color_label {
+        query = query.filter(photos::color_label.eq(color_label.to_lowercase()));
+    }
+    if let Some(pick_flag) = filter.pick_flag {
+        query = query.filter(photos::pick_flag.eq(pick_flag));
+    }
+    if filter.collapse_stacks == Some(true) {
+        // Stacked photos stand in for their stack only if they are its cover
+        let cover_ids = stacks::table.select(stacks::cover_id);
+        query = query.filter(photos::stack_id.is_null().or(photos::id.nullable().eq_any(cover_ids)));
+    }
+
+    query
+}
+
+/// Sorts a photo listing and restricts it to the requested page, as set in `filter`.
+/// Without a `sort`, photos are listed by ID.
+fn sorted_page<'a>(query: photos::BoxedQuery<'a, Mysql>, filter: &PhotoFilter) -> photos::BoxedQuery<'a, Mysql> {
+    let descending = filter.order == Some(SortOrder::Desc);
+    macro_rules! sort_by {
+        ($column:expr) => {
+            if descending { query.order_by($column.desc()) } else { query.order_by($column.asc()) }
+        };
+    }
+
+    let query = match filter.sort.unwrap_or(PhotoSort::Id) {
+        PhotoSort::Date => sort_by!(photos::photo_date),
+        PhotoSort::Name => sort_by!(photos::file_name),
+        PhotoSort::Rating => sort_by!(photos::rating),
+        PhotoSort::Size => sort_by!(photos::size_on_disk),
+        PhotoSort::Id => sort_by!(photos::id),
+    };
+    // Ties are broken by ID, so pages don't overlap
+    let query = if descending { query.then_order_by(photos::id.desc()) } else { query.then_order_by(photos::id.asc()) };
+
+    match filter.page_bounds() {
+        Some((offset, limit)) => query.offset(offset).limit(limit),
+        None => query,
+    }
+}
 
 /// Retrieves all photos associated with the specified album
 ///
 /// # Arguments
 /// * `conn` - Database connection pool
 /// * `album_id` - ID of the album to get photos from
+/// * `filter` - Listing filter to apply (use `PhotoFilter::default()` for all photos)
 ///
 /// # Returns
-/// Vec of all photos belonging to the album, or error if query fails
-pub fn get_photos_in_album(conn: &mut MysqlConnection, album_id: i32) -> Result<Vec<Photo>, Error> {
-    album_photo_join::table
-        .filter(album_photo_join::parent_id.eq(album_id))
-        .inner_join(photos::table)
-        .select(photos::all_columns) // Select all fields from `photos`
-        .load::<Photo>(conn)
+/// Vec of all photos belonging to the album, in album order unless `filter` sets a `sort`, or error if query fails
+pub fn get_photos_in_album(conn: &mut MysqlConnection, album_id: i32, filter: &PhotoFilter) -> Result<Vec<Photo>, Error> {
+    let photo_ids = get_album_photo_ids(conn, album_id)?;
+    in_manual_order(conn, &photo_ids, filter)
 }
 
 /// Retrieves all subalbums associated with the specified album
@@ -30,29 +88,213 @@ pub fn get_photos_in_album(conn: &mut MysqlConnection, album_id: i32) -> Result<
 /// * `album_id` - ID of the album to get photos from
 ///
 /// # Returns
-/// Vec of all subalbums belonging to the album, or error if query fails
+/// Vec of all subalbums belonging to the album in their sort order, or error if query fails
 pub fn get_albums_in_album(conn: &mut MysqlConnection, album_id: i32) -> Result<Vec<Album>, Error> {
     join_dsl::album_album_join
         .filter(join_dsl::parent_id.eq(album_id))
         .inner_join(albums.on(join_dsl::album_id.eq(albums_dsl::id)))
+        .filter(albums_dsl::deleted_at.is_null())
+        .order((albums_dsl::sort_position.is_null().asc(), albums_dsl::sort_position.asc(), albums_dsl::album_name.asc()))
         .select(albums::all_columns())
         .load::<Album>(conn)
 }
 
 /// Gets all photos from the database that are not currently part of any album
 ///
-/// Photos are compared against `album_photos` join table using a left outer join
+/// Photos are compared against the `album_photo_join` table using a `NOT IN` subquery
 /// to find records with no associated album entries.
 ///
 /// # Arguments
 /// * `conn` - Database connection pool
+/// * `filter` - Listing filter to apply (use `PhotoFilter::default()` for all photos)
 ///
 /// # Returns
 /// Vec of all photos not belonging to any album, or error if query fails
-pub fn get_photos_unfiled(conn: &mut MysqlConnection) -> Result<Vec<Photo>, Error> {
-    photos::table
-        .left_outer_join(album_photo_join::table.on(album_photo_join::photo_id.eq(photos::id)))
-        .filter(album_photo_join::photo_id.is_null()) // Only those with no match
-        .select(photos::all_columns) // Select all fields from `photos`
+pub fn get_photos_unfiled(conn: &mut MysqlConnection, filter: &PhotoFilter) -> Result<Vec<Photo>, Error> {
+    let filed_photo_ids = album_photo_join::table.select(album_photo_join::photo_id);
+
+    sorted_page(filtered_photos(filter), filter)
+        .filter(photos::id.ne_all(filed_photo_ids)) // Only those with no album association
+        .load::<Photo>(conn)
+}
+
+/// Gets all geotagged photos located within a bounding box
+///
+/// Bounding boxes crossing the antimeridian (`min_lon > max_lon`) are split into
+/// the two longitude ranges on either side of it.
+///
+/// # Arguments
+/// * `conn` - Database connection pool
+/// * `bbox` - Bounding box to search within
+///
+/// # Returns
+/// Vec of all photos with coordinates inside `bbox`, or error if query fails
+pub fn get_photos_in_bbox(conn: &mut MysqlConnection, bbox: &BoundingBox) -> Result<Vec<Photo>, Error> {
+    let query = photos::table
+        .filter(photos::deleted_at.is_null())
+        .filter(photos::latitude.between(bbox.min_lat, bbox.max_lat))
+        .into_boxed();
+
+    let query = if bbox.crosses_antimeridian() {
+        query.filter(photos::longitude.ge(bbox.min_lon).or(photos::longitude.le(bbox.max_lon)))
+    } else {
+        query.filter(photos::longitude.between(bbox.min_lon, bbox.max_lon))
+    };
+
+    query
+        .select(photos::all_columns)
+        .load::<Photo>(conn)
+}
+
+/// Retrieves all photos tagged with any of the given tags
+///
+/// # Arguments
+/// * `conn` - Database connection pool
+/// * `tag_ids` - IDs of the tags to match (typically a tag and its descendants)
+/// * `filter` - Listing filter to apply (use `PhotoFilter::default()` for all photos)
+///
+/// # Returns
+/// Vec of all matching photos, or error if query fails
+pub fn get_photos_with_tag(conn: &mut MysqlConnection, tag_ids: &[i32], filter: &PhotoFilter) -> Result<Vec<Photo>, Error> {
+    let tagged_photo_ids = photo_tag_join::table
+        .filter(photo_tag_join::tag_id.eq_any(tag_ids))
+        .select(photo_tag_join::photo_id);
+
+    sorted_page(filtered_photos(filter), filter)
+        .filter(photos::id.eq_any(tagged_photo_ids))
+        .load::<Photo>(conn)
+}
+
+/// Retrieves all photos in a collection, in collection order unless `filter` sets a `sort`
+///
+/// # Arguments
+/// * `conn` - Database connection pool
+/// * `collection_id` - ID of the collection to get photos from
+/// * `filter` - Listing filter to apply (use `PhotoFilter::default()` for all photos)
+///
+/// # Returns
+/// Vec of all matching photos in the collection, or error if query fails
+pub fn get_photos_in_collection(conn: &mut MysqlConnection, collection_id: i32, filter: &PhotoFilter) -> Result<Vec<Photo>, Error> {
+    let photo_ids = get_collection_photo_ids(conn, collection_id)?;
+    in_manual_order(conn, &photo_ids, filter)
+}
+
+/// Loads the given photos in the given order, unless `filter` sets a `sort`
+///
+/// # Arguments
+/// * `conn` - Database connection pool
+/// * `photo_ids` - Photos of an album or collection, in their manual order
+/// * `filter` - Listing filter to apply
+///
+/// # Returns
+/// Vec of the matching photos, or error if query fails
+fn in_manual_order(conn: &mut MysqlConnection, photo_ids: &[i64], filter: &PhotoFilter) -> Result<Vec<Photo>, Error> {
+    if filter.sort.is_some() {
+        return sorted_page(filtered_photos(filter), filter)
+            .filter(photos::id.eq_any(photo_ids))
+            .load::<Photo>(conn);
+    }
+
+    // The manual order lives in the join table, so sort and paginate here
+    let positions: HashMap<i64, usize> = photo_ids.iter().enumerate().map(|(position, photo_id)| (*photo_id, position)).collect();
+    let mut ordered_photos = filtered_photos(filter)
+        .filter(photos::id.eq_any(photo_ids))
+        .load::<Photo>(conn)?;
+    ordered_photos.sort_by_key(|photo| positions[&photo.id]);
+    if filter.order == Some(SortOrder::Desc) {
+        ordered_photos.reverse();
+    }
+
+    Ok(match filter.page_bounds() {
+        Some((offset, limit)) => ordered_photos.into_iter().skip(offset as usize).take(limit as usize).collect(),
+        None => ordered_photos,
+    })
+}
+
+/// A condition on `photos`, as built from a smart album rule
+type PhotoCondition = Box<dyn BoxableExpression<photos::table, Mysql, SqlType = Bool>>;
+
+/// Escapes `%`, `_` and `\` in a `LIKE` pattern
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Builds the SQL condition of a single smart album rule
+fn rule_condition(rule: &SmartRule) -> anyhow::Result<PhotoCondition> {
+    macro_rules! compare {
+        ($column:expr, $value:expr) => {
+            match rule.op {
+                SmartOperator::Eq => Box::new($column.eq($value)) as PhotoCondition,
+                SmartOperator::Ne => Box::new($column.ne($value)),
+                SmartOperator::Lt => Box::new($column.lt($value)),
+                SmartOperator::Le => Box::new($column.le($value)),
+                SmartOperator::Gt => Box::new($column.gt($value)),
+                SmartOperator::Ge => Box::new($column.ge($value)),
+                SmartOperator::Contains | SmartOperator::Year => {
+                    return Err(anyhow::anyhow!("{:?} does not apply to {:?}", rule.op, rule.field));
+                }
+            }
+        };
+    }
+    // Integer values are clamped to the range of their column
+    macro_rules! integer {
+        ($type:ty) => {
+            rule.integer()?.clamp(<$type>::MIN as i64, <$type>::MAX as i64) as $type
+        };
+    }
+
+    Ok(match (rule.field, rule.op) {
+        (SmartField::FileName, SmartOperator::Contains) => Box::new(photos::file_name.like(format!("%{}%", escape_like(rule.text()?)))),
+        (SmartField::MimeType, SmartOperator::Contains) => Box::new(photos::mime_type.like(format!("%{}%", escape_like(rule.text()?)))),
+        (SmartField::CameraModel, SmartOperator::Contains) => Box::new(photos::camera_model.like(format!("%{}%", escape_like(rule.text()?)))),
+        (SmartField::LensModel, SmartOperator::Contains) => Box::new(photos::lens_model.like(format!("%{}%", escape_like(rule.text()?)))),
+        (SmartField::ColorLabel, SmartOperator::Contains) => Box::new(photos::color_label.assume_not_null().like(format!("%{}%", escape_like(rule.text()?)))),
+        (SmartField::PhotoDate, SmartOperator::Year) => {
+            let (start, end) = rule.year_range()?;
+            Box::new(photos::photo_date.ge(start).and(photos::photo_date.lt(end)))
+        }
+        (SmartField::FileName, _) => compare!(photos::file_name, rule.text()?.to_string()),
+        (SmartField::MimeType, _) => compare!(photos::mime_type, rule.text()?.to_string()),
+        (SmartField::CameraModel, _) => compare!(photos::camera_model, rule.text()?.to_string()),
+        (SmartField::LensModel, _) => compare!(photos::lens_model, rule.text()?.to_string()),
+        // Photos without a label never match
+        (SmartField::ColorLabel, _) => compare!(photos::color_label.assume_not_null(), rule.text()?.to_lowercase()),
+        (SmartField::PhotoDate, _) => compare!(photos::photo_date, rule.date()?),
+        (SmartField::Iso, _) => compare!(photos::iso, integer!(i32)),
+        (SmartField::FocalLength, _) => compare!(photos::focal_length, integer!(i16)),
+        (SmartField::ShutterCount, _) => compare!(photos::shutter_count, integer!(i32)),
+        (SmartField::Rating, _) => compare!(photos::rating, integer!(i8)),
+        (SmartField::PickFlag, _) => compare!(photos::pick_flag, integer!(i8)),
+        (SmartField::Aperture, _) => compare!(photos::aperture, rule.decimal()? as f32),
+    })
+}
+
+/// Builds the SQL condition of a smart album query, joining its rules with `AND` or `OR`
+fn smart_condition(query: &SmartQuery) -> anyhow::Result<PhotoCondition> {
+    let mut conditions = query.rules.iter().map(rule_condition);
+    let first = conditions.next().ok_or_else(|| anyhow::anyhow!("A smart album needs at least one rule"))??;
+
+    conditions.try_fold(first, |combined, condition| {
+        Ok(match query.match_mode {
+            SmartMatch::All => Box::new(combined.and(condition?)) as PhotoCondition,
+            SmartMatch::Any => Box::new(combined.or(condition?)),
+        })
+    })
+}
+
+/// Retrieves all photos matching a smart album query, evaluated live
+///
+/// # Arguments
+/// * `conn` - Database connection pool
+/// * `query` - The smart album's query
+/// * `filter` - Listing filter to apply on top (use `PhotoFilter::default()` for all matching photos)
+///
+/// # Returns
+/// Vec of all matching photos, or error if the query is invalid or fails
+pub fn get_photos_in_smart_album(conn: &mut MysqlConnection, query: &SmartQuery, filter: &PhotoFilter) -> Result<Vec<Photo>, Error> {
+    let condition = smart_condition(query).map_err(|e| Error::QueryBuilderError(e.into()))?;
+
+    sorted_page(filtered_photos(filter), filter)
+        .filter(condition)
         .load::<Photo>(conn)
 }
@@ -0,0 +1,92 @@
+use crate::db::schema::smart_albums;
+use crate::models::smart_album::{NewSmartAlbum, SmartAlbum};
+use diesel::insert_into;
+use diesel::mysql::MysqlConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+
+/// Retrieves all smart albums, sorted by name
+///
+/// # Arguments
+/// * `conn` - Database connection
+///
+/// # Returns
+/// Vec<SmartAlbum> of all smart albums
+pub fn get_all_smart_albums(conn: &mut MysqlConnection) -> Result<Vec<SmartAlbum>, Error> {
+    smart_albums::table
+        .order(smart_albums::album_name.asc())
+        .select(SmartAlbum::as_select())
+        .load(conn)
+}
+
+/// Gets a single smart album
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `smart_album_id` - ID of the smart album
+///
+/// # Returns
+/// The smart album, or `NotFound` if it doesn't exist
+pub fn get_smart_album(conn: &mut MysqlConnection, smart_album_id: i32) -> Result<SmartAlbum, Error> {
+    smart_albums::table
+        .find(smart_album_id)
+        .select(SmartAlbum::as_select())
+        .first(conn)
+}
+
+/// Creates a smart album
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `smart_album` - Name and query of the new smart album
+///
+/// # Returns
+/// Ok(id) with the ID of the new smart album, or an error if the insert fails
+pub fn create_smart_album(conn: &mut MysqlConnection, smart_album: NewSmartAlbum) -> Result<i32, Error> {
+    insert_into(smart_albums::table)
+        .values(&smart_album)
+        .execute(conn)?;
+
+    diesel::select(diesel::dsl::sql::<diesel::sql_types::Integer>("LAST_INSERT_ID()"))
+        .get_result(conn)
+}
+
+/// Changes the name and/or query of a smart album
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `smart_album_id` - ID of the smart album
+/// * `album_name` - New name, or `None` to keep it
+/// * `query` - New query as JSON, or `None` to keep it
+///
+/// # Returns
+/// The number of rows affected (1 if successful, 0 if the smart album doesn't exist or nothing changed)
+pub fn update_smart_album(conn: &mut MysqlConnection, smart_album_id: i32, album_name: Option<&str>, query: Option<&str>) -> Result<usize, Error> {
+    conn.transaction(|conn| {
+        let mut rows = 0;
+        if let Some(album_name) = album_name {
+            rows = diesel::update(smart_albums::table.find(smart_album_id))
+                .set(smart_albums::album_name.eq(album_name))
+                .execute(conn)?;
+        }
+        if let Some(query) = query {
+            rows = rows.max(diesel::update(smart_albums::table.find(smart_album_id))
+                .set(smart_albums::query.eq(query))
+                .execute(conn)?);
+        }
+        Ok(rows)
+    })
+}
+
+/// Deletes a smart album. The photos it lists are not affected.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `smart_album_id` - ID of the smart album
+///
+/// # Returns
+/// The number of rows affected (1 if successful, 0 if the smart album doesn't exist)
+pub fn delete_smart_album(conn: &mut MysqlConnection, smart_album_id: i32) -> Result<usize, Error> {
+    diesel::delete(smart_albums::table.find(smart_album_id))
+        .execute(conn)
+}
@@ -0,0 +1,145 @@
+use crate::db::schema::{photos, stacks};
+use crate::models::photo::Photo;
+use crate::models::stack::{pick_cover, NewStack, Stack, StackKind};
+use chrono::Local;
+use diesel::dsl::sql;
+use diesel::mysql::MysqlConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::sql_types::Integer;
+
+/// Creates a stack and adds the given photos to it
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `kind` - Kind of the stack
+/// * `photo_ids` - Photos to stack. Photos already in another stack are moved to this one.
+/// * `cover_id` - Photo to show in place of the stack
+///
+/// # Returns
+/// The ID of the new stack, or an error if a query fails
+pub fn create_stack(conn: &mut MysqlConnection, kind: StackKind, photo_ids: &[i64], cover_id: Option<i64>) -> Result<i32, Error> {
+    diesel::insert_into(stacks::table)
+        .values(&NewStack {
+            kind: kind.as_str().to_string(),
+            cover_id,
+            created_at: Local::now().naive_local(),
+        })
+        .execute(conn)?;
+    let stack_id = diesel::select(sql::<Integer>("LAST_INSERT_ID()")).get_result::<i32>(conn)?;
+
+    diesel::update(photos::table.filter(photos::id.eq_any(photo_ids)))
+        .set(photos::stack_id.eq(stack_id))
+        .execute(conn)?;
+
+    Ok(stack_id)
+}
+
+/// Gets a single stack
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `stack_id` - ID of the stack
+///
+/// # Returns
+/// The stack, or `NotFound` if it doesn't exist
+pub fn get_stack(conn: &mut MysqlConnection, stack_id: i32) -> Result<Stack, Error> {
+    stacks::table
+        .find(stack_id)
+        .select(Stack::as_select())
+        .first(conn)
+}
+
+/// Gets the photos of a stack that are not in the trash
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `stack_id` - ID of the stack
+///
+/// # Returns
+/// Vec<Photo> in capture order
+pub fn get_stack_photos(conn: &mut MysqlConnection, stack_id: i32) -> Result<Vec<Photo>, Error> {
+    photos::table
+        .filter(photos::stack_id.eq(stack_id))
+        .filter(photos::deleted_at.is_null())
+        .order((photos::photo_date.asc(), photos::id.asc()))
+        .load::<Photo>(conn)
+}
+
+/// Sets the cover of a stack
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `stack_id` - ID of the stack
+/// * `cover_id` - The new cover, or `None` if the stack has no photos left outside the trash
+///
+/// # Returns
+/// The number of rows affected (1 if successful, 0 if the stack doesn't exist)
+pub fn set_stack_cover(conn: &mut MysqlConnection, stack_id: i32, cover_id: Option<i64>) -> Result<usize, Error> {
+    diesel::update(stacks::table.find(stack_id))
+        .set(stacks::cover_id.eq(cover_id))
+        .execute(conn)
+}
+
+/// Dissolves a stack. Its photos are kept, but no longer stacked.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `stack_id` - ID of the stack
+///
+/// # Returns
+/// The number of stacks deleted (1 if successful, 0 if the stack doesn't exist)
+pub fn delete_stack(conn: &mut MysqlConnection, stack_id: i32) -> Result<usize, Error> {
+    diesel::update(photos::table.filter(photos::stack_id.eq(stack_id)))
+        .set(photos::stack_id.eq(None::<i32>))
+        .execute(conn)?;
+
+    diesel::delete(stacks::table.find(stack_id))
+        .execute(conn)
+}
+
+/// Gets all photos outside the trash that are not in a stack, as candidates for the stacking pass.
+/// Copies made with `copy_photo` are left out, as they would always be stacked with their original.
+///
+/// # Arguments
+/// * `conn` - Database connection
+///
+/// # Returns
+/// Vec<Photo> ordered by camera model, then capture time
+pub fn get_unstacked_photos(conn: &mut MysqlConnection) -> Result<Vec<Photo>, Error> {
+    photos::table
+        .filter(photos::stack_id.is_null())
+        .filter(photos::deleted_at.is_null())
+        .filter(photos::original_id.is_null())
+        .order((photos::camera_model.asc(), photos::photo_date.asc(), photos::id.asc()))
+        .load::<Photo>(conn)
+}
+
+/// Picks a new cover for the stacks of the given photos whose cover is missing or in the trash.
+/// Call this after photos were trashed or restored.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `photo_ids` - Photos that were trashed or restored
+///
+/// # Returns
+/// Ok, or an error if a query fails
+pub fn refresh_stack_covers(conn: &mut MysqlConnection, photo_ids: &[i64]) -> Result<(), Error> {
+    let stack_ids = photos::table
+        .filter(photos::id.eq_any(photo_ids))
+        .filter(photos::stack_id.is_not_null())
+        .select(photos::stack_id.assume_not_null())
+        .distinct()
+        .load::<i32>(conn)?;
+
+    for stack_id in stack_ids {
+        let stack = get_stack(conn, stack_id)?;
+        let stack_photos = get_stack_photos(conn, stack_id)?;
+        if stack.cover_id.is_some_and(|cover_id| stack_photos.iter().any(|photo| photo.id == cover_id)) {
+            continue;
+        }
+        set_stack_cover(conn, stack_id, pick_cover(&stack.kind, &stack_photos))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,243 @@
+use crate::db::operations::join_photo_tag::add_tag_to_photo;
+use crate::db::schema::{photo_tag_join, tags};
+use crate::models::tag::{NewTag, Tag, TAG_SEPARATOR};
+use diesel::insert_into;
+use diesel::prelude::*;
+use diesel::result::Error::DatabaseError;
+use diesel::result::{DatabaseErrorKind, Error};
+use diesel::MysqlConnection;
+use std::collections::HashSet;
+
+/// Gets every tag in the database
+///
+/// # Arguments
+/// * `conn` - Database connection
+///
+/// # Returns
+/// All tags, ordered by name, or an error if the query fails
+pub fn get_all_tags(conn: &mut MysqlConnection) -> Result<Vec<Tag>, Error> {
+    tags::table
+        .order(tags::tag_name.asc())
+        .load::<Tag>(conn)
+}
+
+/// Gets tags by their IDs
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `tag_ids` - List of tag IDs to retrieve
+///
+/// # Returns
+/// Vector of tags matching the provided IDs, or an error if query fails
+pub fn get_tag(conn: &mut MysqlConnection, tag_ids: &[i32]) -> Result<Vec<Tag>, Error> {
+    if tag_ids.is_empty() { return Ok(vec![]); }
+
+    tags::table
+        .filter(tags::id.eq_any(tag_ids))
+        .load::<Tag>(conn)
+}
+
+/// Finds the tag with the given name directly under `parent_id`
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `tag_name` - Name of the tag to find
+/// * `parent_id` - ID of the parent tag, or `None` to search root keywords
+///
+/// # Returns
+/// The matching tag if it exists
+pub fn find_tag(conn: &mut MysqlConnection, tag_name: &str, parent_id: Option<i32>) -> Result<Option<Tag>, Error> {
+    let query = tags::table
+        .filter(tags::tag_name.eq(tag_name))
+        .into_boxed();
+
+    let query = match parent_id {
+        Some(parent_id) => query.filter(tags::parent_id.eq(parent_id)),
+        None => query.filter(tags::parent_id.is_null()),
+    };
+
+    query.first::<Tag>(conn).optional()
+}
+
+/// Creates a new tag in the database
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `tag` - Name and parent of the new tag
+///
+/// # Returns
+/// Ok(id) with the ID of the new tag, or an error if the insert fails (e.g. a sibling with the same name exists)
+pub fn create_tag(conn: &mut MysqlConnection, tag: NewTag) -> Result<i32, Error> {
+    insert_into(tags::table)
+        .values(&tag)
+        .execute(conn)?;
+
+    diesel::select(diesel::dsl::sql::<diesel::sql_types::Integer>("LAST_INSERT_ID()"))
+        .get_result(conn)
+}
+
+/// Resolves a hierarchical keyword to a tag, creating any levels of the hierarchy that don't exist yet
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `segments` - Levels of the keyword from root to leaf (e.g. `["Places", "Japan", "Kyoto"]`)
+///
+/// # Returns
+/// The ID of the leaf tag. If creating a level fails, none of the levels are created.
+pub fn find_or_create_tag_path<S: AsRef<str>>(conn: &mut MysqlConnection, segments: &[S]) -> Result<i32, Error> {
+    conn.transaction(|conn| {
+        let mut parent_id: Option<i32> = None;
+
+        for segment in segments {
+            let segment = segment.as_ref();
+            let tag_id = match find_tag(conn, segment, parent_id)? {
+                Some(tag) => tag.id,
+                None => create_tag(conn, NewTag { tag_name: segment.to_string(), parent_id })?,
+            };
+            parent_id = Some(tag_id);
+        }
+
+        parent_id.ok_or(Error::NotFound)
+    })
+}
+
+/// Gets the full hierarchical keyword of a tag, e.g. `Places|Japan|Kyoto`
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `tag_id` - ID of the tag
+///
+/// # Returns
+/// The keyword path; Returns `diesel::result::Error::Unknown` if a cyclical hierarchy is detected
+pub fn get_tag_path(conn: &mut MysqlConnection, tag_id: i32) -> Result<String, Error> {
+    let mut segments: Vec<String> = Vec::new();
+    let mut current_id: Option<i32> = Some(tag_id);
+    let mut seen: HashSet<i32> = HashSet::new();
+
+    while let Some(tid) = current_id {
+        // Check for cycles (shouldn't happen, but just in case)
+        if !seen.insert(tid) {
+            return Err(DatabaseError(DatabaseErrorKind::Unknown, Box::new("A cycle is detected in the tag hierarchy. This should never happen unless the table is corrupted!".to_string())));
+        }
+
+        let (name, parent) = tags::table
+            .find(tid)
+            .select((tags::tag_name, tags::parent_id))
+            .first::<(String, Option<i32>)>(conn)?;
+        segments.push(name);
+        current_id = parent;
+    }
+
+    // Segments were collected leaf->root, so reverse
+    segments.reverse();
+    Ok(segments.join(&TAG_SEPARATOR.to_string()))
+}
+
+/// Gets the IDs of a tag and all tags nested below it
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `tag_id` - ID of the root of the subtree
+///
+/// # Returns
+/// `tag_id` followed by the IDs of all its descendants
+pub fn get_tag_descendants(conn: &mut MysqlConnection, tag_id: i32) -> Result<Vec<i32>, Error> {
+    let mut result = vec![tag_id];
+    let mut frontier = vec![tag_id];
+
+    while !frontier.is_empty() {
+        frontier = tags::table
+            .filter(tags::parent_id.eq_any(&frontier))
+            .select(tags::id)
+            .load::<i32>(conn)?
+            .into_iter()
+            .filter(|id| !result.contains(id))
+            .collect();
+        result.extend(&frontier);
+    }
+
+    Ok(result)
+}
+
+/// Renames a tag, keeping its position in the hierarchy
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `tag_id` - ID of the tag to rename
+/// * `tag_name` - New name of the tag
+///
+/// # Returns
+/// The number of rows affected (1 if successful, 0 if the tag doesn't exist)
+pub fn rename_tag(conn: &mut MysqlConnection, tag_id: i32, tag_name: &str) -> Result<usize, Error> {
+    diesel::update(tags::table.find(tag_id))
+        .set(tags::tag_name.eq(tag_name))
+        .execute(conn)
+}
+
+/// Merges one tag into another.
+///
+/// Photos tagged with `source_id` are tagged with `target_id` instead, and children of the source
+/// are moved under the target (recursively merging children with the same name). The source is then deleted.
+///
+/// `target_id` must not be `source_id` or one of its descendants.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `source_id` - ID of the tag to merge and remove
+/// * `target_id` - ID of the tag to merge into
+///
+/// # Returns
+/// Ok if the merge was applied, or an error (in which case nothing is changed)
+pub fn merge_tags(conn: &mut MysqlConnection, source_id: i32, target_id: i32) -> Result<(), Error> {
+    conn.transaction(|conn| merge_tags_recurse(conn, source_id, target_id))
+}
+
+fn merge_tags_recurse(conn: &mut MysqlConnection, source_id: i32, target_id: i32) -> Result<(), Error> {
+    // Re-point photo associations
+    let photo_ids = photo_tag_join::table
+        .filter(photo_tag_join::tag_id.eq(source_id))
+        .select(photo_tag_join::photo_id)
+        .load::<i64>(conn)?;
+    add_tag_to_photo(conn, &[target_id], &photo_ids)?;
+
+    // Move or merge children
+    let children = tags::table
+        .filter(tags::parent_id.eq(source_id))
+        .load::<Tag>(conn)?;
+    for child in children {
+        match find_tag(conn, &child.tag_name, Some(target_id))? {
+            Some(existing) => merge_tags_recurse(conn, child.id, existing.id)?,
+            None => {
+                diesel::update(tags::table.find(child.id))
+                    .set(tags::parent_id.eq(target_id))
+                    .execute(conn)?;
+            }
+        }
+    }
+
+    // Delete the now-empty source tag (its photo associations cascade)
+    diesel::delete(tags::table.find(source_id)).execute(conn)?;
+    Ok(())
+}
+
+/// Deletes a tag along with every tag nested below it
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `tag_id` - ID of the tag to delete
+///
+/// # Returns
+/// The deleted `Tag` if found, or an error if the tag doesn't exist
+pub fn delete_tag(conn: &mut MysqlConnection, tag_id: i32) -> Result<Tag, Error> {
+    let tag = tags::table.find(tag_id).first::<Tag>(conn)?;
+
+    // Child tags have to go before their parents; photo associations are removed by `ON DELETE CASCADE`
+    conn.transaction(|conn| {
+        for descendant in get_tag_descendants(conn, tag_id)?.into_iter().rev() {
+            diesel::delete(tags::table.find(descendant)).execute(conn)?;
+        }
+        Ok::<_, Error>(())
+    })?;
+
+    Ok(tag)
+}
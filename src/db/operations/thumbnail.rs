@@ -57,3 +57,30 @@ pub fn delete_thumbnail(conn: &mut MysqlConnection, photo_ids: &[i64]) -> Result
     diesel::delete(thumbnails.filter(id.eq_any(photo_ids))).execute(conn)
 }
 
+/// Gets every thumbnail entry in the database
+///
+/// # Arguments
+/// * `conn` - Database connection
+///
+/// # Returns
+/// Vec<Thumbnail> of all thumbnail entries
+pub fn get_all_thumbnails(conn: &mut MysqlConnection) -> Result<Vec<Thumbnail>, Error> {
+    thumbnails.load::<Thumbnail>(conn)
+}
+
+/// Checks whether a thumbnail file is also used by another photo, as is the case for copies made with `copy_photo`
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `thumb` - Thumbnail entry of the photo
+///
+/// # Returns
+/// `true` if another thumbnail entry has the same path, so the file must be kept
+pub fn is_thumbnail_shared(conn: &mut MysqlConnection, thumb: &Thumbnail) -> Result<bool, Error> {
+    diesel::select(diesel::dsl::exists(
+        thumbnails
+            .filter(thumbnail_path.eq(&thumb.thumbnail_path))
+            .filter(id.ne(thumb.id))
+    ))
+    .get_result(conn)
+}
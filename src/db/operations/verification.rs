@@ -0,0 +1,60 @@
+use crate::db::schema::{photo_verifications, photos};
+use crate::models::photo::Photo;
+use crate::models::verification::{PhotoVerification, VerificationStatus};
+use diesel::insert_into;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::MysqlConnection;
+
+/// Gets the IDs of all photos, ordered from least to most recently verified.
+/// Photos that were never verified come first.
+///
+/// # Arguments
+/// * `conn` - Database connection
+///
+/// # Returns
+/// Vec of photo IDs in verification order
+pub fn get_photos_by_verification_age(conn: &mut MysqlConnection) -> Result<Vec<i64>, Error> {
+    photos::table
+        .left_outer_join(photo_verifications::table)
+        // NULLs sort first in ascending order on MySQL
+        .order((photo_verifications::verified_at.asc(), photos::id.asc()))
+        .select(photos::id)
+        .load::<i64>(conn)
+}
+
+/// Stores the result of a photo's integrity check, replacing the previous one
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `verification` - Result to store, keyed by photo ID
+///
+/// # Returns
+/// Number of rows affected
+pub fn set_photo_verification(conn: &mut MysqlConnection, verification: &PhotoVerification) -> Result<usize, Error> {
+    insert_into(photo_verifications::table)
+        .values(verification)
+        .on_conflict(diesel::dsl::DuplicatedKeys)
+        .do_update()
+        .set((
+            photo_verifications::verified_at.eq(verification.verified_at),
+            photo_verifications::status.eq(&verification.status),
+        ))
+        .execute(conn)
+}
+
+/// Gets every photo whose last integrity check failed, paired with the result of that check
+///
+/// # Arguments
+/// * `conn` - Database connection
+///
+/// # Returns
+/// Vec of `(verification, photo)` pairs, most recently verified first
+pub fn get_failed_verifications(conn: &mut MysqlConnection) -> Result<Vec<(PhotoVerification, Photo)>, Error> {
+    photo_verifications::table
+        .inner_join(photos::table)
+        .filter(photo_verifications::status.ne(VerificationStatus::Ok.as_str()))
+        .order(photo_verifications::verified_at.desc())
+        .select((PhotoVerification::as_select(), photos::all_columns))
+        .load::<(PhotoVerification, Photo)>(conn)
+}
@@ -0,0 +1,89 @@
+use diesel::r2d2::ConnectionManager;
+use diesel::{Connection, MysqlConnection};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::tokio;
+use std::env;
+use std::time::Duration;
+
+pub type Pool = r2d2::Pool<ConnectionManager<MysqlConnection>>;
+
+/// Number of connections kept in the pool, unless `$DATABASE_POOL_SIZE` is set
+const DEFAULT_POOL_SIZE: u32 = 10;
+
+/// Seconds to wait for a free connection before giving up, unless `$DATABASE_TIMEOUT_SECS` is set
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// Seconds an unused connection is kept open, unless `$DATABASE_IDLE_TIMEOUT_SECS` is set
+const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 600;
+
+/// Reads a positive number from an environment variable, falling back to `default` if it is unset or invalid
+fn env_or<T: std::str::FromStr + PartialOrd + Default>(key: &str, default: T) -> T {
+    env::var(key)
+        .ok()
+        .and_then(|value| value.trim().parse::<T>().ok())
+        .filter(|value| *value > T::default())
+        .unwrap_or(default)
+}
+
+/// Establishes the database connection pool for `$DATABASE_URL`.
+///
+/// Its size and timeouts are read from `$DATABASE_POOL_SIZE`, `$DATABASE_TIMEOUT_SECS` and
+/// `$DATABASE_IDLE_TIMEOUT_SECS`. The web server manages the pool as Rocket state (see `Db`);
+/// subcommands use a single connection instead (see `establish_connection`).
+pub fn establish_connection_pool() -> Pool {
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let manager = ConnectionManager::<MysqlConnection>::new(database_url);
+    Pool::builder()
+        .max_size(env_or("DATABASE_POOL_SIZE", DEFAULT_POOL_SIZE))
+        .connection_timeout(Duration::from_secs(env_or("DATABASE_TIMEOUT_SECS", DEFAULT_TIMEOUT_SECS)))
+        .idle_timeout(Some(Duration::from_secs(env_or("DATABASE_IDLE_TIMEOUT_SECS", DEFAULT_IDLE_TIMEOUT_SECS))))
+        .build(manager)
+        .expect("Failed to create pool")
+}
+
+/// Opens a single connection to `$DATABASE_URL`, for subcommands that don't need a pool
+pub fn establish_connection() -> MysqlConnection {
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    MysqlConnection::establish(&database_url).expect("Failed to connect to the database")
+}
+
+/// Access to the database from a request handler
+///
+/// As a request guard, it is taken from the `Pool` managed as Rocket state. Diesel is blocking, so
+/// queries must go through `Db::run` instead of running on the async workers.
+#[derive(Clone)]
+pub struct Db(Pool);
+
+impl Db {
+    /// Runs `f` with a connection from the pool on Tokio's blocking thread pool
+    ///
+    /// # Arguments
+    /// * `f` - Closure doing the database work
+    ///
+    /// # Returns
+    /// What `f` returned, or an error if no connection became free within the timeout (or `f` panicked)
+    pub async fn run<F, R>(&self, f: F) -> anyhow::Result<R>
+    where
+        F: FnOnce(&mut MysqlConnection) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let pool = self.0.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get()?;
+            Ok(f(&mut conn))
+        }).await?
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Db {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match request.rocket().state::<Pool>() {
+            Some(pool) => Outcome::Success(Db(pool.clone())),
+            None => Outcome::Error((Status::InternalServerError, ())),
+        }
+    }
+}
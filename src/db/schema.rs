@@ -11,6 +11,7 @@ diesel::table! {
     album_photo_join (parent_id, photo_id) {
         parent_id -> Integer,
         photo_id -> Bigint,
+        position -> Integer,
     }
 }
 
@@ -19,6 +20,86 @@ diesel::table! {
         id -> Integer,
         #[max_length = 255]
         album_name -> Varchar,
+        deleted_at -> Nullable<Datetime>,
+        description -> Nullable<Text>,
+        cover_id -> Nullable<Bigint>,
+        created_at -> Datetime,
+        updated_at -> Datetime,
+        sort_position -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    audit_log (id) {
+        id -> Bigint,
+        created_at -> Datetime,
+        #[max_length = 255]
+        actor -> Varchar,
+        #[max_length = 32]
+        action -> Varchar,
+        photo_ids -> Longtext,
+        album_ids -> Longtext,
+        before_state -> Nullable<Longtext>,
+        after_state -> Nullable<Longtext>,
+        #[max_length = 16]
+        result -> Varchar,
+        error -> Nullable<Text>,
+        undone_by -> Nullable<Bigint>,
+    }
+}
+
+diesel::table! {
+    collection_photo_join (collection_id, photo_id) {
+        collection_id -> Integer,
+        photo_id -> Bigint,
+        position -> Integer,
+    }
+}
+
+diesel::table! {
+    collections (id) {
+        id -> Integer,
+        #[max_length = 255]
+        collection_name -> Varchar,
+        created_at -> Datetime,
+    }
+}
+
+diesel::table! {
+    photo_metadata (id) {
+        id -> Bigint,
+        metadata -> Longtext,
+    }
+}
+
+diesel::table! {
+    photo_metadata_issues (photo_id, issue) {
+        photo_id -> Bigint,
+        #[max_length = 32]
+        issue -> Varchar,
+    }
+}
+
+diesel::table! {
+    photo_phashes (id) {
+        id -> Bigint,
+        phash -> Bigint,
+    }
+}
+
+diesel::table! {
+    photo_tag_join (tag_id, photo_id) {
+        tag_id -> Integer,
+        photo_id -> Bigint,
+    }
+}
+
+diesel::table! {
+    photo_verifications (id) {
+        id -> Bigint,
+        verified_at -> Datetime,
+        #[max_length = 16]
+        status -> Varchar,
     }
 }
 
@@ -45,6 +126,45 @@ diesel::table! {
         iso -> Integer,
         shutter_speed -> Varchar,
         aperture -> Float,
+        latitude -> Nullable<Double>,
+        longitude -> Nullable<Double>,
+        altitude -> Nullable<Float>,
+        rating -> Tinyint,
+        #[max_length = 16]
+        color_label -> Nullable<Varchar>,
+        pick_flag -> Tinyint,
+        deleted_at -> Nullable<Datetime>,
+        stack_id -> Nullable<Integer>,
+        original_id -> Nullable<Bigint>,
+    }
+}
+
+diesel::table! {
+    smart_albums (id) {
+        id -> Integer,
+        #[max_length = 255]
+        album_name -> Varchar,
+        query -> Longtext,
+        created_at -> Datetime,
+    }
+}
+
+diesel::table! {
+    stacks (id) {
+        id -> Integer,
+        #[max_length = 16]
+        kind -> Varchar,
+        cover_id -> Nullable<Bigint>,
+        created_at -> Datetime,
+    }
+}
+
+diesel::table! {
+    tags (id) {
+        id -> Integer,
+        #[max_length = 255]
+        tag_name -> Varchar,
+        parent_id -> Nullable<Integer>,
     }
 }
 
@@ -57,12 +177,32 @@ diesel::table! {
 
 diesel::joinable!(album_photo_join -> albums (parent_id));
 diesel::joinable!(album_photo_join -> photos (photo_id));
+diesel::joinable!(collection_photo_join -> collections (collection_id));
+diesel::joinable!(collection_photo_join -> photos (photo_id));
+diesel::joinable!(photo_metadata -> photos (id));
+diesel::joinable!(photo_metadata_issues -> photos (photo_id));
+diesel::joinable!(photo_phashes -> photos (id));
+diesel::joinable!(photo_tag_join -> photos (photo_id));
+diesel::joinable!(photo_tag_join -> tags (tag_id));
+diesel::joinable!(photo_verifications -> photos (id));
+diesel::joinable!(photos -> stacks (stack_id));
 diesel::joinable!(thumbnails -> photos (id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     album_album_join,
     album_photo_join,
     albums,
+    audit_log,
+    collection_photo_join,
+    collections,
+    photo_metadata,
+    photo_metadata_issues,
+    photo_phashes,
+    photo_tag_join,
+    photo_verifications,
     photos,
+    smart_albums,
+    stacks,
+    tags,
     thumbnails,
 );
@@ -0,0 +1,44 @@
+use crate::fs_operations::journal::FsJournal;
+use diesel::{Connection, MysqlConnection};
+
+/// Runs `work` as a single unit: its database changes run in one transaction, and its filesystem
+/// changes are recorded in an `FsJournal`.
+///
+/// If `work` returns an error, the transaction is rolled back and the journal restores the previous
+/// state on disk. Otherwise the transaction is committed, and then the journal carries out its
+/// scheduled deletions.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `work` - Closure doing the actual changes. Filesystem changes must go through the journal.
+///
+/// # Returns
+/// The result of `work`, or its error (with a note if the filesystem could not be fully restored)
+///
+/// # Example
+/// ```no_run
+/// unit_of_work(&mut conn, |conn, journal| {
+///     move_photo_fs(journal, &photo_path, &dest_path)?;
+///     add_photo_to_album(conn, album_id, &[photo_id])?;
+///     Ok(())
+/// })?;
+/// ```
+pub fn unit_of_work<T, F>(conn: &mut MysqlConnection, work: F) -> anyhow::Result<T>
+where
+    F: FnOnce(&mut MysqlConnection, &mut FsJournal) -> anyhow::Result<T>,
+{
+    let mut journal = FsJournal::new();
+
+    match conn.transaction(|conn| work(conn, &mut journal)) {
+        Ok(value) => {
+            for e in journal.commit() {
+                println!("Error cleaning up after a committed change: {e}");
+            }
+            Ok(value)
+        }
+        Err(e) => match journal.rollback() {
+            Ok(()) => Err(e),
+            Err(rollback_error) => Err(anyhow::anyhow!("{e} (files could not be fully restored: {rollback_error}, run `fsck` to find leftovers)")),
+        },
+    }
+}
@@ -1,44 +1,91 @@
+use crate::_utils::actor::Actor;
 use crate::_utils::json_map::JsonMap;
-use crate::db::operations::album::{create_album, delete_album, get_album, get_root_albums, rename_album as rename_album_db};
+use crate::audit::{record_operation, take_snapshot};
+use crate::db::operations::album::{create_album, find_child_album, get_album, get_album_details, get_root_albums, rename_album as rename_album_db, set_album_trashed, update_album_metadata};
+use crate::db::operations::join_album_album::{add_album_to_album, remove_album_from_album};
+use crate::db::operations::join_album_photo::{get_album_photo_ids, remove_photo_from_album, set_album_order};
 use crate::db::operations::paths::get_album_path;
 use crate::db::operations::query::{get_albums_in_album, get_photos_in_album, get_photos_unfiled};
-use crate::fs_operations::album::{create_album_fs, delete_album_fs, move_album_fs};
-use crate::models::album::{Album, NewAlbum};
+use crate::db::operations::smart_album::get_all_smart_albums;
+use crate::db::pool::Db;
+use crate::db::unit_of_work::unit_of_work;
+use crate::fs_operations::album::{create_album_fs, move_album_fs, trash_album_fs};
+use crate::models::album::{AlbumChanges, AlbumDetails, AlbumName, NewAlbum};
+use crate::models::audit::AuditAction;
+use crate::models::filter::{AlbumListing, PhotoFilter};
 use crate::models::photo::Photo;
-use crate::{msg, unwrap_err, unwrap_ret, DB_POOL};
+use crate::models::smart_album::SmartAlbum;
+use crate::{msg, unwrap_err, unwrap_ret};
+use chrono::Local;
 use diesel::result::Error;
 use rocket::http::Status;
 use rocket::serde::json::{Json, Value};
-use rocket::{delete, get, patch, post};
+use rocket::{delete, get, patch, post, put};
+use serde_json::json;
+use std::collections::HashSet;
+use std::io::ErrorKind;
+use std::path::PathBuf;
 
-/// Creates a new "root" album at `$STORAGE_ROOT`
+/// Creates a new album, either at `$STORAGE_ROOT` or inside a parent album
+///
+/// The directory and the album (including its link to the parent) are created in one operation.
 ///
 /// # Endpoint
 /// `POST /album/new`
 ///
 /// # Request Body
 /// JSON object with:
-/// - `album_name`: Name for the new album (String)
+/// - `album_name`: Name for the new album (String), see `AlbumName` for what is allowed
+/// - `parent_id` (optional): The ID of the album to create it in (i32); a root album is created if omitted
 ///
 /// # Returns
-/// - `201 Created`: Album was successfully created
-/// - `400 Bad Request`: Missing or invalid album_name in request body
+/// - `201 Created`: Album was successfully created; body contains its `albumId`
+/// - `400 Bad Request`: Missing or invalid album_name or parent_id in request body (e.g. a name containing `/` or `..`)
+/// - `404 Not Found`: Parent album with the specified ID does not exist
+/// - `409 Conflict`: The parent already has an album or a file with the same name
 /// - `500 Internal Server Error`: Database or other server error occurred
 #[post("/album/new", format = "json", data = "<input>")]
-pub fn new_album(input: Json<Value>) -> (Status, Json<Value>) {
-    let album_name = unwrap_ret!(input.get_value::<String>("album_name"), Status::BadRequest);
-    let mut conn = unwrap_ret!(DB_POOL.get(), Status::InternalServerError);
-
-    // Create the album directory in the filesystem
-    unwrap_ret!(create_album_fs(&album_name), Status::InternalServerError);
-
-    // Create albums and return the appropriate response based off the number of rows created
-    let rows = unwrap_ret!(create_album(&mut conn, NewAlbum {album_name: album_name.to_string()}), Status::InternalServerError);
-    match rows {
-        1 => (Status::Created, msg!("Success")),
-        0 => (Status::Conflict, msg!("Album already exists")),
-        _ => (Status::InternalServerError, msg!("Something went horribly wrong. The database is probably corrupted (Database transacted > 1 rows)")),
-    }
+pub async fn new_album(db: Db, actor: Actor, input: Json<Value>) -> (Status, Json<Value>) {
+    let album_name = unwrap_ret!(input.get_value::<AlbumName>("album_name"), Status::BadRequest);
+    let parent_id = unwrap_ret!(input.get_optional::<i32>("parent_id"), Status::BadRequest);
+    unwrap_ret!(db.run(move |conn| {
+        let parent_path = match parent_id {
+            Some(parent_id) => {
+                if unwrap_ret!(get_album(conn, &[parent_id]), Status::InternalServerError).is_empty() {
+                    return (Status::NotFound, msg!("Parent album not found"));
+                }
+                unwrap_ret!(get_album_path(conn, parent_id), Status::InternalServerError)
+            }
+            None => PathBuf::from("/"),
+        };
+        if unwrap_ret!(find_child_album(conn, parent_id, &album_name), Status::InternalServerError).is_some() {
+            return (Status::Conflict, msg!("An album named {} already exists there", album_name));
+        }
+
+        let result = unit_of_work(conn, |conn, journal| {
+            // Create the album directory in the filesystem
+            create_album_fs(journal, &parent_path, &album_name)?;
+
+            // Create the album and link it to its parent
+            let now = Local::now().naive_local();
+            let album_id = create_album(conn, NewAlbum {album_name: album_name.to_string(), created_at: now, updated_at: now})?;
+            if let Some(parent_id) = parent_id {
+                add_album_to_album(conn, parent_id, &[album_id])?;
+            }
+            Ok(album_id)
+        });
+        let mut album_ids: Vec<i32> = result.iter().copied().collect();
+        album_ids.extend(parent_id);
+        record_operation(conn, &actor, AuditAction::AlbumNew, &[], &album_ids, None, &result);
+
+        match result {
+            Ok(album_id) => (Status::Created, Json(json!({ "message": "Success", "albumId": album_id }))),
+            Err(err) if err.downcast_ref::<std::io::Error>().is_some_and(|e| e.kind() == ErrorKind::AlreadyExists) => {
+                (Status::Conflict, msg!("Failed to create album: {}", err))
+            }
+            Err(err) => (Status::InternalServerError, msg!("Failed to create album: {}", err)),
+        }
+    }).await, Status::InternalServerError)
 }
 
 /// Renames an existing album
@@ -51,32 +98,164 @@ pub fn new_album(input: Json<Value>) -> (Status, Json<Value>) {
 ///
 /// # Request Body
 /// JSON object with:
-/// - `album_name`: New name for the album (String)
+/// - `album_name`: New name for the album (String), see `AlbumName` for what is allowed
 ///
 /// # Returns
 /// - `200 OK`: Album was successfully renamed
-/// - `400 Bad Request`: Missing or invalid album_name in the request body
+/// - `400 Bad Request`: Missing or invalid album_name in the request body (e.g. containing `/` or `..`)
 /// - `404 Not Found`: Album with the specified ID does not exist
 /// - `500 Internal Server Error`: Database or other server error occurred
 #[patch("/album/<id>/rename", format = "json", data = "<input>")]
-pub fn rename_album(id: i32, input: Json<Value>) -> (Status, Json<Value>) {
-    let album_name = unwrap_ret!(input.get_value::<String>("album_name"), Status::BadRequest);
-    let mut conn = unwrap_ret!(DB_POOL.get(), Status::InternalServerError);
-
-    // Rename the album on disk
-    let old_path = unwrap_ret!(get_album_path(&mut conn, id), Status::InternalServerError);
-    let new_path = unwrap_ret!(old_path.parent().ok_or("Cannot rename the root directory itself!"), Status::InternalServerError).join(&album_name);
-    unwrap_ret!(move_album_fs(&old_path, &new_path), Status::InternalServerError);
-
-    // Rename the album in the DB
-    match rename_album_db(&mut conn, Album {id, album_name}) {
-        Ok(_) => (Status::Ok, msg!("Success")),
-        Err(Error::NotFound) => (Status::NotFound, msg!("Album not found")),
-        Err(err) => (Status::InternalServerError, msg!("Failed to rename album in database: {:#?}", err)),
+pub async fn rename_album(db: Db, actor: Actor, id: i32, input: Json<Value>) -> (Status, Json<Value>) {
+    let album_name = unwrap_ret!(input.get_value::<AlbumName>("album_name"), Status::BadRequest);
+    unwrap_ret!(db.run(move |conn| {
+        let old_path = unwrap_ret!(get_album_path(conn, id), Status::InternalServerError);
+        let new_path = unwrap_ret!(old_path.parent().ok_or("Cannot rename the root directory itself!"), Status::InternalServerError).join(&album_name);
+
+        let before = take_snapshot(conn, &[], &[id]);
+        let result = unit_of_work(conn, |conn, journal| {
+            // Rename the album on disk
+            move_album_fs(journal, &old_path, &new_path)?;
+
+            // Rename the album in the DB
+            Ok(rename_album_db(conn, id, &album_name)?)
+        });
+        record_operation(conn, &actor, AuditAction::AlbumRename, &[], &[id], before, &result);
+        match result {
+            Ok(_) => (Status::Ok, msg!("Success")),
+            Err(err) if matches!(err.downcast_ref::<Error>(), Some(Error::NotFound)) => (Status::NotFound, msg!("Album not found")),
+            Err(err) => (Status::InternalServerError, msg!("Failed to rename album: {}", err)),
+        }
+    }).await, Status::InternalServerError)
+}
+
+/// Retrieves a single album with its metadata
+///
+/// # Endpoint
+/// `GET /album/<id>`
+///
+/// # URL Parameters
+/// - `id`: The ID of the album (i32)
+///
+/// # Returns
+/// - `200 OK`: The album, in the form described at `GET /album/root`
+/// - `404 Not Found`: Album with the specified ID does not exist (or is trashed)
+/// - `500 Internal Server Error`: Database or another server error occurred
+#[get("/album/<id>")]
+pub async fn album_info(db: Db, id: i32) -> Result<Json<AlbumDetails>, (Status, Json<Value>)> {
+    unwrap_err!(db.run(move |conn| {
+        let album = unwrap_err!(get_album(conn, &[id]), Status::InternalServerError);
+        if album.is_empty() {
+            return Err((Status::NotFound, msg!("Album not found")));
+        }
+        let mut details = unwrap_err!(get_album_details(conn, album), Status::InternalServerError);
+        Ok(Json(details.remove(0)))
+    }).await, Status::InternalServerError)
+}
+
+/// Updates the description, cover photo and/or sort position of an album
+///
+/// # Endpoint
+/// `PATCH /album/<id>`
+///
+/// # URL Parameters
+/// - `id`: The ID of the album to update (i32)
+///
+/// # Request Body
+/// JSON object with (at least one of):
+/// - `description` (optional): New description (String), or `null` to clear it
+/// - `cover_id` (optional): Photo to use as cover (i64), which must be in the album, or `null` to use its
+///   earliest photo
+/// - `sort_position` (optional): Position among the sibling albums (i32), lower first, or `null` to list
+///   the album by name after the positioned ones
+///
+/// # Returns
+/// - `200 OK`: Album was updated
+/// - `400 Bad Request`: No field given, a field of the wrong type, or a cover that is not in the album
+/// - `404 Not Found`: Album with the specified ID does not exist
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[patch("/album/<id>", format = "json", data = "<input>")]
+pub async fn update_album(db: Db, actor: Actor, id: i32, input: Json<Value>) -> (Status, Json<Value>) {
+    let changes = AlbumChanges {
+        description: unwrap_ret!(input.get_optional::<Option<String>>("description"), Status::BadRequest),
+        cover_id: unwrap_ret!(input.get_optional::<Option<i64>>("cover_id"), Status::BadRequest),
+        sort_position: unwrap_ret!(input.get_optional::<Option<i32>>("sort_position"), Status::BadRequest),
+        updated_at: Local::now().naive_local(),
+    };
+    if changes.description.is_none() && changes.cover_id.is_none() && changes.sort_position.is_none() {
+        return (Status::BadRequest, msg!("Nothing to update: give description, coverId and/or sortPosition"));
     }
+    unwrap_ret!(db.run(move |conn| {
+        if unwrap_ret!(get_album(conn, &[id]), Status::InternalServerError).is_empty() {
+            return (Status::NotFound, msg!("Album not found"));
+        }
+        if let Some(Some(cover_id)) = changes.cover_id {
+            let album_photos = unwrap_ret!(get_photos_in_album(conn, id, &PhotoFilter::default()), Status::InternalServerError);
+            if !album_photos.iter().any(|photo| photo.id == cover_id) {
+                return (Status::BadRequest, msg!("Photo {} is not in album {}", cover_id, id));
+            }
+        }
+
+        let result = update_album_metadata(conn, id, &changes);
+        record_operation(conn, &actor, AuditAction::AlbumUpdate, &[], &[id], None, &result);
+        unwrap_ret!(result, Status::InternalServerError);
+
+        (Status::Ok, msg!("Success"))
+    }).await, Status::InternalServerError)
+}
+
+/// Sets the manual order of the photos in an album
+///
+/// # Endpoint
+/// `PUT /album/<id>/order`
+///
+/// # URL Parameters
+/// - `id`: The ID of the album to reorder (i32)
+///
+/// # Request Body
+/// JSON object with:
+/// - `photo_ids`: Photos of the album in their new order (Vec<i64>). This may be a partial ordering: photos
+///   left out keep their relative order and move behind the listed ones.
+///
+/// # Returns
+/// - `200 OK`: The album was reordered
+/// - `400 Bad Request`: Missing photo_ids, duplicates, or a photo that is not in the album
+/// - `404 Not Found`: Album with the specified ID does not exist
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[put("/album/<id>/order", format = "json", data = "<input>")]
+pub async fn order_album(db: Db, actor: Actor, id: i32, input: Json<Value>) -> (Status, Json<Value>) {
+    let photo_ids = unwrap_ret!(input.get_value::<Vec<i64>>("photo_ids"), Status::BadRequest);
+    unwrap_ret!(db.run(move |conn| {
+        if unwrap_ret!(get_album(conn, &[id]), Status::InternalServerError).is_empty() {
+            return (Status::NotFound, msg!("Album not found"));
+        }
+        let member_ids = unwrap_ret!(get_album_photo_ids(conn, id), Status::InternalServerError);
+
+        let mut seen = HashSet::new();
+        for photo_id in &photo_ids {
+            if !member_ids.contains(photo_id) {
+                return (Status::BadRequest, msg!("Photo {} is not in album {}", photo_id, id));
+            }
+            if !seen.insert(*photo_id) {
+                return (Status::BadRequest, msg!("Photo {} is listed more than once", photo_id));
+            }
+        }
+
+        let mut order = photo_ids;
+        order.extend(member_ids.into_iter().filter(|photo_id| !seen.contains(photo_id)));
+        let result = set_album_order(conn, id, &order);
+        record_operation(conn, &actor, AuditAction::AlbumOrder, &[], &[id], None, &result);
+        unwrap_ret!(result, Status::InternalServerError);
+
+        (Status::Ok, msg!("Success"))
+    }).await, Status::InternalServerError)
 }
 
-/// Deletes an album from the database by ID
+/// Moves an album to the trash by ID
+///
+/// The album directory is moved to `$STORAGE_ROOT/.trash` and the album is hidden from all other
+/// queries. Its photos are moved to `unfiled` and its subalbums to the root first, as they are not
+/// trashed with it. The album keeps its link to its parent, so `POST /trash/restore` can put it back.
 ///
 /// # Endpoint
 /// `DELETE /album/<id>/delete`
@@ -85,90 +264,126 @@ pub fn rename_album(id: i32, input: Json<Value>) -> (Status, Json<Value>) {
 /// - `id`: The ID of the album to delete (i32)
 ///
 /// # Returns
-/// - `200 OK`: Album was successfully deleted
+/// - `200 OK`: Album was successfully trashed
 /// - `404 Not Found`: Album with the specified ID does not exist
 /// - `500 Internal Server Error`: Database or other server error occurred
 #[delete("/album/<id>/delete")]
-pub fn del_album(id: i32) -> (Status, Json<Value>) {
-    let mut conn = unwrap_ret!(DB_POOL.get(), Status::InternalServerError);
-
-    // Delete album from disk, moving its children to root
-    let album = unwrap_ret!(get_album(&mut conn, &[id]).and_then(|mut albums| albums.pop().ok_or(Error::NotFound)), Status::InternalServerError);
-    let album_path = unwrap_ret!(get_album_path(&mut conn, album.id), Status::InternalServerError);
-    let child_photos = unwrap_ret!(get_photos_in_album(&mut conn, album.id), Status::InternalServerError);
-    let child_albums = unwrap_ret!(get_albums_in_album(&mut conn, album.id), Status::InternalServerError);
-
-    unwrap_ret!(delete_album_fs(&album_path, &child_photos, &child_albums), Status::InternalServerError);
-
-    // Delete album from DB
-    match delete_album(&mut conn, id) {
-        Err(Error::NotFound) => (Status::NotFound, msg!("Album not found")),
-        Err(err) => (Status::InternalServerError, msg!("Failed to delete album from database: {:#?}", err)),
-        Ok(_) => {
-            (Status::Ok, msg!("Success"))
-        },
-    }
+pub async fn del_album(db: Db, actor: Actor, id: i32) -> (Status, Json<Value>) {
+    unwrap_ret!(db.run(move |conn| {
+        let album = match get_album(conn, &[id]).map(|mut albums| albums.pop()) {
+            Ok(Some(album)) => album,
+            Ok(None) => return (Status::NotFound, msg!("Album not found")),
+            Err(err) => return (Status::InternalServerError, msg!("Failed to query album: {:#?}", err)),
+        };
+        let album_path = unwrap_ret!(get_album_path(conn, album.id), Status::InternalServerError);
+        let child_photos = unwrap_ret!(get_photos_in_album(conn, album.id, &PhotoFilter::default()), Status::InternalServerError);
+        let child_albums = unwrap_ret!(get_albums_in_album(conn, album.id), Status::InternalServerError);
+
+        // The children are moved as well, so they are part of the record
+        let photo_ids: Vec<i64> = child_photos.iter().map(|photo| photo.id).collect();
+        let mut album_ids: Vec<i32> = vec![id];
+        album_ids.extend(child_albums.iter().map(|album| album.id));
+        let before = take_snapshot(conn, &photo_ids, &album_ids);
+
+        let result = unit_of_work(conn, |conn, journal| {
+            // Move album to the trash, moving its children to root
+            trash_album_fs(journal, &album, &album_path, &child_photos, &child_albums)?;
+
+            // Unlink the children, then mark the album as trashed in DB
+            remove_photo_from_album(conn, &photo_ids)?;
+            remove_album_from_album(conn, &album_ids[1..])?;
+            Ok(set_album_trashed(conn, id, Some(Local::now().naive_local()))?)
+        });
+        record_operation(conn, &actor, AuditAction::AlbumDelete, &photo_ids, &album_ids, before, &result);
+        match result {
+            Ok(_) => (Status::Ok, msg!("Success")),
+            Err(err) => (Status::InternalServerError, msg!("Failed to delete album: {}", err)),
+        }
+    }).await, Status::InternalServerError)
 }
 
 /// Retrieves a list of all root albums from the database
 ///
 /// # Endpoint
-/// `GET /album/all`
+/// `GET /album/root`
+///
+/// # Query Parameters
+/// Optional `AlbumListing` field: `includeSmart`
 ///
 /// # Returns
 /// - `200 OK`: JSON array of all root albums
 /// - `500 Internal Server Error`: Database or another server error occurred
 ///
 /// # Response Body
-/// Array of Album objects, each containing:
+/// Array of Album objects, sorted by `sortPosition` and then by name, each containing:
 /// - `albumId`: Album's unique identifier (i32)
 /// - `albumName`: Name of the album (String)
-#[get("/album/root")]
-pub fn all_root_albums() -> Result<Json<Vec<Album>>, (Status, Json<Value>)> {
-    let mut conn = unwrap_err!(DB_POOL.get(), Status::InternalServerError);
-    let albums = unwrap_err!(get_root_albums(&mut conn), Status::InternalServerError);
-    
-    Ok(Json(albums))
+/// - `description`: Description of the album, or `null`
+/// - `coverId`: Chosen cover photo, or `null`
+/// - `effectiveCoverId`: Cover to show: `coverId` while it is in the album, otherwise the earliest photo
+/// - `createdAt` / `updatedAt`: When the album was created and last renamed or updated
+/// - `sortPosition`: Position among its siblings, or `null`
+/// - `firstPhotoDate` / `lastPhotoDate`: Date range of the album's photos, `null` for an empty album
+///
+/// Smart albums carry a `smartAlbumId` instead of an `albumId`, along with their `query` and `createdAt`.
+#[get("/album/root?<listing..>")]
+pub async fn all_root_albums(db: Db, listing: AlbumListing) -> Result<Json<Value>, (Status, Json<Value>)> {
+    unwrap_err!(db.run(move |conn| {
+        let albums = unwrap_err!(get_root_albums(conn), Status::InternalServerError);
+        let albums = unwrap_err!(get_album_details(conn, albums), Status::InternalServerError);
+        let mut entries: Vec<Value> = albums.iter().map(|album| json!(album)).collect();
+
+        if listing.include_smart == Some(true) {
+            let smart_albums = unwrap_err!(get_all_smart_albums(conn), Status::InternalServerError);
+            entries.extend(smart_albums.iter().map(SmartAlbum::to_json));
+        }
+
+        Ok(Json(Value::Array(entries)))
+    }).await, Status::InternalServerError)
 }
 
 
-/// Retrieves all photos linked to a given album
+/// Retrieves all photos linked to a given album, in album order unless a `sort` is given
 ///
 /// # Endpoint
 /// `GET /album/<id>/photos`
 ///
+/// # Query Parameters
+/// Optional `PhotoFilter` fields: `minRating`, `colorLabel`, `pickFlag`, `collapseStacks`, `sort`, `order`, `page`, `perPage`
+///
 /// # Returns
 /// - `200 OK`: JSON array of unfiled photos
 /// - `500 Internal Server Error`: Database or another server error occurred
 ///
 /// # Response Body
 /// Array of webapi::Photo objects containing metadata for each photo in the album
-#[get("/album/<id>/photos")]
-pub fn album_photos(id: i32) -> Result<Json<Vec<Photo>>, (Status, Json<Value>)> {
-    let mut conn = unwrap_err!(DB_POOL.get(), Status::InternalServerError);
-
-    let album_photos =  unwrap_err!(get_photos_in_album(&mut conn, id), Status::InternalServerError);
-    Ok(Json(album_photos))
+#[get("/album/<id>/photos?<filter..>")]
+pub async fn album_photos(db: Db, id: i32, filter: PhotoFilter) -> Result<Json<Vec<Photo>>, (Status, Json<Value>)> {
+    unwrap_err!(db.run(move |conn| {
+        let album_photos =  unwrap_err!(get_photos_in_album(conn, id, &filter), Status::InternalServerError);
+        Ok(Json(album_photos))
+    }).await, Status::InternalServerError)
 }
 
 
 /// Retrieves all subalbums linked to a given album
 ///
 /// # Endpoint
-/// `GET /album/<id>/photos`
+/// `GET /album/<id>/albums`
 ///
 /// # Returns
-/// - `200 OK`: JSON array of unfiled photos
+/// - `200 OK`: JSON array of subalbums
 /// - `500 Internal Server Error`: Database or another server error occurred
 ///
 /// # Response Body
-/// Array of api::Album objects containing metadata for each photo in the album
+/// Array of Album objects in the form described at `GET /album/root`, sorted the same way
 #[get("/album/<id>/albums")]
-pub fn album_albums(id: i32) -> Result<Json<Vec<Album>>, (Status, Json<Value>)> {
-    let mut conn = unwrap_err!(DB_POOL.get(), Status::InternalServerError);
-
-    let album_albums =  unwrap_err!(get_albums_in_album(&mut conn, id), Status::InternalServerError);
-    Ok(Json(album_albums))
+pub async fn album_albums(db: Db, id: i32) -> Result<Json<Vec<AlbumDetails>>, (Status, Json<Value>)> {
+    unwrap_err!(db.run(move |conn| {
+        let album_albums =  unwrap_err!(get_albums_in_album(conn, id), Status::InternalServerError);
+        let album_albums = unwrap_err!(get_album_details(conn, album_albums), Status::InternalServerError);
+        Ok(Json(album_albums))
+    }).await, Status::InternalServerError)
 }
 
 
@@ -177,16 +392,19 @@ pub fn album_albums(id: i32) -> Result<Json<Vec<Album>>, (Status, Json<Value>)>
 /// # Endpoint
 /// `GET /album/unfiled/photos`
 ///
+/// # Query Parameters
+/// Optional `PhotoFilter` fields: `minRating`, `colorLabel`, `pickFlag`, `collapseStacks`, `sort`, `order`, `page`, `perPage`
+///
 /// # Returns
 /// - `200 OK`: JSON array of unfiled photos
 /// - `500 Internal Server Error`: Database or another server error occurred
 ///
 /// # Response Body
 /// Array of webapi::Photo objects containing metadata for each unfiled photo
-#[get("/album/unfiled/photos")]
-pub fn unfiled_photos() -> Result<Json<Vec<Photo>>, (Status, Json<Value>)> {
-    let mut conn = unwrap_err!(DB_POOL.get(), Status::InternalServerError);
-
-    let unfiled_photos =  unwrap_err!(get_photos_unfiled(&mut conn), Status::InternalServerError);
-    Ok(Json(unfiled_photos))
+#[get("/album/unfiled/photos?<filter..>")]
+pub async fn unfiled_photos(db: Db, filter: PhotoFilter) -> Result<Json<Vec<Photo>>, (Status, Json<Value>)> {
+    unwrap_err!(db.run(move |conn| {
+        let unfiled_photos =  unwrap_err!(get_photos_unfiled(conn, &filter), Status::InternalServerError);
+        Ok(Json(unfiled_photos))
+    }).await, Status::InternalServerError)
 }
\ No newline at end of file
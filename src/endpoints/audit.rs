@@ -0,0 +1,75 @@
+use crate::db::operations::audit::get_audit_entries;
+use crate::db::pool::Db;
+use crate::models::audit::{AuditEntry, AuditFilter};
+use crate::{msg, unwrap_err};
+use rocket::get;
+use rocket::http::Status;
+use rocket::serde::json::{Json, Value};
+use serde_json::json;
+
+/// Number of entries per page, unless `perPage` is given
+const DEFAULT_PER_PAGE: i64 = 50;
+
+/// Largest accepted `perPage`
+const MAX_PER_PAGE: i64 = 500;
+
+/// Lists the audit log of mutating operations, newest first
+///
+/// Operations are attributed to the `X-Suisai-User` request header (`anonymous` if it is missing),
+/// or to `cli:<user>` for subcommands.
+///
+/// # Endpoint
+/// `GET /audit?<filter..>`
+///
+/// # Query Parameters
+/// Optional `AuditFilter` fields: `actor`, `action`, `result`, `photoId`, `albumId`, `since`, `until`,
+/// `page` and `perPage`
+///
+/// # Returns
+/// - `200 OK`: JSON object with the `entries` of the page, and `page`, `perPage` and `total` (number of matching entries)
+/// - `400 Bad Request`: `page` is too large to be reached
+/// - `422 Unprocessable Entity`: Unknown `action` or `result`, or malformed `since` / `until`
+/// - `500 Internal Server Error`: Database or other server error occurred
+///
+/// # Response Body
+/// Every entry carries `id` (the operation ID), `createdAt`, `actor`, `action`, the affected `photoIds`
+/// and `albumIds`, `before` and `after` snapshots of their names, paths and album links, `result`, `error` and
+/// `undoneBy` (the operation ID of the undo that reversed it, see `POST /management/undo/<operation_id>`).
+#[get("/audit?<filter..>")]
+pub async fn audit_log(db: Db, filter: AuditFilter) -> Result<Json<Value>, (Status, Json<Value>)> {
+    let page = filter.page.unwrap_or(1).max(1);
+    let per_page = filter.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE);
+    let Some(offset) = (page - 1).checked_mul(per_page) else {
+        return Err((Status::BadRequest, msg!("page is out of range")));
+    };
+
+    unwrap_err!(db.run(move |conn| {
+        let (entries, total) = unwrap_err!(get_audit_entries(conn, &filter, offset, per_page), Status::InternalServerError);
+
+        Ok(Json(json!({
+            "entries": entries.iter().map(entry_json).collect::<Vec<Value>>(),
+            "page": page,
+            "perPage": per_page,
+            "total": total,
+        })))
+    }).await, Status::InternalServerError)
+}
+
+/// Converts an audit log entry to JSON, embedding its stored JSON columns as-is
+fn entry_json(entry: &AuditEntry) -> Value {
+    let parse = |column: &str| serde_json::from_str::<Value>(column).unwrap_or(Value::Null);
+
+    json!({
+        "id": entry.id,
+        "createdAt": entry.created_at,
+        "actor": entry.actor,
+        "action": entry.action,
+        "photoIds": parse(&entry.photo_ids),
+        "albumIds": parse(&entry.album_ids),
+        "before": entry.before_state.as_deref().map(parse),
+        "after": entry.after_state.as_deref().map(parse),
+        "result": entry.result,
+        "error": entry.error,
+        "undoneBy": entry.undone_by,
+    })
+}
@@ -0,0 +1,247 @@
+use crate::_utils::json_map::JsonMap;
+use crate::db::operations::collection::{add_photo_to_collection, create_collection, delete_collection, find_collection, get_all_collections, get_collection, get_collection_photo_ids, remove_photo_from_collection, rename_collection as rename_collection_db, set_collection_order};
+use crate::db::operations::photo::get_photo;
+use crate::db::operations::query::get_photos_in_collection;
+use crate::db::pool::Db;
+use crate::models::collection::{validate_collection_name, Collection, NewCollection};
+use crate::models::filter::PhotoFilter;
+use crate::models::photo::Photo;
+use crate::{msg, unwrap_err, unwrap_ret};
+use chrono::Local;
+use diesel::result::Error;
+use rocket::http::Status;
+use rocket::serde::json::{Json, Value};
+use rocket::{delete, get, patch, post, put};
+use serde_json::json;
+use std::collections::HashSet;
+
+/// Retrieves every collection
+///
+/// # Endpoint
+/// `GET /collection/all`
+///
+/// # Returns
+/// - `200 OK`: JSON array of all collections, sorted by name
+/// - `500 Internal Server Error`: Database or another server error occurred
+///
+/// # Response Body
+/// Array of Collection objects, each containing:
+/// - `collectionId`: Collection's unique identifier (i32)
+/// - `collectionName`: Name of the collection (String)
+/// - `createdAt`: When the collection was created
+#[get("/collection/all")]
+pub async fn all_collections(db: Db) -> Result<Json<Vec<Collection>>, (Status, Json<Value>)> {
+    unwrap_err!(db.run(move |conn| {
+        let collections = unwrap_err!(get_all_collections(conn), Status::InternalServerError);
+
+        Ok(Json(collections))
+    }).await, Status::InternalServerError)
+}
+
+/// Creates a new, empty collection. Collections only exist in the database; no files are moved.
+///
+/// # Endpoint
+/// `POST /collection/new`
+///
+/// # Request Body
+/// JSON object with:
+/// - `collection_name`: Name for the new collection (String)
+///
+/// # Returns
+/// - `201 Created`: Collection was created; body contains its `collectionId`
+/// - `400 Bad Request`: Missing or invalid collection_name in request body
+/// - `409 Conflict`: A collection with the same name already exists
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[post("/collection/new", format = "json", data = "<input>")]
+pub async fn new_collection(db: Db, input: Json<Value>) -> (Status, Json<Value>) {
+    let collection_name = unwrap_ret!(input.get_value::<String>("collection_name"), Status::BadRequest);
+    unwrap_ret!(validate_collection_name(&collection_name), Status::BadRequest);
+    let collection_name = collection_name.trim().to_string();
+    unwrap_ret!(db.run(move |conn| {
+        if unwrap_ret!(find_collection(conn, &collection_name), Status::InternalServerError).is_some() {
+            return (Status::Conflict, msg!("A collection named {} already exists", collection_name));
+        }
+
+        let collection = NewCollection { collection_name, created_at: Local::now().naive_local() };
+        let collection_id = unwrap_ret!(create_collection(conn, collection), Status::InternalServerError);
+        (Status::Created, Json(json!({ "collectionId": collection_id })))
+    }).await, Status::InternalServerError)
+}
+
+/// Renames a collection
+///
+/// # Endpoint
+/// `PATCH /collection/<id>/rename`
+///
+/// # Request Body
+/// JSON object with:
+/// - `collection_name`: New name for the collection (String)
+///
+/// # Returns
+/// - `200 OK`: Collection was successfully renamed
+/// - `400 Bad Request`: Missing or invalid collection_name in the request body
+/// - `404 Not Found`: Collection with the specified ID does not exist
+/// - `409 Conflict`: Another collection with the same name already exists
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[patch("/collection/<id>/rename", format = "json", data = "<input>")]
+pub async fn rename_collection(db: Db, id: i32, input: Json<Value>) -> (Status, Json<Value>) {
+    let collection_name = unwrap_ret!(input.get_value::<String>("collection_name"), Status::BadRequest);
+    unwrap_ret!(validate_collection_name(&collection_name), Status::BadRequest);
+    let collection_name = collection_name.trim().to_string();
+    unwrap_ret!(db.run(move |conn| {
+        if unwrap_ret!(find_collection(conn, &collection_name), Status::InternalServerError).is_some_and(|other| other.id != id) {
+            return (Status::Conflict, msg!("A collection named {} already exists", collection_name));
+        }
+
+        match rename_collection_db(conn, id, &collection_name) {
+            Ok(0) if get_collection(conn, id).is_err_and(|err| err == Error::NotFound) => (Status::NotFound, msg!("Collection not found")),
+            Ok(_) => (Status::Ok, msg!("Success")),
+            Err(err) => (Status::InternalServerError, msg!("Failed to rename collection: {:#?}", err)),
+        }
+    }).await, Status::InternalServerError)
+}
+
+/// Deletes a collection. Its photos are not affected.
+///
+/// # Endpoint
+/// `DELETE /collection/<id>/delete`
+///
+/// # Returns
+/// - `200 OK`: Collection was successfully deleted
+/// - `404 Not Found`: Collection with the specified ID does not exist
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[delete("/collection/<id>/delete")]
+pub async fn del_collection(db: Db, id: i32) -> (Status, Json<Value>) {
+    unwrap_ret!(db.run(move |conn| {
+        match delete_collection(conn, id) {
+            Ok(0) => (Status::NotFound, msg!("Collection not found")),
+            Ok(_) => (Status::Ok, msg!("Success")),
+            Err(err) => (Status::InternalServerError, msg!("Failed to delete collection: {:#?}", err)),
+        }
+    }).await, Status::InternalServerError)
+}
+
+/// Retrieves the photos of a collection, in collection order. Trashed photos are left out.
+///
+/// # Endpoint
+/// `GET /collection/<id>/photos`
+///
+/// # Query Parameters
+/// Optional `PhotoFilter` fields: `minRating`, `colorLabel`, `pickFlag`, `collapseStacks`, `sort`, `order`, `page`, `perPage`
+///
+/// # Returns
+/// - `200 OK`: JSON array of photos
+/// - `404 Not Found`: Collection with the specified ID does not exist
+/// - `500 Internal Server Error`: Database or another server error occurred
+#[get("/collection/<id>/photos?<filter..>")]
+pub async fn collection_photos(db: Db, id: i32, filter: PhotoFilter) -> Result<Json<Vec<Photo>>, (Status, Json<Value>)> {
+    unwrap_err!(db.run(move |conn| {
+        match get_collection(conn, id) {
+            Ok(_) => (),
+            Err(Error::NotFound) => return Err((Status::NotFound, msg!("Collection not found"))),
+            Err(err) => return Err((Status::InternalServerError, msg!("Failed to query collection: {:#?}", err))),
+        }
+
+        let photos = unwrap_err!(get_photos_in_collection(conn, id, &filter), Status::InternalServerError);
+        Ok(Json(photos))
+    }).await, Status::InternalServerError)
+}
+
+/// Adds photos to the end of a collection, regardless of the albums they are in
+///
+/// # Endpoint
+/// `POST /collection/<id>/add`
+///
+/// # Request Body
+/// JSON object with:
+/// - `photo_ids`: Array of photo IDs to add, in the order they should appear (Vec<i64>)
+///
+/// # Returns
+/// - `200 OK`: Photos were added (photos already in the collection keep their place)
+/// - `400 Bad Request`: Missing or invalid photo_ids in request body
+/// - `404 Not Found`: The collection or one of the photos does not exist (or the photo is trashed)
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[post("/collection/<id>/add", format = "json", data = "<input>")]
+pub async fn add_to_collection(db: Db, id: i32, input: Json<Value>) -> (Status, Json<Value>) {
+    let photo_ids = unwrap_ret!(input.get_value::<Vec<i64>>("photo_ids"), Status::BadRequest);
+    unwrap_ret!(db.run(move |conn| {
+        match get_collection(conn, id) {
+            Ok(_) => (),
+            Err(Error::NotFound) => return (Status::NotFound, msg!("Collection not found")),
+            Err(err) => return (Status::InternalServerError, msg!("Failed to query collection: {:#?}", err)),
+        }
+        let unique_ids: HashSet<i64> = photo_ids.iter().copied().collect();
+        if unwrap_ret!(get_photo(conn, &photo_ids), Status::InternalServerError).len() != unique_ids.len() {
+            return (Status::NotFound, msg!("Not all photos exist"));
+        }
+
+        let rows = unwrap_ret!(add_photo_to_collection(conn, id, &photo_ids), Status::InternalServerError);
+        (Status::Ok, msg!("Added {} photos", rows))
+    }).await, Status::InternalServerError)
+}
+
+/// Removes photos from a collection. The photos themselves are not affected.
+///
+/// # Endpoint
+/// `POST /collection/<id>/remove`
+///
+/// # Request Body
+/// JSON object with:
+/// - `photo_ids`: Array of photo IDs to remove (Vec<i64>)
+///
+/// # Returns
+/// - `200 OK`: Photos were removed (photos not in the collection are ignored)
+/// - `400 Bad Request`: Missing or invalid photo_ids in request body
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[post("/collection/<id>/remove", format = "json", data = "<input>")]
+pub async fn remove_from_collection(db: Db, id: i32, input: Json<Value>) -> (Status, Json<Value>) {
+    let photo_ids = unwrap_ret!(input.get_value::<Vec<i64>>("photo_ids"), Status::BadRequest);
+    unwrap_ret!(db.run(move |conn| {
+        let rows = unwrap_ret!(remove_photo_from_collection(conn, id, &photo_ids), Status::InternalServerError);
+        (Status::Ok, msg!("Removed {} photos", rows))
+    }).await, Status::InternalServerError)
+}
+
+/// Reorders the photos of a collection
+///
+/// # Endpoint
+/// `PUT /collection/<id>/order`
+///
+/// # Request Body
+/// JSON object with:
+/// - `photo_ids`: Photos of the collection in their new order (Vec<i64>). Photos left out (e.g. trashed
+///   ones the client doesn't see) keep their relative order and move behind the listed ones.
+///
+/// # Returns
+/// - `200 OK`: The collection was reordered
+/// - `400 Bad Request`: Missing photo_ids, duplicates, or a photo that is not in the collection
+/// - `404 Not Found`: Collection with the specified ID does not exist
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[put("/collection/<id>/order", format = "json", data = "<input>")]
+pub async fn order_collection(db: Db, id: i32, input: Json<Value>) -> (Status, Json<Value>) {
+    let photo_ids = unwrap_ret!(input.get_value::<Vec<i64>>("photo_ids"), Status::BadRequest);
+    unwrap_ret!(db.run(move |conn| {
+        match get_collection(conn, id) {
+            Ok(_) => (),
+            Err(Error::NotFound) => return (Status::NotFound, msg!("Collection not found")),
+            Err(err) => return (Status::InternalServerError, msg!("Failed to query collection: {:#?}", err)),
+        }
+        let member_ids = unwrap_ret!(get_collection_photo_ids(conn, id), Status::InternalServerError);
+
+        let mut seen = HashSet::new();
+        for photo_id in &photo_ids {
+            if !member_ids.contains(photo_id) {
+                return (Status::BadRequest, msg!("Photo {} is not in collection {}", photo_id, id));
+            }
+            if !seen.insert(*photo_id) {
+                return (Status::BadRequest, msg!("Photo {} is listed more than once", photo_id));
+            }
+        }
+
+        let mut order = photo_ids;
+        order.extend(member_ids.into_iter().filter(|photo_id| !seen.contains(photo_id)));
+        unwrap_ret!(set_collection_order(conn, id, &order), Status::InternalServerError);
+
+        (Status::Ok, msg!("Success"))
+    }).await, Status::InternalServerError)
+}
@@ -0,0 +1,146 @@
+use crate::_utils::actor::Actor;
+use crate::_utils::json_map::JsonMap;
+use crate::audit::{record_operation, take_snapshot};
+use crate::db::operations::join_photo_tag::{add_tag_to_photo, get_photo_tag_ids};
+use crate::db::operations::phash::get_photo_phashes;
+use crate::db::operations::photo::{get_photo, set_photo_flags};
+use crate::db::pool::Db;
+use crate::db::unit_of_work::unit_of_work;
+use crate::maintenance::duplicates::{best_photo, cluster_phashes};
+use crate::models::audit::AuditAction;
+use crate::models::phash::phash_distance;
+use crate::models::photo::PhotoFlags;
+use crate::sidecar::{sync_xmp_sidecars, with_sidecar_warnings};
+use crate::trash::trash_photos;
+use crate::{msg, unwrap_err, unwrap_ret};
+use rocket::http::Status;
+use rocket::serde::json::{Json, Value};
+use rocket::{get, post};
+use serde_json::json;
+use std::collections::HashMap;
+
+/// Similarity used when no `threshold` is given (at most 6 of 64 bits differ)
+const DEFAULT_THRESHOLD: f64 = 0.9;
+
+/// Lowest accepted similarity. Below this, unrelated photos start to cluster together.
+const MIN_THRESHOLD: f64 = 0.5;
+
+/// Lists groups of photos that look alike, e.g. the same frame exported at different sizes,
+/// re-encoded JPEGs or a DNG next to its raw
+///
+/// Photos are compared by the perceptual hash of their thumbnail. Photos without one (ingested
+/// before hashes were stored) are left out until the `backfill-phash` subcommand has run.
+///
+/// # Endpoint
+/// `GET /duplicates?threshold=<similarity>`
+///
+/// # Query Parameters
+/// - `threshold` (optional): Minimum similarity between two photos, from 0.5 to 1.0 (defaults to 0.9).
+///   1.0 only groups photos with identical hashes.
+///
+/// # Returns
+/// - `200 OK`: JSON array of clusters
+/// - `400 Bad Request`: `threshold` out of range
+/// - `500 Internal Server Error`: Database or other server error occurred
+///
+/// # Response Body
+/// Every cluster carries `keepId`, the copy `POST /duplicates/merge` would keep, and `photos`: a list of
+/// `{ "photo": Photo, "similarity": ... }`, best copy first, with the similarity to the kept copy.
+#[get("/duplicates?<threshold>")]
+pub async fn duplicates(db: Db, threshold: Option<f64>) -> Result<Json<Value>, (Status, Json<Value>)> {
+    let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD);
+    if !(MIN_THRESHOLD..=1.0).contains(&threshold) {
+        return Err((Status::BadRequest, msg!("threshold must be between {} and 1.0", MIN_THRESHOLD)));
+    }
+    let max_distance = ((1.0 - threshold) * 64.0).floor() as u32;
+
+    unwrap_err!(db.run(move |conn| {
+        let phashes = unwrap_err!(get_photo_phashes(conn), Status::InternalServerError);
+        let phash_by_id: HashMap<i64, i64> = phashes.iter().map(|phash| (phash.id, phash.phash)).collect();
+
+        let mut clusters = Vec::new();
+        for photo_ids in cluster_phashes(&phashes, max_distance) {
+            let mut photos = unwrap_err!(get_photo(conn, &photo_ids), Status::InternalServerError);
+            let Some(keep) = best_photo(&photos) else { continue };
+            let (keep_id, keep_phash) = (keep.id, phash_by_id[&keep.id]);
+
+            // Best copy first, then by similarity to it
+            photos.sort_by_key(|photo| (photo.id != keep_id, phash_distance(keep_phash, phash_by_id[&photo.id]), photo.id));
+            clusters.push(json!({
+                "keepId": keep_id,
+                "photos": photos.into_iter().map(|photo| {
+                    let similarity = 1.0 - phash_distance(keep_phash, phash_by_id[&photo.id]) as f64 / 64.0;
+                    json!({ "photo": photo, "similarity": similarity })
+                }).collect::<Vec<Value>>(),
+            }));
+        }
+
+        Ok(Json(json!(clusters)))
+    }).await, Status::InternalServerError)
+}
+
+/// Merges a group of duplicates into one photo: the best copy is kept and the others are moved to the trash.
+///
+/// The kept photo takes over the tags of the others and the highest rating among them, so no culling
+/// work is lost. Trashed copies can be restored with `POST /trash/restore` until they are purged.
+///
+/// # Endpoint
+/// `POST /duplicates/merge`
+///
+/// # Request Body
+/// JSON object with:
+/// - `photo_ids`: JSON array of the photos to merge (at least two)
+/// - `keep_id` (optional): The photo to keep. Defaults to the best copy, as suggested by `GET /duplicates`:
+///   anything over a JPEG, then the most pixels, then the largest file.
+///
+/// # Returns
+/// - `200 OK`: The photos were merged. The response carries the `keptId`, and a `warnings` array if the
+///   kept photo's XMP sidecar could not be updated.
+/// - `400 Bad Request`: Fewer than two photos, or `keep_id` is not one of them
+/// - `404 Not Found`: One of the photos does not exist or is already trashed
+/// - `500 Internal Server Error`: Database or filesystem error occurred. Nothing is merged in that case.
+#[post("/duplicates/merge", format = "json", data = "<input>")]
+pub async fn merge_duplicates(db: Db, actor: Actor, input: Json<Value>) -> (Status, Json<Value>) {
+    let photo_ids = unwrap_ret!(input.get_value::<Vec<i64>>("photo_ids"), Status::BadRequest);
+    let keep_id = unwrap_ret!(input.get_optional::<i64>("keep_id"), Status::BadRequest);
+    unwrap_ret!(db.run(move |conn| {
+        let photos = unwrap_ret!(get_photo(conn, &photo_ids), Status::InternalServerError);
+        if photos.len() < 2 {
+            return (Status::BadRequest, msg!("At least two photos are needed to merge"));
+        }
+        if photos.len() != photo_ids.len() {
+            return (Status::NotFound, msg!("Not all photos exist"));
+        }
+        let keep = match keep_id {
+            Some(keep_id) => match photos.iter().find(|photo| photo.id == keep_id) {
+                Some(keep) => keep,
+                None => return (Status::BadRequest, msg!("keepId must be one of photoIds")),
+            },
+            None => unwrap_ret!(best_photo(&photos).ok_or("No photos to merge"), Status::InternalServerError),
+        };
+        let others: Vec<i64> = photos.iter().filter(|photo| photo.id != keep.id).map(|photo| photo.id).collect();
+        let rating = photos.iter().map(|photo| photo.rating).max().unwrap_or(keep.rating);
+
+        let before = take_snapshot(conn, &photo_ids, &[]);
+        let result = unit_of_work(conn, |conn, journal| {
+            // Carry over tags and rating to the kept photo
+            for other in &others {
+                let tag_ids = get_photo_tag_ids(conn, *other)?;
+                add_tag_to_photo(conn, &tag_ids, &[keep.id])?;
+            }
+            if rating != keep.rating {
+                set_photo_flags(conn, &[keep.id], &PhotoFlags { rating: Some(rating), ..Default::default() })?;
+            }
+
+            // Retire the other copies
+            trash_photos(conn, journal, &others)
+        });
+        record_operation(conn, &actor, AuditAction::DuplicateMerge, &photo_ids, &[], before, &result);
+        unwrap_ret!(result, Status::InternalServerError);
+
+        // The kept photo may have gained tags or a rating
+        let failures = sync_xmp_sidecars(conn, &[keep.id]);
+
+        (Status::Ok, with_sidecar_warnings(Json(json!({ "message": "Success", "keptId": keep.id })), failures))
+    }).await, Status::InternalServerError)
+}
@@ -1,9 +1,21 @@
+use crate::db::pool::establish_connection_pool;
 use crate::endpoints::album::*;
+use crate::endpoints::audit::audit_log;
+use crate::endpoints::collection::*;
+use crate::endpoints::duplicates::*;
 use crate::endpoints::management::*;
+use crate::endpoints::map::photo_map;
 use crate::endpoints::meow::health_check;
 use crate::endpoints::photo::*;
+use crate::endpoints::smart_album::*;
+use crate::endpoints::stack::*;
+use crate::endpoints::tag::*;
 use crate::endpoints::thumbnail::get_thumbnail;
+use crate::endpoints::trash::*;
+use crate::maintenance::purge_trash::run_scheduled_purge;
+use crate::maintenance::verify::{run_scheduled_verification, scheduled_verification_budget};
 use crate::preflight::check_directories;
+use rocket::tokio;
 use rocket::routes;
 use rocket_cors::{AllowedHeaders, AllowedOrigins, CorsOptions};
 use std::str::FromStr;
@@ -13,6 +25,17 @@ pub async fn start_webserver() {
     // Run preflight checks
     check_directories().unwrap();
 
+    // Handlers get their connections from the pool through the `Db` request guard
+    let pool = establish_connection_pool();
+
+    // Start the nightly bit-rot verification, if enabled
+    if let Some(byte_budget) = scheduled_verification_budget() {
+        tokio::spawn(run_scheduled_verification(pool.clone(), byte_budget));
+    }
+
+    // Purge expired entries from the trash once a day
+    tokio::spawn(run_scheduled_purge(pool.clone()));
+
     // Set CORS options
     let cors = CorsOptions {
         allowed_origins: AllowedOrigins::all(),
@@ -27,33 +50,91 @@ pub async fn start_webserver() {
     .expect("Failed to create CORS");
 
     // Start server with appropriate endpoints
-    rocket::build().attach(cors).mount("/", routes![
+    rocket::build().manage(pool).attach(cors).mount("/", routes![
         // General
         health_check,
         
         // Album endpoints
         new_album,
         rename_album,
+        update_album,
+        order_album,
         del_album,
         all_root_albums,
+        album_info,
 
         // Album queries
         album_photos,
         album_albums,
         unfiled_photos,
 
+        // Smart album endpoints
+        all_smart_albums,
+        new_smart_album,
+        update_smart,
+        del_smart_album,
+        smart_album_photos,
+
         // Photo endpoints
         del_photo,
         get_photos,
+        photo_metadata,
+        set_flags,
+        set_time,
+        photos_needing_attention,
         
         // Photo/album management endpoints
         unfile_photo,
         reassign_photo,
+        copy_photo,
         unfile_album,
         reassign_album,
+        merge_album,
+        undo_operation,
+        verification_failures,
+        rescan_storage,
+
+        // Audit log
+        audit_log,
+
+        // Duplicate endpoints
+        duplicates,
+        merge_duplicates,
+
+        // Stack endpoints
+        stack_photos,
+        set_cover,
+        del_stack,
+
+        // Trash endpoints
+        trash_contents,
+        restore_from_trash,
+
+        // Tag endpoints
+        all_tags,
+        new_tag,
+        rename_tag,
+        merge_tag,
+        del_tag,
+        assign_tag,
+        unassign_tag,
+        tag_photos,
+
+        // Collection endpoints
+        all_collections,
+        new_collection,
+        rename_collection,
+        del_collection,
+        collection_photos,
+        add_to_collection,
+        remove_from_collection,
+        order_collection,
         
         // Thumbnail serving endpoints
         get_thumbnail,
+
+        // Map endpoints
+        photo_map,
     ]).launch().await.expect("Failed to launch server");
 
 }
\ No newline at end of file
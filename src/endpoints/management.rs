@@ -1,16 +1,30 @@
+use crate::_utils::actor::Actor;
 use crate::_utils::json_map::JsonMap;
+use crate::album_merge::merge_albums;
+use crate::audit::{find_undo_conflicts, record_operation, take_snapshot, undo_changes, UNDOABLE_ACTIONS};
 use crate::db::operations::album::{get_album, get_album_by_photo};
 use crate::db::operations::join_album_album::{add_album_to_album, remove_album_from_album};
-use crate::db::operations::join_album_photo::{add_photo_to_album, remove_photo_from_album};
-use crate::db::operations::paths::get_album_path;
-use crate::db::operations::photo::get_photo;
+use crate::db::operations::join_album_photo::{add_photo_to_album, get_album_photo_ids, remove_photo_from_album};
+use crate::db::operations::audit::{get_audit_entry, set_audit_undone_by};
+use crate::db::operations::paths::{get_album_path, get_photo_path};
+use crate::db::operations::photo::{copy_photo as copy_photo_entry, get_photo};
+use crate::db::operations::query::get_albums_in_album;
+use crate::db::operations::verification::get_failed_verifications;
+use crate::db::pool::Db;
+use crate::db::unit_of_work::unit_of_work;
 use crate::fs_operations::album::move_album_fs;
-use crate::fs_operations::photo::move_photo_fs;
-use crate::{msg, unwrap_ret, DB_POOL};
+use crate::fs_operations::journal::CopyMode;
+use crate::fs_operations::photo::{copy_photo_fs, move_photo_fs};
+use crate::maintenance::rescan::{rescan_library, RescanSummary};
+use crate::models::audit::{AuditAction, AuditResult, AuditSnapshot};
+use crate::{msg, unwrap_err, unwrap_ret};
 use diesel::result::Error;
 use rocket::http::Status;
-use rocket::post;
+use rocket::{get, post};
 use rocket::serde::json::{Json, Value};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 
 /// Removes photos from all albums they are currently assigned to
@@ -27,29 +41,32 @@ use std::path::{Path, PathBuf};
 /// - `400 Bad Request`: Missing or invalid album_ids or photo_ids in request body
 /// - `500 Internal Server Error`: Full or partial error occurred. Query may not be fully executed
 #[post("/management/photo/unfile", format = "json", data = "<input>")]
-pub fn unfile_photo(input: Json<Value>) -> (Status, Json<Value>) {
+pub async fn unfile_photo(db: Db, actor: Actor, input: Json<Value>) -> (Status, Json<Value>) {
     let photo_ids = unwrap_ret!(input.get_value::<Vec<i64>>("photo_ids"), Status::BadRequest);
-    let mut conn = unwrap_ret!(DB_POOL.get(), Status::InternalServerError);
-
-    let photos = unwrap_ret!(get_photo(&mut conn, &photo_ids), Status::InternalServerError);
-    for photo in photos {
-        // Get path to parent album
-        let parent_album = get_album_by_photo(&mut conn, photo.id);
-        let album_path = match parent_album {
-            Ok(album) => unwrap_ret!(get_album_path(&mut conn, album.id), Status::InternalServerError),
-            Err(Error::NotFound) => PathBuf::from("/unfiled"),
-            Err(err) => {
-                return (Status::InternalServerError, msg!("Failed to query album path: {:#?}", err));
-            }
-        };
+    unwrap_ret!(db.run(move |conn| {
+        // Unfile all photos or none of them
+        let before = take_snapshot(conn, &photo_ids, &[]);
+        let result = unit_of_work(conn, |conn, journal| {
+            for photo in get_photo(conn, &photo_ids)? {
+                // Get path to parent album
+                let album_path = match get_album_by_photo(conn, photo.id) {
+                    Ok(album) => get_album_path(conn, album.id)?,
+                    Err(Error::NotFound) => PathBuf::from("/unfiled"),
+                    Err(err) => return Err(anyhow::anyhow!("Failed to query album path: {:#?}", err)),
+                };
 
-        // Move photo and associated files
-        unwrap_ret!(move_photo_fs(&album_path.join(photo.file_name), Path::new("/unfiled")), Status::InternalServerError);
-        // Move photo and associated files
-        unwrap_ret!(remove_photo_from_album(&mut conn, &[photo.id]), Status::InternalServerError);
-    }
+                // Move photo and associated files
+                move_photo_fs(journal, &album_path.join(photo.file_name), Path::new("/unfiled"))?;
+                // Remove photo-album associations
+                remove_photo_from_album(conn, &[photo.id])?;
+            }
+            Ok(())
+        });
+        record_operation(conn, &actor, AuditAction::PhotoUnfile, &photo_ids, &[], before, &result);
+        unwrap_ret!(result, Status::InternalServerError);
 
-    (Status::Ok, msg!("Success"))
+        (Status::Ok, msg!("Success"))
+    }).await, Status::InternalServerError)
 }
 
 /// Moves photos from their current album(s) to a different album
@@ -67,36 +84,100 @@ pub fn unfile_photo(input: Json<Value>) -> (Status, Json<Value>) {
 /// - `400 Bad Request`: Missing or invalid album_id or photo_ids in request body
 /// - `500 Internal Server Error`: Full or partial error occurred. Query may not be fully executed
 #[post("/management/photo/reassign", format = "json", data = "<input>")]
-pub fn reassign_photo(input: Json<Value>) -> (Status, Json<Value>) {
+pub async fn reassign_photo(db: Db, actor: Actor, input: Json<Value>) -> (Status, Json<Value>) {
     let album_id = unwrap_ret!(input.get_value::<i32>("album_id"), Status::BadRequest);
     let photo_ids = unwrap_ret!(input.get_value::<Vec<i64>>("photo_ids"), Status::BadRequest);
 
-    let mut conn = unwrap_ret!(DB_POOL.get(), Status::InternalServerError);
-
-    let photos = unwrap_ret!(get_photo(&mut conn, &photo_ids), Status::InternalServerError);
-    for photo in photos {
-        // Get path to parent album
-        let parent_album = get_album_by_photo(&mut conn, photo.id);
-        let album_path = match parent_album {
-            Ok(album) => unwrap_ret!(get_album_path(&mut conn, album.id), Status::InternalServerError),
-            Err(Error::NotFound) => PathBuf::from("/unfiled"),
-            Err(err) => {
-                return (Status::InternalServerError, msg!("Failed to query album path: {:#?}", err));
-            }
-        };
+    unwrap_ret!(db.run(move |conn| {
+        // Move all photos or none of them
+        let before = take_snapshot(conn, &photo_ids, &[album_id]);
+        let result = unit_of_work(conn, |conn, journal| {
+            let dest_path = get_album_path(conn, album_id)?;
+            for photo in get_photo(conn, &photo_ids)? {
+                // Get path to parent album
+                let album_path = match get_album_by_photo(conn, photo.id) {
+                    Ok(album) => get_album_path(conn, album.id)?,
+                    Err(Error::NotFound) => PathBuf::from("/unfiled"),
+                    Err(err) => return Err(anyhow::anyhow!("Failed to query album path: {:#?}", err)),
+                };
 
-        // Move photo and associated files
-        let dest_path = unwrap_ret!(get_album_path(&mut conn, album_id), Status::InternalServerError);
-        unwrap_ret!(move_photo_fs(&album_path.join(photo.file_name), &dest_path), Status::InternalServerError);
+                // Move photo and associated files
+                move_photo_fs(journal, &album_path.join(photo.file_name), &dest_path)?;
 
-        // Delete existing photo-album associations
-        unwrap_ret!(remove_photo_from_album(&mut conn, &[photo.id]), Status::InternalServerError);
+                // Delete existing photo-album associations
+                remove_photo_from_album(conn, &[photo.id])?;
 
-        // Create a new photo-album association
-        unwrap_ret!(add_photo_to_album(&mut conn, album_id, &[photo.id]), Status::InternalServerError);
-    }
+                // Create a new photo-album association
+                add_photo_to_album(conn, album_id, &[photo.id])?;
+            }
+            Ok(())
+        });
+        record_operation(conn, &actor, AuditAction::PhotoReassign, &photo_ids, &[album_id], before, &result);
+        unwrap_ret!(result, Status::InternalServerError);
     
-    (Status::Ok, msg!("Success"))
+        (Status::Ok, msg!("Success"))
+    }).await, Status::InternalServerError)
+}
+
+
+/// Copies photos into another album, as new photos linked to the originals.
+///
+/// The photo and its associated files (same base name) are duplicated into the album's directory.
+/// Each copy shares the original's metadata, tags and thumbnail, and carries its `originalId`.
+///
+/// # Endpoint
+/// `POST /management/photo/copy`
+///
+/// # Request Body
+/// JSON object with:
+/// - `album_id`: The ID of the destination album (i32)
+/// - `photo_ids`: Array of photo IDs to copy into the album (Vec<i64>)
+/// - `mode` (optional): `copy` (default) for independent files, `hardlink` to share the data on disk, or
+///   `reflink` for a copy-on-write clone (needs a filesystem that supports it, e.g. Btrfs or XFS)
+///
+/// # Returns
+/// - `200 OK`: Photos were copied; `copies` maps each original photo ID to the ID of its copy
+/// - `400 Bad Request`: Missing or invalid album_id, photo_ids or mode in request body
+/// - `404 Not Found`: The album or one of the photos does not exist (or is trashed)
+/// - `409 Conflict`: A file of the same name already exists in the album's directory
+/// - `500 Internal Server Error`: Database or filesystem error occurred. Nothing was copied
+#[post("/management/photo/copy", format = "json", data = "<input>")]
+pub async fn copy_photo(db: Db, actor: Actor, input: Json<Value>) -> (Status, Json<Value>) {
+    let album_id = unwrap_ret!(input.get_value::<i32>("album_id"), Status::BadRequest);
+    let photo_ids = unwrap_ret!(input.get_value::<Vec<i64>>("photo_ids"), Status::BadRequest);
+    let mode = unwrap_ret!(input.get_optional::<CopyMode>("mode"), Status::BadRequest).unwrap_or_default();
+    unwrap_ret!(db.run(move |conn| {
+        if unwrap_ret!(get_album(conn, &[album_id]), Status::InternalServerError).is_empty() {
+            return (Status::NotFound, msg!("Album not found"));
+        }
+        let photos = unwrap_ret!(get_photo(conn, &photo_ids), Status::InternalServerError);
+        if let Some(photo_id) = photo_ids.iter().find(|photo_id| !photos.iter().any(|photo| photo.id == **photo_id)) {
+            return (Status::NotFound, msg!("Photo {} not found", photo_id));
+        }
+
+        // Copy all photos or none of them
+        let before = take_snapshot(conn, &photo_ids, &[album_id]);
+        let result = unit_of_work(conn, |conn, journal| {
+            let dest_path = get_album_path(conn, album_id)?;
+            let mut copies: BTreeMap<i64, i64> = BTreeMap::new();
+            for photo in &photos {
+                copy_photo_fs(journal, &get_photo_path(conn, photo.id)?, &dest_path, mode)?;
+                let copy_id = copy_photo_entry(conn, photo)?;
+                add_photo_to_album(conn, album_id, &[copy_id])?;
+                copies.insert(photo.id, copy_id);
+            }
+            Ok(copies)
+        });
+        record_operation(conn, &actor, AuditAction::PhotoCopy, &photo_ids, &[album_id], before, &result);
+
+        match result {
+            Ok(copies) => (Status::Ok, Json(json!({ "message": "Success", "copies": copies }))),
+            Err(err) if err.downcast_ref::<std::io::Error>().is_some_and(|e| e.kind() == ErrorKind::AlreadyExists) => {
+                (Status::Conflict, msg!("Failed to copy photos: {}", err))
+            }
+            Err(err) => (Status::InternalServerError, msg!("Failed to copy photos: {}", err)),
+        }
+    }).await, Status::InternalServerError)
 }
 
 
@@ -114,21 +195,27 @@ pub fn reassign_photo(input: Json<Value>) -> (Status, Json<Value>) {
 /// - `400 Bad Request`: Missing or invalid album_ids or photo_ids in request body
 /// - `500 Internal Server Error`: Database or other server error occurred
 #[post("/management/album/unfile", format = "json", data = "<input>")]
-pub fn unfile_album(input: Json<Value>) -> (Status, Json<Value>) {
+pub async fn unfile_album(db: Db, actor: Actor, input: Json<Value>) -> (Status, Json<Value>) {
     let album_ids = unwrap_ret!(input.get_value::<Vec<i32>>("album_ids"), Status::BadRequest);
-    let mut conn = unwrap_ret!(DB_POOL.get(), Status::InternalServerError);
-
-    let albums = unwrap_ret!(get_album(&mut conn, &album_ids), Status::InternalServerError);
-    for album in albums {
-        // Move album to root
-        let album_path = unwrap_ret!(get_album_path(&mut conn, album.id), Status::InternalServerError);
-        unwrap_ret!(move_album_fs(&album_path, &Path::new("/").join(album.album_name)), Status::InternalServerError);
+    unwrap_ret!(db.run(move |conn| {
+        // Unfile all albums or none of them
+        let before = take_snapshot(conn, &[], &album_ids);
+        let result = unit_of_work(conn, |conn, journal| {
+            for album in get_album(conn, &album_ids)? {
+                // Move album to root
+                let album_path = get_album_path(conn, album.id)?;
+                move_album_fs(journal, &album_path, &Path::new("/").join(album.album_name))?;
 
-        // Reflect change in DB
-        unwrap_ret!(remove_album_from_album(&mut conn, &[album.id]), Status::InternalServerError);
-    }
+                // Reflect change in DB
+                remove_album_from_album(conn, &[album.id])?;
+            }
+            Ok(())
+        });
+        record_operation(conn, &actor, AuditAction::AlbumUnfile, &[], &album_ids, before, &result);
+        unwrap_ret!(result, Status::InternalServerError);
 
-    (Status::Ok, msg!("Success"))
+        (Status::Ok, msg!("Success"))
+    }).await, Status::InternalServerError)
 }
 
 /// Moves albums from their current parent album(s) to a different one
@@ -146,25 +233,204 @@ pub fn unfile_album(input: Json<Value>) -> (Status, Json<Value>) {
 /// - `400 Bad Request`: Missing or invalid album_id or photo_ids in request body
 /// - `500 Internal Server Error`: Database error or other server error occurred
 #[post("/management/album/reassign", format = "json", data = "<input>")]
-pub fn reassign_album(input: Json<Value>) -> (Status, Json<Value>) {
+pub async fn reassign_album(db: Db, actor: Actor, input: Json<Value>) -> (Status, Json<Value>) {
     let parent_id = unwrap_ret!(input.get_value::<i32>("album_id"), Status::BadRequest);
     let album_ids = unwrap_ret!(input.get_value::<Vec<i32>>("photo_ids"), Status::BadRequest);
 
-    let mut conn = unwrap_ret!(DB_POOL.get(), Status::InternalServerError);
+    unwrap_ret!(db.run(move |conn| {
+        // Move all albums or none of them
+        let mut affected_ids = album_ids.clone();
+        affected_ids.push(parent_id);
+        let before = take_snapshot(conn, &[], &affected_ids);
+        let result = unit_of_work(conn, |conn, journal| {
+            let dest = get_album_path(conn, parent_id)?;
+            for album in get_album(conn, &album_ids)? {
+                // Move album to new parent
+                let album_path = get_album_path(conn, album.id)?;
+                let dest_path = dest.join(album.album_name);
+                move_album_fs(journal, &album_path, &dest_path)?;
 
-    let albums = unwrap_ret!(get_album(&mut conn, &album_ids), Status::InternalServerError);
-    let dest = unwrap_ret!(get_album_path(&mut conn, parent_id), Status::InternalServerError);
-    for album in albums {
-        // Move album to new parent
-        let album_path = unwrap_ret!(get_album_path(&mut conn, album.id), Status::InternalServerError);
-        let dest_path = dest.join(album.album_name);
-        unwrap_ret!(move_album_fs(&album_path, &dest_path), Status::InternalServerError);
+                // Reflect changes in DB
+                remove_album_from_album(conn, &[album.id])?;
+                add_album_to_album(conn, parent_id, &[album.id])?;
+            }
+            Ok(())
+        });
+        record_operation(conn, &actor, AuditAction::AlbumReassign, &[], &affected_ids, before, &result);
+        unwrap_ret!(result, Status::InternalServerError);
 
-        // Reflect changes in DB
-        unwrap_ret!(remove_album_from_album(&mut conn, &[album.id]), Status::InternalServerError);
-        unwrap_ret!(add_album_to_album(&mut conn, parent_id, &[album.id]), Status::InternalServerError);
+        (Status::Ok, msg!("Success"))
+    }).await, Status::InternalServerError)
+}
+
+/// Merges an album into another: moves all its photos and subalbums into the target, then moves the
+/// emptied source album to the trash. Everything happens in one operation, or not at all.
+///
+/// Photos whose base name is already used in the target directory are renamed (`IMG_0001.CR3` becomes
+/// `IMG_0001_2.CR3`, along with its sidecars), as are subalbums whose name is taken (`Day 1` becomes `Day 1 (2)`).
+///
+/// # Endpoint
+/// `POST /management/album/merge`
+///
+/// # Request Body
+/// JSON object with:
+/// - `source_id`: The ID of the album to merge (i32)
+/// - `target_id`: The ID of the album to merge into (i32)
+///
+/// # Returns
+/// - `200 OK`: `MergeSummary` with the number of moved `photos` and `albums`, and the new names of
+///   `renamedPhotos` and `renamedAlbums` by ID
+/// - `400 Bad Request`: Missing IDs, or the target is the source itself or inside it
+/// - `404 Not Found`: One of the albums does not exist (or is trashed)
+/// - `500 Internal Server Error`: Database or filesystem error occurred
+#[post("/management/album/merge", format = "json", data = "<input>")]
+pub async fn merge_album(db: Db, actor: Actor, input: Json<Value>) -> (Status, Json<Value>) {
+    let source_id = unwrap_ret!(input.get_value::<i32>("source_id"), Status::BadRequest);
+    let target_id = unwrap_ret!(input.get_value::<i32>("target_id"), Status::BadRequest);
+    if source_id == target_id {
+        return (Status::BadRequest, msg!("Cannot merge an album into itself"));
     }
+    unwrap_ret!(db.run(move |conn| {
+        let albums = unwrap_ret!(get_album(conn, &[source_id, target_id]), Status::InternalServerError);
+        let (Some(source), Some(target)) = (albums.iter().find(|album| album.id == source_id), albums.iter().find(|album| album.id == target_id)) else {
+            return (Status::NotFound, msg!("Album not found"));
+        };
+        let source_path = unwrap_ret!(get_album_path(conn, source_id), Status::InternalServerError);
+        let target_path = unwrap_ret!(get_album_path(conn, target_id), Status::InternalServerError);
+        if target_path.starts_with(&source_path) {
+            return (Status::BadRequest, msg!("Cannot merge album {} into its own subalbum {}", source_id, target_id));
+        }
+
+        // The children are moved as well, so they are part of the record
+        let photo_ids = unwrap_ret!(get_album_photo_ids(conn, source_id), Status::InternalServerError);
+        let child_albums = unwrap_ret!(get_albums_in_album(conn, source_id), Status::InternalServerError);
+        let mut album_ids = vec![source_id, target_id];
+        album_ids.extend(child_albums.iter().map(|album| album.id));
+        let before = take_snapshot(conn, &photo_ids, &album_ids);
+
+        let result = unit_of_work(conn, |conn, journal| merge_albums(conn, journal, source, target));
+        record_operation(conn, &actor, AuditAction::AlbumMerge, &photo_ids, &album_ids, before, &result);
+
+        (Status::Ok, Json(json!(unwrap_ret!(result, Status::InternalServerError))))
+    }).await, Status::InternalServerError)
+}
 
-    (Status::Ok, msg!("Success"))
+/// Reverses an earlier photo/album reassign, unfile, rename or album move, as recorded in the audit log.
+///
+/// Photos and albums are moved back on disk and their album links are restored. If anything the
+/// operation touched has changed since (e.g. a photo was moved again, or its old location is taken),
+/// nothing is undone and the conflicts are reported instead.
+///
+/// # Endpoint
+/// `POST /management/undo/<operation_id>`
+///
+/// # URL Parameters
+/// - `operation_id`: ID of the audit log entry of the operation (see `GET /audit`)
+///
+/// # Returns
+/// - `200 OK`: The operation was undone. The response carries the `operationId` of the undo itself.
+/// - `400 Bad Request`: The operation failed, or is of a kind that cannot be undone
+/// - `404 Not Found`: No operation with this ID exists
+/// - `409 Conflict`: The operation was already undone, or things changed since. The response lists the `conflicts`.
+/// - `500 Internal Server Error`: Database or filesystem error occurred
+#[post("/management/undo/<operation_id>")]
+pub async fn undo_operation(db: Db, actor: Actor, operation_id: i64) -> (Status, Json<Value>) {
+    unwrap_ret!(db.run(move |conn| {
+        let entry = match get_audit_entry(conn, operation_id) {
+            Ok(entry) => entry,
+            Err(Error::NotFound) => return (Status::NotFound, msg!("Operation not found")),
+            Err(err) => return (Status::InternalServerError, msg!("Failed to query operation: {:#?}", err)),
+        };
+        if !UNDOABLE_ACTIONS.iter().any(|action| action.as_str() == entry.action) {
+            return (Status::BadRequest, msg!("Operations of type {} cannot be undone", entry.action));
+        }
+        if entry.result != AuditResult::Success.as_str() {
+            return (Status::BadRequest, msg!("Operation {} failed, so there is nothing to undo", operation_id));
+        }
+        if let Some(undo_id) = entry.undone_by {
+            return (Status::Conflict, msg!("Operation {} was already undone by operation {}", operation_id, undo_id));
+        }
+        let (Some(before), Some(after)) = (&entry.before_state, &entry.after_state) else {
+            return (Status::BadRequest, msg!("Operation {} has no record of what it changed", operation_id));
+        };
+        let before = unwrap_ret!(serde_json::from_str::<AuditSnapshot>(before), Status::InternalServerError);
+        let after = unwrap_ret!(serde_json::from_str::<AuditSnapshot>(after), Status::InternalServerError);
+
+        let conflicts = unwrap_ret!(find_undo_conflicts(conn, &before, &after), Status::InternalServerError);
+        if !conflicts.is_empty() {
+            return (Status::Conflict, Json(json!({
+                "message": format!("Operation {} cannot be undone without overwriting later changes", operation_id),
+                "conflicts": conflicts,
+            })));
+        }
+
+        // Undo everything or nothing
+        let photo_ids: Vec<i64> = serde_json::from_str(&entry.photo_ids).unwrap_or_default();
+        let album_ids: Vec<i32> = serde_json::from_str(&entry.album_ids).unwrap_or_default();
+        let snapshot = take_snapshot(conn, &photo_ids, &album_ids);
+        let result = unit_of_work(conn, |conn, journal| undo_changes(conn, journal, &before, &after));
+        let undo_id = record_operation(conn, &actor, AuditAction::Undo, &photo_ids, &album_ids, snapshot, &result);
+
+        match result {
+            Ok(()) => {
+                if let Some(undo_id) = undo_id {
+                    unwrap_ret!(set_audit_undone_by(conn, operation_id, undo_id), Status::InternalServerError);
+                }
+                (Status::Ok, Json(json!({ "message": "Success", "operationId": undo_id })))
+            }
+            Err(err) if err.downcast_ref::<std::io::Error>().is_some_and(|e| e.kind() == ErrorKind::AlreadyExists) => {
+                (Status::Conflict, msg!("Failed to undo operation: {}", err))
+            }
+            Err(err) => (Status::InternalServerError, msg!("Failed to undo operation: {}", err)),
+        }
+    }).await, Status::InternalServerError)
 }
 
+/// Lists photos whose original failed its last integrity check, as recorded by the `verify`
+/// subcommand or the nightly verification job
+///
+/// # Endpoint
+/// `GET /management/verification`
+///
+/// # Returns
+/// - `200 OK`: JSON object mapping each failure status (`"mismatch"`, `"missing"`) to a list of
+///   `{ "photo": Photo, "verifiedAt": ... }`, most recently verified first. Statuses without any photos are left out.
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[get("/management/verification")]
+pub async fn verification_failures(db: Db) -> Result<Json<Value>, (Status, Json<Value>)> {
+    unwrap_err!(db.run(move |conn| {
+        let mut grouped: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+        for (verification, photo) in unwrap_err!(get_failed_verifications(conn), Status::InternalServerError) {
+            grouped.entry(verification.status).or_default().push(json!({
+                "photo": photo,
+                "verifiedAt": verification.verified_at,
+            }));
+        }
+
+        Ok(Json(json!(grouped)))
+    }).await, Status::InternalServerError)
+}
+
+/// Adopts images that were copied into $STORAGE_ROOT directly (e.g. over SMB) and are unknown to the database.
+/// Albums are created for directories that don't have one yet. Same as the `rescan` subcommand.
+///
+/// # Endpoint
+/// `POST /management/rescan`
+///
+/// # Request Body
+/// JSON object with:
+/// - `dry` (optional): If `true`, only count what would be adopted
+///
+/// # Returns
+/// - `200 OK`: `RescanSummary` with the number of adopted, duplicate and failed images
+/// - `400 Bad Request`: Invalid `dry` in request body
+/// - `500 Internal Server Error`: Database or filesystem error occurred
+#[post("/management/rescan", format = "json", data = "<input>")]
+pub async fn rescan_storage(db: Db, input: Json<Value>) -> Result<Json<RescanSummary>, (Status, Json<Value>)> {
+    let dry = unwrap_err!(input.get_optional::<bool>("dry"), Status::BadRequest).unwrap_or(false);
+
+    // Hashing and thumbnail extraction are blocking as well, so they run along with the queries
+    let summary = unwrap_err!(db.run(move |conn| rescan_library(conn, dry)).await, Status::InternalServerError);
+
+    Ok(Json(unwrap_err!(summary, Status::InternalServerError)))
+}
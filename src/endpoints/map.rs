@@ -0,0 +1,106 @@
+use crate::db::operations::query::get_photos_in_bbox;
+use crate::db::pool::Db;
+use crate::models::geo::{BoundingBox, Feature, FeatureCollection};
+use crate::models::photo::Photo;
+use crate::{msg, unwrap_err};
+use rocket::get;
+use rocket::http::Status;
+use rocket::serde::json::{Json, Value};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+/// Approximate on-screen radius (in pixels) within which photos are merged into one cluster
+const CLUSTER_RADIUS_PX: f64 = 60.0;
+
+/// Size of a web map tile in pixels, used to convert a zoom level into degrees per pixel
+const TILE_SIZE_PX: f64 = 256.0;
+
+/// Highest zoom level accepted for clustering. Beyond this, every photo is its own point anyway.
+const MAX_ZOOM: u8 = 24;
+
+/// Retrieves geotagged photos inside a bounding box as GeoJSON, for the map view
+///
+/// # Endpoint
+/// `GET /map?bbox=<minLon,minLat,maxLon,maxLat>&zoom=<zoom>`
+///
+/// # Query Parameters
+/// - `bbox`: Bounding box to search, as `minLon,minLat,maxLon,maxLat` in decimal degrees
+/// - `zoom` (optional): Web map zoom level (0-24). If given, nearby photos are clustered server-side
+///
+/// # Returns
+/// - `200 OK`: GeoJSON `FeatureCollection` of photo points
+/// - `400 Bad Request`: Malformed `bbox` or out of range `zoom`
+/// - `500 Internal Server Error`: Database or other server error occurred
+///
+/// # Response Body
+/// A `FeatureCollection` of `Point` features. Single photos carry `photoId`, `hash`, `fileName`,
+/// `photoDate` and `altitude` properties. Clusters carry `cluster: true`, `pointCount`, and
+/// the `bbox` spanned by their photos, which can be used to zoom into the cluster.
+#[get("/map?<bbox>&<zoom>")]
+pub async fn photo_map(db: Db, bbox: &str, zoom: Option<u8>) -> Result<Json<FeatureCollection>, (Status, Json<Value>)> {
+    let bbox = unwrap_err!(bbox.parse::<BoundingBox>(), Status::BadRequest);
+    if zoom.is_some_and(|zoom| zoom > MAX_ZOOM) {
+        return Err((Status::BadRequest, msg!("zoom must be between 0 and {}", MAX_ZOOM)));
+    }
+
+    unwrap_err!(db.run(move |conn| {
+        let photos = unwrap_err!(get_photos_in_bbox(conn, &bbox), Status::InternalServerError);
+
+        let features = match zoom {
+            Some(zoom) => cluster_features(&photos, zoom),
+            None => photos.iter().filter_map(photo_feature).collect(),
+        };
+
+        Ok(Json(FeatureCollection::new(features)))
+    }).await, Status::InternalServerError)
+}
+
+/// Converts a single geotagged photo into a GeoJSON point feature
+fn photo_feature(photo: &Photo) -> Option<Feature> {
+    Some(Feature::point(photo.longitude?, photo.latitude?, json!({
+        "photoId": photo.id,
+        "hash": photo.hash,
+        "fileName": photo.file_name,
+        "photoDate": photo.photo_date,
+        "altitude": photo.altitude,
+    })))
+}
+
+/// Groups photos into grid cells sized to roughly `CLUSTER_RADIUS_PX` at the given zoom level.
+///
+/// Cells containing a single photo are emitted as regular photo features; all others become
+/// a cluster feature placed at the centroid of its photos.
+fn cluster_features(photos: &[Photo], zoom: u8) -> Vec<Feature> {
+    let cell_size = CLUSTER_RADIUS_PX * 360.0 / (TILE_SIZE_PX * 2f64.powi(zoom as i32));
+
+    // BTreeMap keeps the output order stable between requests
+    let mut cells: BTreeMap<(i64, i64), Vec<&Photo>> = BTreeMap::new();
+    for photo in photos {
+        if let (Some(lon), Some(lat)) = (photo.longitude, photo.latitude) {
+            let cell = ((lon / cell_size).floor() as i64, (lat / cell_size).floor() as i64);
+            cells.entry(cell).or_default().push(photo);
+        }
+    }
+
+    cells.into_values().filter_map(|members| {
+        if let [photo] = members[..] {
+            return photo_feature(photo);
+        }
+
+        // Every photo in a cell is geotagged, so the coordinates can safely be unwrapped
+        let lons: Vec<f64> = members.iter().map(|photo| photo.longitude.unwrap_or_default()).collect();
+        let lats: Vec<f64> = members.iter().map(|photo| photo.latitude.unwrap_or_default()).collect();
+        let count = members.len() as f64;
+
+        let min_lon = lons.iter().copied().fold(f64::INFINITY, f64::min);
+        let max_lon = lons.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let min_lat = lats.iter().copied().fold(f64::INFINITY, f64::min);
+        let max_lat = lats.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        Some(Feature::point(lons.iter().sum::<f64>() / count, lats.iter().sum::<f64>() / count, json!({
+            "cluster": true,
+            "pointCount": members.len(),
+            "bbox": [min_lon, min_lat, max_lon, max_lat],
+        })))
+    }).collect()
+}
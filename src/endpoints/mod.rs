@@ -2,6 +2,14 @@ pub mod album;
 pub mod photo;
 pub mod management;
 pub mod thumbnail;
+pub mod map;
+pub mod tag;
+pub mod collection;
+pub mod smart_album;
+pub mod trash;
+pub mod audit;
+pub mod duplicates;
+pub mod stack;
 pub mod main;
 mod meow;
 
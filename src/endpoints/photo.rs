@@ -1,16 +1,36 @@
+use crate::_utils::actor::Actor;
 use crate::_utils::json_map::JsonMap;
+use crate::_utils::path_prefix::PathPrefix;
+use crate::_utils::time_shift::parse_time_shift;
+use crate::audit::{record_operation, take_snapshot};
+use crate::db::operations::metadata::{get_photo_metadata, get_photos_with_issues, resolve_metadata_issue};
 use crate::db::operations::paths::get_photo_path;
-use crate::db::operations::photo::{delete_photo, get_photo};
-use crate::db::operations::thumbnail::get_thumbnail;
-use crate::fs_operations::photo::delete_photo_fs;
-use crate::models::photo::Photo;
-use crate::{msg, unwrap_err, unwrap_ret, DB_POOL};
+use crate::db::operations::photo::{get_photo, set_photo_flags, set_photo_hash, set_photo_time};
+use crate::db::pool::Db;
+use crate::db::unit_of_work::unit_of_work;
+use crate::fs_operations::photo::write_photo_date_fs;
+use crate::ingest::trait_suisai_image_path::SuisaiImagePath;
+use crate::models::audit::AuditAction;
+use crate::models::metadata::MetadataIssue;
+use crate::models::photo::{is_utc_offset, Photo, PhotoFlags, PhotoTimeChange};
+use crate::sidecar::{sync_xmp_sidecars, with_sidecar_warnings};
+use crate::trash::trash_photos;
+use crate::{msg, unwrap_err, unwrap_ret};
+use chrono::{NaiveDateTime, TimeDelta};
+use diesel::result::Error;
 use rocket::http::Status;
 use rocket::serde::json::{Json, Value};
-use rocket::{delete, post};
+use rocket::{delete, get, patch, post};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::env;
 use std::path::PathBuf;
 
-/// Delete multiple photos from the database by their IDs
+/// Move multiple photos to the trash by their IDs
+///
+/// The photos and their associated files are moved to `$STORAGE_ROOT/.trash` and hidden from all
+/// other queries. They stay in their albums, so `POST /trash/restore` can put them back, until
+/// they are purged after `$TRASH_RETENTION_DAYS`.
 ///
 /// # Route  
 /// `DELETE /photo/delete`
@@ -20,26 +40,21 @@ use std::path::PathBuf;
 /// - `photo_ids`: JSON array of photo IDs to delete
 ///
 /// # Returns
-/// - `Status::Ok` (200) if the photos were successfully deleted
-///   (will succeed even if some photos did not exist)
-/// - `Status::InternalServerError` (500) if deletion fails for reasons other than missing photos
+/// - `Status::Ok` (200) if the photos were successfully trashed
+///   (will succeed even if some photos did not exist or were already trashed)
+/// - `Status::InternalServerError` (500) if trashing fails for reasons other than missing photos.
+///   No photos are trashed in that case.
 #[delete("/photo/delete", format = "json", data = "<input>")]
-pub fn del_photo(input: Json<Value>) -> (Status, Json<Value>) {
+pub async fn del_photo(db: Db, actor: Actor, input: Json<Value>) -> (Status, Json<Value>) {
     let photo_ids = unwrap_ret!(input.get_value::<Vec<i64>>("photo_ids"), Status::BadRequest);
-    let mut conn = unwrap_ret!(DB_POOL.get(), Status::InternalServerError);
-    
-    // Delete photos from DB
-    let deleted = unwrap_ret!(delete_photo(&mut conn, &photo_ids), Status::InternalServerError);
-    
-    // Also delete photos & thumbnail from filesystem, and ignore nonexistent/permission errors
-    for photo in deleted {
-        let photo_path = unwrap_ret!(get_photo_path(&mut conn, photo.id), Status::InternalServerError);
-        let thumb_path = unwrap_ret!(get_thumbnail(&mut conn, photo.id), Status::InternalServerError).thumbnail_path;
-
-        unwrap_ret!(delete_photo_fs(&photo_path, &PathBuf::from(thumb_path)), Status::InternalServerError);
-    }
+    unwrap_ret!(db.run(move |conn| {
+        let before = take_snapshot(conn, &photo_ids, &[]);
+        let result = unit_of_work(conn, |conn, journal| trash_photos(conn, journal, &photo_ids));
+        record_operation(conn, &actor, AuditAction::PhotoDelete, &photo_ids, &[], before, &result);
+        unwrap_ret!(result, Status::InternalServerError);
 
-    (Status::Ok, msg!("Success"))
+        (Status::Ok, msg!("Success"))
+    }).await, Status::InternalServerError)
 }
 
 
@@ -57,10 +72,238 @@ pub fn del_photo(input: Json<Value>) -> (Status, Json<Value>) {
 ///   (skips any IDs that don't exist)
 /// - `Status::InternalServerError` (500) if retrieval fails
 #[post("/photo/get", format = "json", data = "<input>")]
-pub fn get_photos(input: Json<Value>) -> Result<Json<Vec<Photo>>, (Status, Json<Value>)> {
+pub async fn get_photos(db: Db, input: Json<Value>) -> Result<Json<Vec<Photo>>, (Status, Json<Value>)> {
+    let photo_ids = unwrap_err!(input.get_value::<Vec<i64>>("photo_ids"), Status::BadRequest);
+    unwrap_err!(db.run(move |conn| {
+        let photos = unwrap_err!(get_photo(conn, &photo_ids), Status::InternalServerError);
+        Ok(Json(photos))
+    }).await, Status::InternalServerError)
+}
+
+
+/// Retrieve the complete EXIF/XMP metadata of a photo, as extracted at ingest
+///
+/// # Route
+/// `GET /photo/<id>/metadata`
+///
+/// # Returns
+/// - `Ok(Json<Value>)` containing a JSON object keyed by `Group:Tag` (e.g. `"ExifIFD:WhiteBalance"`)
+/// - `Status::NotFound` (404) if the photo does not exist or has no stored metadata
+/// - `Status::InternalServerError` (500) if retrieval fails
+#[get("/photo/<id>/metadata")]
+pub async fn photo_metadata(db: Db, id: i64) -> Result<Json<Value>, (Status, Json<Value>)> {
+    unwrap_err!(db.run(move |conn| {
+        match get_photo_metadata(conn, id) {
+            Ok(metadata) => Ok(Json(unwrap_err!(serde_json::from_str::<Value>(&metadata.metadata), Status::InternalServerError))),
+            Err(Error::NotFound) => Err((Status::NotFound, msg!("No metadata stored for photo {}", id))),
+            Err(err) => Err((Status::InternalServerError, msg!("Failed to query photo metadata: {:#?}", err))),
+        }
+    }).await, Status::InternalServerError)
+}
+
+
+/// Set the star rating, color label and/or pick flag of multiple photos
+///
+/// # Route
+/// `PATCH /photo/flags`
+///
+/// # Request Body
+/// JSON object with:
+/// - `photo_ids`: JSON array of photo IDs to update
+/// - `rating` (optional): Star rating from 0 to 5
+/// - `color_label` (optional): One of `"red"`, `"yellow"`, `"green"`, `"blue"`, `"purple"`, or `null` to clear
+/// - `pick_flag` (optional): -1 (rejected), 0 (unflagged) or 1 (picked)
+///
+/// Fields that are left out are not changed. The photos' XMP sidecars are updated to match.
+///
+/// # Returns
+/// - `Status::Ok` (200) if the photos were updated (skips any IDs that don't exist). Sidecars that could
+///   not be updated are listed in `warnings`.
+/// - `Status::BadRequest` (400) if no flag is given or a value is out of range
+/// - `Status::InternalServerError` (500) if the update fails
+#[patch("/photo/flags", format = "json", data = "<input>")]
+pub async fn set_flags(db: Db, input: Json<Value>) -> (Status, Json<Value>) {
+    let photo_ids = unwrap_ret!(input.get_value::<Vec<i64>>("photo_ids"), Status::BadRequest);
+    let flags = PhotoFlags {
+        rating: unwrap_ret!(input.get_optional::<i8>("rating"), Status::BadRequest),
+        color_label: unwrap_ret!(input.get_optional::<Option<String>>("color_label"), Status::BadRequest)
+            .map(|label| label.map(|label| label.to_lowercase())),
+        pick_flag: unwrap_ret!(input.get_optional::<i8>("pick_flag"), Status::BadRequest),
+    };
+
+    if flags.is_empty() {
+        return (Status::BadRequest, msg!("At least one of rating, colorLabel or pickFlag must be given"));
+    }
+    unwrap_ret!(flags.validate(), Status::BadRequest);
+
+    unwrap_ret!(db.run(move |conn| {
+        let rows = unwrap_ret!(set_photo_flags(conn, &photo_ids, &flags), Status::InternalServerError);
+        let failures = sync_xmp_sidecars(conn, &photo_ids);
+
+        (Status::Ok, with_sidecar_warnings(msg!("Updated {} photos", rows), failures))
+    }).await, Status::InternalServerError)
+}
+
+
+/// Set or shift the capture time and/or timezone of multiple photos, e.g. to correct camera clock drift
+///
+/// # Route
+/// `PATCH /photo/time`
+///
+/// # Request Body
+/// JSON object with:
+/// - `photo_ids`: JSON array of photo IDs to update
+/// - At most one of:
+///   - `date`: New capture time for all photos, in local time (e.g. `"2025-06-01T10:00:00"`)
+///   - `shift`: Offset to add to each photo's capture time (e.g. `"+1h 3m 12s"`, `"-30s"`)
+///   - `reference_photo_id` and `reference_date`: Shift all photos by the offset that makes the
+///     reference photo's capture time equal `reference_date`
+/// - `timezone` (optional): New UTC offset for all photos (e.g. `"+09:00"`)
+/// - `dry` (optional): If `true`, only return the planned changes without applying them
+/// - `write_to_file` (optional): If `true`, also write the new capture time into the original files
+///   (this changes their stored hash). XMP sidecars are always updated.
+///
+/// Applying a new time resolves the photos' `missing_date` issue, and a new timezone their `missing_timezone` issue.
+///
+/// # Returns
+/// - `Ok(Json<Vec<PhotoTimeChange>>)` with the old and new time of every photo (skips any IDs that don't exist),
+///   and a `warning` for those whose sidecar could not be updated
+/// - `Status::BadRequest` (400) if the request is malformed or the reference photo doesn't exist
+/// - `Status::InternalServerError` (500) if the update fails, in which case nothing is changed, or if the
+///   write-back fails, in which case the new times are kept and `rewritten` lists the files already rewritten
+#[patch("/photo/time", format = "json", data = "<input>")]
+pub async fn set_time(db: Db, input: Json<Value>) -> Result<Json<Vec<PhotoTimeChange>>, (Status, Json<Value>)> {
     let photo_ids = unwrap_err!(input.get_value::<Vec<i64>>("photo_ids"), Status::BadRequest);
-    let mut conn = unwrap_err!(DB_POOL.get(), Status::InternalServerError);
+    let date = unwrap_err!(input.get_optional::<NaiveDateTime>("date"), Status::BadRequest);
+    let shift = unwrap_err!(input.get_optional::<String>("shift"), Status::BadRequest);
+    let reference_photo_id = unwrap_err!(input.get_optional::<i64>("reference_photo_id"), Status::BadRequest);
+    let reference_date = unwrap_err!(input.get_optional::<NaiveDateTime>("reference_date"), Status::BadRequest);
+    let timezone = unwrap_err!(input.get_optional::<String>("timezone"), Status::BadRequest);
+    let dry = unwrap_err!(input.get_optional::<bool>("dry"), Status::BadRequest).unwrap_or(false);
+    let write_to_file = unwrap_err!(input.get_optional::<bool>("write_to_file"), Status::BadRequest).unwrap_or(false);
+
+    let modes = [date.is_some(), shift.is_some(), reference_photo_id.is_some() || reference_date.is_some()];
+    if modes.iter().filter(|&&mode| mode).count() > 1 {
+        return Err((Status::BadRequest, msg!("Only one of date, shift or referencePhotoId/referenceDate may be given")));
+    }
+    if modes.iter().all(|&mode| !mode) && timezone.is_none() {
+        return Err((Status::BadRequest, msg!("One of date, shift, referencePhotoId/referenceDate or timezone must be given")));
+    }
+    if let Some(tz) = &timezone && !is_utc_offset(tz) {
+        return Err((Status::BadRequest, msg!("timezone must be a UTC offset like \"+09:00\"")));
+    }
+
+    unwrap_err!(db.run(move |conn| {
+        // Resolve the shift to apply, if any
+        let offset: Option<TimeDelta> = match (shift, reference_photo_id, reference_date) {
+            (Some(shift), _, _) => Some(unwrap_err!(parse_time_shift(&shift), Status::BadRequest)),
+            (None, Some(reference_id), Some(reference_date)) => {
+                let reference = unwrap_err!(get_photo(conn, &[reference_id]), Status::InternalServerError).pop();
+                match reference {
+                    Some(reference) => Some(reference_date - reference.photo_date),
+                    None => return Err((Status::BadRequest, msg!("Reference photo {} not found", reference_id))),
+                }
+            }
+            (None, None, None) => None,
+            _ => return Err((Status::BadRequest, msg!("referencePhotoId and referenceDate must be given together"))),
+        };
+
+        // Plan the changes
+        let photos = unwrap_err!(get_photo(conn, &photo_ids), Status::InternalServerError);
+        let mut changes: Vec<PhotoTimeChange> = Vec::with_capacity(photos.len());
+        for photo in photos {
+            let new_date = match (date, offset) {
+                (Some(date), _) => date,
+                (None, Some(offset)) => unwrap_err!(photo.photo_date.checked_add_signed(offset).ok_or("Shifted date is out of range"), Status::BadRequest),
+                (None, None) => photo.photo_date,
+            };
+
+            changes.push(PhotoTimeChange {
+                id: photo.id,
+                file_name: photo.file_name,
+                old_date: photo.photo_date,
+                new_date,
+                new_timezone: timezone.clone().unwrap_or_else(|| photo.photo_timezone.clone()),
+                old_timezone: photo.photo_timezone,
+                warning: None,
+            });
+        }
+
+        if dry {
+            return Ok(Json(changes));
+        }
+
+        // Apply the changes to the database as one unit, resolving the paths of the files to rewrite
+        let photo_ids: Vec<i64> = changes.iter().map(|change| change.id).collect();
+        let photo_paths = unwrap_err!(unit_of_work(conn, |conn, _| {
+            let mut photo_paths: Vec<(&PhotoTimeChange, PathBuf)> = Vec::new();
+            for change in &changes {
+                set_photo_time(conn, change.id, change.new_date, &change.new_timezone)?;
+                if write_to_file {
+                    photo_paths.push((change, get_photo_path(conn, change.id)?));
+                }
+            }
+
+            // A user-supplied time counts as a fix for times that fell back to defaults at ingest
+            if modes.iter().any(|&mode| mode) {
+                resolve_metadata_issue(conn, &photo_ids, MetadataIssue::MissingDate)?;
+            }
+            if timezone.is_some() {
+                resolve_metadata_issue(conn, &photo_ids, MetadataIssue::MissingTimezone)?;
+            }
+            Ok(photo_paths)
+        }), Status::InternalServerError);
+
+        // Only then rewrite the originals. A file can't be restored once exiftool rewrote it, so a
+        // failure reports which files already carry the new time.
+        let storage_root = PathBuf::from(env::var("STORAGE_ROOT").unwrap());
+        let mut rewritten: Vec<String> = Vec::new();
+        for (change, photo_path) in &photo_paths {
+            let mut rewrite = || -> anyhow::Result<()> {
+                write_photo_date_fs(photo_path, change.new_date, &change.new_timezone)?;
+
+                // The original was rewritten, so its hash and size have changed
+                let full_photo_path = photo_path.prefix_within(&storage_root)?;
+                set_photo_hash(conn, change.id, &full_photo_path.get_hash(), full_photo_path.get_size_on_disk())?;
+                Ok(())
+            };
+            if let Err(e) = rewrite() {
+                return Err((Status::InternalServerError, Json(json!({
+                    "message": format!("The new times were saved, but {} could not be rewritten: {e}", photo_path.display()),
+                    "rewritten": rewritten,
+                }))));
+            }
+            rewritten.push(photo_path.display().to_string());
+        }
+
+        let mut failures = sync_xmp_sidecars(conn, &photo_ids);
+        for change in &mut changes {
+            change.warning = failures.remove(&change.id);
+        }
+
+        Ok(Json(changes))
+    }).await, Status::InternalServerError)
+}
+
+
+/// List photos whose metadata fell back to default values at ingest (e.g. a 1970 capture date
+/// because the date couldn't be read), grouped by problem
+///
+/// # Route
+/// `GET /photo/needs-attention`
+///
+/// # Returns
+/// - `Ok(Json<Value>)` object mapping each issue (e.g. `"missing_date"`, `"unknown_camera"`)
+///   to the photos affected by it. Issues without any affected photos are left out.
+/// - `Status::InternalServerError` (500) if retrieval fails
+#[get("/photo/needs-attention")]
+pub async fn photos_needing_attention(db: Db) -> Result<Json<Value>, (Status, Json<Value>)> {
+    unwrap_err!(db.run(move |conn| {
+        let mut grouped: BTreeMap<String, Vec<Photo>> = BTreeMap::new();
+        for (issue, photo) in unwrap_err!(get_photos_with_issues(conn), Status::InternalServerError) {
+            grouped.entry(issue).or_default().push(photo);
+        }
 
-    let photos = unwrap_err!(get_photo(&mut conn, &photo_ids), Status::InternalServerError);
-    Ok(Json(photos))
+        Ok(Json(serde_json::json!(grouped)))
+    }).await, Status::InternalServerError)
 }
@@ -0,0 +1,167 @@
+use crate::_utils::json_map::JsonMap;
+use crate::db::operations::query::get_photos_in_smart_album;
+use crate::db::operations::smart_album::{create_smart_album, delete_smart_album, get_all_smart_albums, get_smart_album, update_smart_album};
+use crate::db::pool::Db;
+use crate::models::filter::PhotoFilter;
+use crate::models::photo::Photo;
+use crate::models::smart_album::{NewSmartAlbum, SmartAlbum, SmartQuery};
+use crate::{msg, unwrap_err, unwrap_ret};
+use chrono::Local;
+use diesel::result::Error;
+use rocket::http::Status;
+use rocket::serde::json::{Json, Value};
+use rocket::{delete, get, patch, post};
+use serde_json::json;
+
+/// Checks that a smart album name is non-empty and fits in the database
+fn validate_album_name(album_name: &str) -> anyhow::Result<()> {
+    if album_name.trim().is_empty() {
+        return Err(anyhow::anyhow!("Album names must not be empty"));
+    }
+    if album_name.len() > 255 {
+        return Err(anyhow::anyhow!("Album names must be at most 255 bytes long"));
+    }
+    Ok(())
+}
+
+/// Retrieves every smart album
+///
+/// # Endpoint
+/// `GET /smart-album/all`
+///
+/// # Returns
+/// - `200 OK`: JSON array of all smart albums, sorted by name. Each carries its `smartAlbumId`, `albumName`,
+///   `query` and `createdAt`.
+/// - `500 Internal Server Error`: Database or another server error occurred
+#[get("/smart-album/all")]
+pub async fn all_smart_albums(db: Db) -> Result<Json<Value>, (Status, Json<Value>)> {
+    unwrap_err!(db.run(move |conn| {
+        let smart_albums = unwrap_err!(get_all_smart_albums(conn), Status::InternalServerError);
+
+        Ok(Json(Value::Array(smart_albums.iter().map(SmartAlbum::to_json).collect())))
+    }).await, Status::InternalServerError)
+}
+
+/// Creates a smart album from a saved query over photo metadata
+///
+/// # Endpoint
+/// `POST /smart-album/new`
+///
+/// # Request Body
+/// JSON object with:
+/// - `album_name`: Name for the new smart album (String)
+/// - `query`: A `SmartQuery`: `match` (`all` or `any`, defaults to `all`) and a list of `rules`, each with a
+///   `field` (a `photos` column such as `lens_model`, `iso` or `photo_date`), an `op` (`eq`, `ne`, `lt`, `le`,
+///   `gt`, `ge`, `contains` for text or `year` for dates) and a `value`
+///
+/// # Returns
+/// - `201 Created`: Smart album was created; body contains its `smartAlbumId`
+/// - `400 Bad Request`: Missing or invalid album_name or query in request body
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[post("/smart-album/new", format = "json", data = "<input>")]
+pub async fn new_smart_album(db: Db, input: Json<Value>) -> (Status, Json<Value>) {
+    let album_name = unwrap_ret!(input.get_value::<String>("album_name"), Status::BadRequest);
+    unwrap_ret!(validate_album_name(&album_name), Status::BadRequest);
+    let query = unwrap_ret!(input.get_value::<SmartQuery>("query"), Status::BadRequest);
+    unwrap_ret!(query.validate(), Status::BadRequest);
+    unwrap_ret!(db.run(move |conn| {
+        let smart_album = NewSmartAlbum {
+            album_name: album_name.trim().to_string(),
+            query: unwrap_ret!(serde_json::to_string(&query), Status::InternalServerError),
+            created_at: Local::now().naive_local(),
+        };
+        let smart_album_id = unwrap_ret!(create_smart_album(conn, smart_album), Status::InternalServerError);
+        (Status::Created, Json(json!({ "smartAlbumId": smart_album_id })))
+    }).await, Status::InternalServerError)
+}
+
+/// Renames a smart album and/or replaces its query
+///
+/// # Endpoint
+/// `PATCH /smart-album/<id>`
+///
+/// # Request Body
+/// JSON object with (at least one of):
+/// - `album_name` (optional): New name for the smart album (String)
+/// - `query` (optional): New `SmartQuery`, see `POST /smart-album/new`
+///
+/// # Returns
+/// - `200 OK`: Smart album was updated
+/// - `400 Bad Request`: Neither field given, or an invalid album_name or query
+/// - `404 Not Found`: Smart album with the specified ID does not exist
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[patch("/smart-album/<id>", format = "json", data = "<input>")]
+pub async fn update_smart(db: Db, id: i32, input: Json<Value>) -> (Status, Json<Value>) {
+    let album_name = unwrap_ret!(input.get_optional::<String>("album_name"), Status::BadRequest);
+    let query = unwrap_ret!(input.get_optional::<SmartQuery>("query"), Status::BadRequest);
+    if album_name.is_none() && query.is_none() {
+        return (Status::BadRequest, msg!("Nothing to update: give albumName and/or query"));
+    }
+    if let Some(album_name) = &album_name {
+        unwrap_ret!(validate_album_name(album_name), Status::BadRequest);
+    }
+    if let Some(query) = &query {
+        unwrap_ret!(query.validate(), Status::BadRequest);
+    }
+    let query = match query.map(|query| serde_json::to_string(&query)).transpose() {
+        Ok(query) => query,
+        Err(err) => return (Status::InternalServerError, msg!("Failed to serialize query: {}", err)),
+    };
+    unwrap_ret!(db.run(move |conn| {
+        match get_smart_album(conn, id) {
+            Ok(_) => (),
+            Err(Error::NotFound) => return (Status::NotFound, msg!("Smart album not found")),
+            Err(err) => return (Status::InternalServerError, msg!("Failed to query smart album: {:#?}", err)),
+        }
+
+        unwrap_ret!(update_smart_album(conn, id, album_name.as_deref().map(str::trim), query.as_deref()), Status::InternalServerError);
+        (Status::Ok, msg!("Success"))
+    }).await, Status::InternalServerError)
+}
+
+/// Deletes a smart album. The photos it lists are not affected.
+///
+/// # Endpoint
+/// `DELETE /smart-album/<id>/delete`
+///
+/// # Returns
+/// - `200 OK`: Smart album was successfully deleted
+/// - `404 Not Found`: Smart album with the specified ID does not exist
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[delete("/smart-album/<id>/delete")]
+pub async fn del_smart_album(db: Db, id: i32) -> (Status, Json<Value>) {
+    unwrap_ret!(db.run(move |conn| {
+        match delete_smart_album(conn, id) {
+            Ok(0) => (Status::NotFound, msg!("Smart album not found")),
+            Ok(_) => (Status::Ok, msg!("Success")),
+            Err(err) => (Status::InternalServerError, msg!("Failed to delete smart album: {:#?}", err)),
+        }
+    }).await, Status::InternalServerError)
+}
+
+/// Retrieves the photos matching a smart album's query, evaluated live
+///
+/// # Endpoint
+/// `GET /smart-album/<id>/photos`
+///
+/// # Query Parameters
+/// Optional `PhotoFilter` fields: `minRating`, `colorLabel`, `pickFlag`, `collapseStacks`, `sort`, `order`, `page`, `perPage`
+///
+/// # Returns
+/// - `200 OK`: JSON array of matching photos
+/// - `404 Not Found`: Smart album with the specified ID does not exist
+/// - `500 Internal Server Error`: Database or another server error occurred
+#[get("/smart-album/<id>/photos?<filter..>")]
+pub async fn smart_album_photos(db: Db, id: i32, filter: PhotoFilter) -> Result<Json<Vec<Photo>>, (Status, Json<Value>)> {
+    unwrap_err!(db.run(move |conn| {
+        let smart_album = match get_smart_album(conn, id) {
+            Ok(smart_album) => smart_album,
+            Err(Error::NotFound) => return Err((Status::NotFound, msg!("Smart album not found"))),
+            Err(err) => return Err((Status::InternalServerError, msg!("Failed to query smart album: {:#?}", err))),
+        };
+        let query = unwrap_err!(smart_album.parsed_query(), Status::InternalServerError);
+
+        let photos = unwrap_err!(get_photos_in_smart_album(conn, &query, &filter), Status::InternalServerError);
+        Ok(Json(photos))
+    }).await, Status::InternalServerError)
+}
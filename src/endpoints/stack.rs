@@ -0,0 +1,111 @@
+use crate::_utils::actor::Actor;
+use crate::_utils::json_map::JsonMap;
+use crate::audit::record_operation;
+use crate::db::operations::stack::{delete_stack, get_stack, get_stack_photos, set_stack_cover};
+use crate::db::pool::Db;
+use crate::models::audit::AuditAction;
+use crate::{msg, unwrap_err, unwrap_ret};
+use diesel::result::Error;
+use diesel::Connection;
+use rocket::http::Status;
+use rocket::serde::json::{Json, Value};
+use rocket::{delete, get, patch};
+use serde_json::json;
+
+/// Retrieves a burst or bracket with its photos
+///
+/// # Endpoint
+/// `GET /stack/<id>`
+///
+/// # URL Parameters
+/// - `id`: The ID of the stack (i32)
+///
+/// # Returns
+/// - `200 OK`: JSON object with `stack` (`stackId`, `kind`, `coverId`, `createdAt`) and `photos`
+///   in capture order. Trashed photos are left out.
+/// - `404 Not Found`: Stack with the specified ID does not exist
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[get("/stack/<id>")]
+pub async fn stack_photos(db: Db, id: i32) -> Result<Json<Value>, (Status, Json<Value>)> {
+    unwrap_err!(db.run(move |conn| {
+        let stack = match get_stack(conn, id) {
+            Ok(stack) => stack,
+            Err(Error::NotFound) => return Err((Status::NotFound, msg!("Stack not found"))),
+            Err(err) => return Err((Status::InternalServerError, msg!("Failed to query stack: {}", err))),
+        };
+        let photos = unwrap_err!(get_stack_photos(conn, id), Status::InternalServerError);
+
+        Ok(Json(json!({
+            "stack": stack,
+            "photos": photos,
+        })))
+    }).await, Status::InternalServerError)
+}
+
+/// Chooses the photo shown in place of a stack when listings collapse stacks
+///
+/// # Endpoint
+/// `PATCH /stack/<id>/cover`
+///
+/// # URL Parameters
+/// - `id`: The ID of the stack (i32)
+///
+/// # Request Body
+/// JSON object with:
+/// - `photo_id`: The new cover, which must be part of the stack (i64)
+///
+/// # Returns
+/// - `200 OK`: The cover was changed
+/// - `400 Bad Request`: Missing `photo_id`, or the photo is not part of the stack (or trashed)
+/// - `404 Not Found`: Stack with the specified ID does not exist
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[patch("/stack/<id>/cover", format = "json", data = "<input>")]
+pub async fn set_cover(db: Db, actor: Actor, id: i32, input: Json<Value>) -> (Status, Json<Value>) {
+    let photo_id = unwrap_ret!(input.get_value::<i64>("photo_id"), Status::BadRequest);
+    unwrap_ret!(db.run(move |conn| {
+        match get_stack(conn, id) {
+            Ok(_) => (),
+            Err(Error::NotFound) => return (Status::NotFound, msg!("Stack not found")),
+            Err(err) => return (Status::InternalServerError, msg!("Failed to query stack: {}", err)),
+        }
+        let photos = unwrap_ret!(get_stack_photos(conn, id), Status::InternalServerError);
+        if !photos.iter().any(|photo| photo.id == photo_id) {
+            return (Status::BadRequest, msg!("Photo {} is not part of stack {}", photo_id, id));
+        }
+
+        let result = set_stack_cover(conn, id, Some(photo_id));
+        record_operation(conn, &actor, AuditAction::StackCover, &[photo_id], &[], None, &result);
+        unwrap_ret!(result, Status::InternalServerError);
+
+        (Status::Ok, msg!("Success"))
+    }).await, Status::InternalServerError)
+}
+
+/// Dissolves a stack. Its photos are kept and show up individually again.
+///
+/// # Endpoint
+/// `DELETE /stack/<id>`
+///
+/// # URL Parameters
+/// - `id`: The ID of the stack to dissolve (i32)
+///
+/// # Returns
+/// - `200 OK`: The stack was dissolved
+/// - `404 Not Found`: Stack with the specified ID does not exist
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[delete("/stack/<id>")]
+pub async fn del_stack(db: Db, actor: Actor, id: i32) -> (Status, Json<Value>) {
+    unwrap_ret!(db.run(move |conn| {
+        let photos = unwrap_ret!(get_stack_photos(conn, id), Status::InternalServerError);
+        let photo_ids: Vec<i64> = photos.iter().map(|photo| photo.id).collect();
+
+        let result = conn.transaction(|conn| delete_stack(conn, id));
+        if let Ok(0) = result {
+            return (Status::NotFound, msg!("Stack not found"));
+        }
+        record_operation(conn, &actor, AuditAction::StackDelete, &photo_ids, &[], None, &result);
+        unwrap_ret!(result, Status::InternalServerError);
+
+        (Status::Ok, msg!("Success"))
+    }).await, Status::InternalServerError)
+}
@@ -0,0 +1,254 @@
+use crate::_utils::json_map::JsonMap;
+use crate::db::operations::join_photo_tag::{add_tag_to_photo, get_tagged_photo_ids, remove_tag_from_photo};
+use crate::db::operations::photo::get_photo;
+use crate::db::operations::query::get_photos_with_tag;
+use crate::db::operations::tag::{delete_tag, find_or_create_tag_path, find_tag, get_all_tags, get_tag, get_tag_descendants, merge_tags, rename_tag as rename_tag_db};
+use crate::db::pool::Db;
+use crate::models::filter::PhotoFilter;
+use crate::models::photo::Photo;
+use crate::models::tag::{split_tag_path, validate_tag_name, Tag};
+use crate::sidecar::{sync_xmp_sidecars, with_sidecar_warnings};
+use crate::{msg, unwrap_err, unwrap_ret};
+use diesel::result::Error;
+use rocket::http::Status;
+use rocket::serde::json::{Json, Value};
+use rocket::{delete, get, patch, post};
+use serde_json::json;
+
+/// Retrieves every tag in the keyword hierarchy
+///
+/// # Endpoint
+/// `GET /tag/all`
+///
+/// # Returns
+/// - `200 OK`: JSON array of all tags
+/// - `500 Internal Server Error`: Database or another server error occurred
+///
+/// # Response Body
+/// Array of Tag objects, each containing:
+/// - `tagId`: Tag's unique identifier (i32)
+/// - `tagName`: Name of this level of the keyword (String)
+/// - `parentId`: ID of the parent tag, or `null` for root keywords
+#[get("/tag/all")]
+pub async fn all_tags(db: Db) -> Result<Json<Vec<Tag>>, (Status, Json<Value>)> {
+    unwrap_err!(db.run(move |conn| {
+        let tags = unwrap_err!(get_all_tags(conn), Status::InternalServerError);
+
+        Ok(Json(tags))
+    }).await, Status::InternalServerError)
+}
+
+/// Creates a hierarchical keyword, along with any missing levels above it
+///
+/// # Endpoint
+/// `POST /tag/new`
+///
+/// # Request Body
+/// JSON object with:
+/// - `tag_path`: Full keyword, with levels separated by `|` (e.g. `"Places|Japan|Kyoto"`)
+///
+/// # Returns
+/// - `201 Created`: Keyword was created (or already existed); body contains its `tagId`
+/// - `400 Bad Request`: Missing or invalid tag_path in request body
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[post("/tag/new", format = "json", data = "<input>")]
+pub async fn new_tag(db: Db, input: Json<Value>) -> (Status, Json<Value>) {
+    let tag_path = unwrap_ret!(input.get_value::<String>("tag_path"), Status::BadRequest);
+    let segments = unwrap_ret!(split_tag_path(&tag_path), Status::BadRequest);
+    unwrap_ret!(db.run(move |conn| {
+        let tag_id = unwrap_ret!(find_or_create_tag_path(conn, &segments), Status::InternalServerError);
+        (Status::Created, Json(json!({ "tagId": tag_id })))
+    }).await, Status::InternalServerError)
+}
+
+/// Renames a single level of a keyword, keeping its place in the hierarchy
+///
+/// # Endpoint
+/// `PATCH /tag/<id>/rename`
+///
+/// # Request Body
+/// JSON object with:
+/// - `tag_name`: New name for the tag (String, must not contain `|`)
+///
+/// # Returns
+/// - `200 OK`: Tag was successfully renamed. XMP sidecars of the affected photos that could not be updated are listed in `warnings`
+/// - `400 Bad Request`: Missing or invalid tag_name in the request body
+/// - `404 Not Found`: Tag with the specified ID does not exist
+/// - `409 Conflict`: A sibling tag with the same name already exists (merge them instead)
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[patch("/tag/<id>/rename", format = "json", data = "<input>")]
+pub async fn rename_tag(db: Db, id: i32, input: Json<Value>) -> (Status, Json<Value>) {
+    let tag_name = unwrap_ret!(input.get_value::<String>("tag_name"), Status::BadRequest);
+    unwrap_ret!(validate_tag_name(&tag_name), Status::BadRequest);
+    let tag_name = tag_name.trim().to_string();
+    unwrap_ret!(db.run(move |conn| {
+        let tag = match get_tag(conn, &[id]).map(|mut tags| tags.pop()) {
+            Ok(Some(tag)) => tag,
+            Ok(None) => return (Status::NotFound, msg!("Tag not found")),
+            Err(err) => return (Status::InternalServerError, msg!("Failed to query tag: {:#?}", err)),
+        };
+
+        if unwrap_ret!(find_tag(conn, &tag_name, tag.parent_id), Status::InternalServerError).is_some_and(|sibling| sibling.id != id) {
+            return (Status::Conflict, msg!("A tag named {} already exists at this level", tag_name));
+        }
+
+        unwrap_ret!(rename_tag_db(conn, id, &tag_name), Status::InternalServerError);
+
+        // Every keyword at or below the renamed level changes
+        let affected_tags = unwrap_ret!(get_tag_descendants(conn, id), Status::InternalServerError);
+        let affected_photos = unwrap_ret!(get_tagged_photo_ids(conn, &affected_tags), Status::InternalServerError);
+        let failures = sync_xmp_sidecars(conn, &affected_photos);
+
+        (Status::Ok, with_sidecar_warnings(msg!("Success"), failures))
+    }).await, Status::InternalServerError)
+}
+
+/// Merges one tag into another, moving its photos and child tags to the target
+///
+/// # Endpoint
+/// `POST /tag/merge`
+///
+/// # Request Body
+/// JSON object with:
+/// - `source_id`: ID of the tag to merge and remove (i32)
+/// - `target_id`: ID of the tag to merge into (i32)
+///
+/// # Returns
+/// - `200 OK`: Tags were merged. XMP sidecars of the affected photos that could not be updated are listed in `warnings`
+/// - `400 Bad Request`: Missing IDs, or the target is the source or nested below it
+/// - `404 Not Found`: Either tag does not exist
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[post("/tag/merge", format = "json", data = "<input>")]
+pub async fn merge_tag(db: Db, input: Json<Value>) -> (Status, Json<Value>) {
+    let source_id = unwrap_ret!(input.get_value::<i32>("source_id"), Status::BadRequest);
+    let target_id = unwrap_ret!(input.get_value::<i32>("target_id"), Status::BadRequest);
+    unwrap_ret!(db.run(move |conn| {
+        if unwrap_ret!(get_tag(conn, &[source_id, target_id]), Status::InternalServerError).len() != 2 {
+            return (Status::NotFound, msg!("Tag not found"));
+        }
+
+        // Merging a tag into its own subtree would orphan the subtree
+        if unwrap_ret!(get_tag_descendants(conn, source_id), Status::InternalServerError).contains(&target_id) {
+            return (Status::BadRequest, msg!("Cannot merge a tag into itself or one of its children"));
+        }
+
+        let source_tags = unwrap_ret!(get_tag_descendants(conn, source_id), Status::InternalServerError);
+        let affected_photos = unwrap_ret!(get_tagged_photo_ids(conn, &source_tags), Status::InternalServerError);
+
+        unwrap_ret!(merge_tags(conn, source_id, target_id), Status::InternalServerError);
+        let failures = sync_xmp_sidecars(conn, &affected_photos);
+
+        (Status::Ok, with_sidecar_warnings(msg!("Success"), failures))
+    }).await, Status::InternalServerError)
+}
+
+/// Deletes a tag along with every tag nested below it. Tagged photos are not affected.
+///
+/// # Endpoint
+/// `DELETE /tag/<id>/delete`
+///
+/// # Returns
+/// - `200 OK`: Tag was successfully deleted. XMP sidecars of the affected photos that could not be updated are listed in `warnings`
+/// - `404 Not Found`: Tag with the specified ID does not exist
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[delete("/tag/<id>/delete")]
+pub async fn del_tag(db: Db, id: i32) -> (Status, Json<Value>) {
+    unwrap_ret!(db.run(move |conn| {
+        let deleted_tags = unwrap_ret!(get_tag_descendants(conn, id), Status::InternalServerError);
+        let affected_photos = unwrap_ret!(get_tagged_photo_ids(conn, &deleted_tags), Status::InternalServerError);
+
+        match delete_tag(conn, id) {
+            Ok(_) => {
+                let failures = sync_xmp_sidecars(conn, &affected_photos);
+                (Status::Ok, with_sidecar_warnings(msg!("Success"), failures))
+            },
+            Err(Error::NotFound) => (Status::NotFound, msg!("Tag not found")),
+            Err(err) => (Status::InternalServerError, msg!("Failed to delete tag from database: {:#?}", err)),
+        }
+    }).await, Status::InternalServerError)
+}
+
+/// Adds tags to multiple photos
+///
+/// # Endpoint
+/// `POST /tag/assign`
+///
+/// # Request Body
+/// JSON object with:
+/// - `tag_ids`: Array of tag IDs to add (Vec<i32>)
+/// - `photo_ids`: Array of photo IDs to tag (Vec<i64>)
+///
+/// The photos' XMP sidecars are updated to match; those that could not be are listed in `warnings`.
+///
+/// # Returns
+/// - `200 OK`: Photos were tagged (photos that already had a tag are left as is)
+/// - `400 Bad Request`: Missing or invalid tag_ids or photo_ids in request body
+/// - `404 Not Found`: One of the tags or photos does not exist (or the photo is trashed). Nothing is tagged
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[post("/tag/assign", format = "json", data = "<input>")]
+pub async fn assign_tag(db: Db, input: Json<Value>) -> (Status, Json<Value>) {
+    let tag_ids = unwrap_ret!(input.get_value::<Vec<i32>>("tag_ids"), Status::BadRequest);
+    let photo_ids = unwrap_ret!(input.get_value::<Vec<i64>>("photo_ids"), Status::BadRequest);
+    unwrap_ret!(db.run(move |conn| {
+        // Unknown IDs would be skipped silently by the insert
+        let tags = unwrap_ret!(get_tag(conn, &tag_ids), Status::InternalServerError);
+        if let Some(tag_id) = tag_ids.iter().find(|tag_id| !tags.iter().any(|tag| tag.id == **tag_id)) {
+            return (Status::NotFound, msg!("Tag {} not found", tag_id));
+        }
+        let photos = unwrap_ret!(get_photo(conn, &photo_ids), Status::InternalServerError);
+        if let Some(photo_id) = photo_ids.iter().find(|photo_id| !photos.iter().any(|photo| photo.id == **photo_id)) {
+            return (Status::NotFound, msg!("Photo {} not found", photo_id));
+        }
+
+        let rows = unwrap_ret!(add_tag_to_photo(conn, &tag_ids, &photo_ids), Status::InternalServerError);
+        let failures = sync_xmp_sidecars(conn, &photo_ids);
+        (Status::Ok, with_sidecar_warnings(msg!("Added {} tags", rows), failures))
+    }).await, Status::InternalServerError)
+}
+
+/// Removes tags from multiple photos
+///
+/// # Endpoint
+/// `POST /tag/unassign`
+///
+/// # Request Body
+/// JSON object with:
+/// - `tag_ids`: Array of tag IDs to remove (Vec<i32>)
+/// - `photo_ids`: Array of photo IDs to untag (Vec<i64>)
+///
+/// The photos' XMP sidecars are updated to match; those that could not be are listed in `warnings`.
+///
+/// # Returns
+/// - `200 OK`: Tags were removed from the photos
+/// - `400 Bad Request`: Missing or invalid tag_ids or photo_ids in request body
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[post("/tag/unassign", format = "json", data = "<input>")]
+pub async fn unassign_tag(db: Db, input: Json<Value>) -> (Status, Json<Value>) {
+    let tag_ids = unwrap_ret!(input.get_value::<Vec<i32>>("tag_ids"), Status::BadRequest);
+    let photo_ids = unwrap_ret!(input.get_value::<Vec<i64>>("photo_ids"), Status::BadRequest);
+    unwrap_ret!(db.run(move |conn| {
+        let rows = unwrap_ret!(remove_tag_from_photo(conn, &tag_ids, &photo_ids), Status::InternalServerError);
+        let failures = sync_xmp_sidecars(conn, &photo_ids);
+        (Status::Ok, with_sidecar_warnings(msg!("Removed {} tags", rows), failures))
+    }).await, Status::InternalServerError)
+}
+
+/// Retrieves all photos tagged with a keyword or any keyword nested below it
+///
+/// # Endpoint
+/// `GET /tag/<id>/photos`
+///
+/// # Query Parameters
+/// Optional `PhotoFilter` fields: `minRating`, `colorLabel`, `pickFlag`, `collapseStacks`, `sort`, `order`, `page`, `perPage`
+///
+/// # Returns
+/// - `200 OK`: JSON array of tagged photos
+/// - `500 Internal Server Error`: Database or another server error occurred
+#[get("/tag/<id>/photos?<filter..>")]
+pub async fn tag_photos(db: Db, id: i32, filter: PhotoFilter) -> Result<Json<Vec<Photo>>, (Status, Json<Value>)> {
+    unwrap_err!(db.run(move |conn| {
+        let tag_ids = unwrap_err!(get_tag_descendants(conn, id), Status::InternalServerError);
+        let photos = unwrap_err!(get_photos_with_tag(conn, &tag_ids, &filter), Status::InternalServerError);
+        Ok(Json(photos))
+    }).await, Status::InternalServerError)
+}
@@ -1,6 +1,7 @@
 use crate::db::operations::photo::check_hash;
 use crate::db::operations::thumbnail::get_thumbnail as get_thumbs;
-use crate::{msg, unwrap_err, DB_POOL};
+use crate::db::pool::Db;
+use crate::{msg, unwrap_err};
 use rocket::fs::NamedFile;
 use rocket::get;
 use rocket::http::Status;
@@ -17,15 +18,14 @@ use serde_json::Value;
 /// - `404 Not Found`: No image with hash <hash> was found
 /// - `500 Internal Server Error`: Database or other server error occurred
 #[get("/thumbnail/<hash>")]
-pub async fn get_thumbnail(hash: &str) -> Result<NamedFile, (Status, Json<Value>)> {
-    let mut conn = unwrap_err!(DB_POOL.get(), Status::InternalServerError);
-    let photo = unwrap_err!(check_hash(&mut conn, hash), Status::InternalServerError);
+pub async fn get_thumbnail(db: Db, hash: &str) -> Result<NamedFile, (Status, Json<Value>)> {
+    let hash = hash.to_string();
+    let thumb = unwrap_err!(db.run(move |conn| {
+        match unwrap_err!(check_hash(conn, &hash), Status::InternalServerError) {
+            Some(photo) => Ok(unwrap_err!(get_thumbs(conn, photo.id), Status::InternalServerError)),
+            None => Err((Status::NotFound, msg!("No photo with hash {} found", hash))),
+        }
+    }).await, Status::InternalServerError)?;
 
-    match photo {
-        Some(photo) => {
-            let thumb = unwrap_err!(get_thumbs(&mut conn, photo.id), Status::InternalServerError);
-            Ok(unwrap_err!(NamedFile::open(thumb.thumbnail_path).await, Status::InternalServerError))
-        },
-        None => Err((Status::NotFound, msg!("No photo with hash {} found"))),
-    }
-}
\ No newline at end of file
+    Ok(unwrap_err!(NamedFile::open(thumb.thumbnail_path).await, Status::InternalServerError))
+}
@@ -0,0 +1,129 @@
+use crate::_utils::actor::Actor;
+use crate::_utils::json_map::JsonMap;
+use crate::audit::{record_operation, take_snapshot};
+use crate::db::operations::album::{get_album, get_album_by_photo, get_trashed_albums, set_album_trashed};
+use crate::db::operations::join_album_album::{get_parent_album_id, remove_album_from_album};
+use crate::db::operations::join_album_photo::remove_photo_from_album;
+use crate::db::operations::paths::get_album_path;
+use crate::db::operations::photo::{get_trashed_photos, set_photos_trashed};
+use crate::db::pool::Db;
+use crate::db::unit_of_work::unit_of_work;
+use crate::fs_operations::album::restore_album_fs;
+use crate::fs_operations::photo::restore_photo_fs;
+use crate::models::album::Album;
+use crate::models::audit::AuditAction;
+use crate::{msg, unwrap_err, unwrap_ret};
+use diesel::result::Error;
+use diesel::MysqlConnection;
+use rocket::http::Status;
+use rocket::serde::json::{Json, Value};
+use rocket::{get, post};
+use serde_json::json;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// Lists everything in the trash
+///
+/// # Endpoint
+/// `GET /trash`
+///
+/// # Returns
+/// - `200 OK`: JSON object with `photos` and `albums`, each most recently trashed first. Every entry
+///   carries a `deletedAt` timestamp; entries are purged `$TRASH_RETENTION_DAYS` after it.
+/// - `500 Internal Server Error`: Database or other server error occurred
+#[get("/trash")]
+pub async fn trash_contents(db: Db) -> Result<Json<Value>, (Status, Json<Value>)> {
+    unwrap_err!(db.run(move |conn| {
+        let photos = unwrap_err!(get_trashed_photos(conn), Status::InternalServerError);
+        let albums = unwrap_err!(get_trashed_albums(conn), Status::InternalServerError);
+
+        Ok(Json(json!({
+            "photos": photos,
+            "albums": albums,
+        })))
+    }).await, Status::InternalServerError)
+}
+
+/// Restores photos and/or albums from the trash to where they were deleted from
+///
+/// Albums go back into their parent album, or to the root if the parent is gone or trashed itself.
+/// Photos go back into their album, or to `unfiled` if the album is gone or trashed.
+/// Albums are restored before photos, so restoring an album together with its photos puts the photos
+/// back into it. Either everything is restored, or nothing is.
+///
+/// # Endpoint
+/// `POST /trash/restore`
+///
+/// # Request Body
+/// JSON object with:
+/// - `photo_ids` (optional): JSON array of trashed photo IDs to restore
+/// - `album_ids` (optional): JSON array of trashed album IDs to restore
+///
+/// # Returns
+/// - `200 OK`: Everything was restored
+/// - `400 Bad Request`: Invalid IDs in request body
+/// - `404 Not Found`: One of the IDs is not in the trash
+/// - `409 Conflict`: A file or directory already exists at a restore destination
+/// - `500 Internal Server Error`: Database or filesystem error occurred
+#[post("/trash/restore", format = "json", data = "<input>")]
+pub async fn restore_from_trash(db: Db, actor: Actor, input: Json<Value>) -> (Status, Json<Value>) {
+    let photo_ids = unwrap_ret!(input.get_optional::<Vec<i64>>("photo_ids"), Status::BadRequest).unwrap_or_default();
+    let album_ids = unwrap_ret!(input.get_optional::<Vec<i32>>("album_ids"), Status::BadRequest).unwrap_or_default();
+    unwrap_ret!(db.run(move |conn| {
+        let trashed_photos = unwrap_ret!(get_trashed_photos(conn), Status::InternalServerError);
+        let trashed_albums = unwrap_ret!(get_trashed_albums(conn), Status::InternalServerError);
+
+        let photos: Vec<_> = trashed_photos.into_iter().filter(|photo| photo_ids.contains(&photo.id)).collect();
+        let albums: Vec<_> = trashed_albums.into_iter().filter(|album| album_ids.contains(&album.id)).collect();
+        if photos.len() != photo_ids.len() || albums.len() != album_ids.len() {
+            return (Status::NotFound, msg!("Not all photos and albums are in the trash"));
+        }
+
+        let before = take_snapshot(conn, &photo_ids, &album_ids);
+        let result = unit_of_work(conn, |conn, journal| {
+            // Trashing an album detaches its subalbums, so an album can only point to a trashed parent if it
+            // was trashed first. Restoring the most recently trashed first thus restores parents before children.
+            for album in &albums {
+                let destination = restore_destination(conn, album)?;
+                restore_album_fs(journal, album, &destination)?;
+                set_album_trashed(conn, album.id, None)?;
+            }
+
+            for photo in &photos {
+                let destination = match get_album_by_photo(conn, photo.id) {
+                    Ok(album) => get_album_path(conn, album.id)?,
+                    Err(Error::NotFound) => {
+                        // Drop links to albums that are trashed themselves
+                        remove_photo_from_album(conn, &[photo.id])?;
+                        PathBuf::from("unfiled")
+                    }
+                    Err(err) => return Err(err.into()),
+                };
+                restore_photo_fs(journal, photo.id, &photo.file_name, &destination)?;
+                set_photos_trashed(conn, &[photo.id], None)?;
+            }
+            Ok(())
+        });
+        record_operation(conn, &actor, AuditAction::TrashRestore, &photo_ids, &album_ids, before, &result);
+
+        match result {
+            Ok(()) => (Status::Ok, msg!("Success")),
+            Err(err) if err.downcast_ref::<std::io::Error>().is_some_and(|e| e.kind() == ErrorKind::AlreadyExists) => {
+                (Status::Conflict, msg!("Failed to restore: {}", err))
+            }
+            Err(err) => (Status::InternalServerError, msg!("Failed to restore: {}", err)),
+        }
+    }).await, Status::InternalServerError)
+}
+
+/// Gets the path a trashed album is restored to, relative to $STORAGE_ROOT.
+/// If its parent is gone or trashed, the album is detached and restored to the root.
+fn restore_destination(conn: &mut MysqlConnection, album: &Album) -> Result<PathBuf, Error> {
+    if let Some(parent_id) = get_parent_album_id(conn, album.id)? {
+        if !get_album(conn, &[parent_id])?.is_empty() {
+            return Ok(get_album_path(conn, parent_id)?.join(&album.album_name));
+        }
+        remove_album_from_album(conn, &[album.id])?;
+    }
+    Ok(PathBuf::from(&album.album_name))
+}
@@ -1,67 +1,118 @@
 use crate::_utils::path_prefix::PathPrefix;
+use crate::db::operations::paths::trashed_album_dir;
+use crate::fs_operations::journal::FsJournal;
 use crate::fs_operations::photo::move_photo_fs;
-use crate::models::album::Album;
+use crate::models::album::{Album, AlbumName};
 use crate::models::photo::Photo;
-use std::fs;
 use std::io::Error;
 use std::path::{Path, PathBuf};
 
 
-/// Creates a directory for an album at the storage root
+/// Creates a directory for an album inside its parent album (or at the storage root)
 ///
 /// # Arguments
+/// * `journal` - Journal of the current unit of work
+/// * `parent_path` - Path to the parent album, relative to $STORAGE_ROOT (`/` for a root album)
 /// * `album_name` - The name of the new album
 ///
 /// # Returns
-/// Ok if the album was successfully created at `$STORAGE_ROOT/album_name`
-pub fn create_album_fs(album_name: &str) -> Result<(), Error> {
+/// Ok if the album was successfully created at `$STORAGE_ROOT/parent_path/album_name`, or an
+/// `AlreadyExists` error if something by that name is in the way
+pub fn create_album_fs(journal: &mut FsJournal, parent_path: &Path, album_name: &AlbumName) -> Result<(), Error> {
     let storage_root = PathBuf::from(std::env::var("STORAGE_ROOT").unwrap());
 
     // Create the album directory
-    fs::create_dir(storage_root.join(album_name))?;
+    journal.create_dir(&parent_path.join(album_name).prefix_within(&storage_root)?)?;
     Ok(())
 }
 
-/// Deletes an album, moving its children to the root (albums) / unfiled (photos).
+/// Moves an album to the trash, moving its children to the root (albums) / unfiled (photos) first.
+///
+/// Anything else left in the album directory (e.g. files that don't belong to a photo) is trashed with it.
 ///
 /// # Arguments
+/// * `journal` - Journal of the current unit of work
+/// * `album` - The album to trash
 /// * `album_path` - Path to the album directory, relative to $STORAGE_ROOT
 /// * `child_photos` - Photos linked to the album
 /// * `child_albums` - Subalbums linked to the album
 ///
 /// # Returns
-/// Ok if the album was deleted successfully and its children moved, or an error if deletion failed.
-pub fn delete_album_fs(album_path: &Path, child_photos: &[Photo], child_albums: &[Album]) -> Result<(), Error> {
+/// Ok if the album was trashed successfully and its children moved, or an error if something failed.
+pub fn trash_album_fs(journal: &mut FsJournal, album: &Album, album_path: &Path, child_photos: &[Photo], child_albums: &[Album]) -> Result<(), Error> {
     let storage_root = PathBuf::from(std::env::var("STORAGE_ROOT").unwrap());
-    let full_album_path = album_path.prefix(&storage_root);
+    let full_album_path = album_path.prefix_within(&storage_root)?;
 
     // Move child photos to the unfiled directory
     for photo in child_photos {
-        move_photo_fs(&album_path.join(&photo.file_name), &PathBuf::from("/unfiled"))?;
+        move_photo_fs(journal, &album_path.join(&photo.file_name), &PathBuf::from("/unfiled"))?;
     }
 
     // Move child albums to root
     for album in child_albums {
         let child_album_name = &album.album_name;
-        let current_child_path = full_album_path.join(child_album_name);
-        let new_child_path = storage_root.join(child_album_name);
+        let current_child_path = album_path.join(child_album_name).prefix_within(&storage_root)?;
+        let new_child_path = Path::new(child_album_name).prefix_within(&storage_root)?;
         
         if current_child_path.exists() {
-            fs::rename(current_child_path, new_child_path)?;
+            journal.rename(&current_child_path, &new_child_path)?;
         }
     }
 
-    // Delete the now-empty album directory
+    // Move the now-empty album directory to the trash
     if full_album_path.exists() {
-        fs::remove_dir_all(full_album_path)?;
+        let trash_dir = trashed_album_dir(album.id);
+        journal.create_dir_all(&trash_dir.prefix_within(&storage_root)?)?;
+        move_album_fs(journal, album_path, &trash_dir.join(&album.album_name))?;
     }
 
     Ok(())
 }
 
+/// Moves a trashed album directory back into the album tree
+///
+/// # Arguments
+/// * `journal` - Journal of the current unit of work
+/// * `album` - The trashed album
+/// * `destination_path` - Where to restore the album to, relative to $STORAGE_ROOT
+///
+/// # Returns
+/// Ok if the album was restored, or an error if the destination is taken or something failed.
+/// A trashed album whose directory is gone is restored as a new, empty directory.
+pub fn restore_album_fs(journal: &mut FsJournal, album: &Album, destination_path: &Path) -> Result<(), Error> {
+    let storage_root = PathBuf::from(std::env::var("STORAGE_ROOT").unwrap());
+    let trash_dir = trashed_album_dir(album.id);
+    let trashed_path = trash_dir.join(&album.album_name);
+
+    if trashed_path.prefix_within(&storage_root)?.is_dir() {
+        move_album_fs(journal, &trashed_path, destination_path)?;
+        journal.remove_dir_if_empty(&trash_dir.prefix_within(&storage_root)?);
+    } else {
+        journal.create_dir(&destination_path.prefix_within(&storage_root)?)?;
+    }
+
+    Ok(())
+}
+
+/// Permanently deletes a trashed album directory and anything left in it.
+/// The deletion is carried out when `journal` is committed.
+///
+/// # Arguments
+/// * `journal` - Journal of the current unit of work
+/// * `album_id` - ID of the trashed album
+///
+/// # Returns
+/// Ok if the deletion was scheduled
+pub fn purge_album_fs(journal: &mut FsJournal, album_id: i32) -> Result<(), Error> {
+    let storage_root = PathBuf::from(std::env::var("STORAGE_ROOT").unwrap());
+    journal.remove_dir_all(&trashed_album_dir(album_id).prefix_within(&storage_root)?);
+    Ok(())
+}
+
 /// Moves the entire album (and its children) to a new album.
 ///
 /// # Arguments
+/// * `journal` - Journal of the current unit of work
 /// * `album_path` - Path to the album to be moved, relative to $STORAGE_ROOT
 /// * `destination_path` - Path to the new album, relative to $STORAGE_ROOT. This must not be
 ///   a child of `album_path`.
@@ -77,6 +128,7 @@ pub fn delete_album_fs(album_path: &Path, child_photos: &[Photo], child_albums:
 ///
 /// // Move "2023/vacation" to "archived/2023/vacation"
 /// move_album_fs(
+///     &mut journal,
 ///     Path::new("2023/vacation"),
 ///     Path::new("archived/2023/vacation")
 /// )?;
@@ -89,6 +141,7 @@ pub fn delete_album_fs(album_path: &Path, child_photos: &[Photo], child_albums:
 ///
 /// // Move "2023/events/birthday" to "birthday"
 /// move_album_fs(
+///     &mut journal,
 ///     Path::new("2023/events/birthday"),
 ///     Path::new("birthday")
 /// )?;
@@ -101,14 +154,15 @@ pub fn delete_album_fs(album_path: &Path, child_photos: &[Photo], child_albums:
 ///
 /// // This panics - cannot move "photos" into "photos/archive"
 /// move_album_fs(
+///     &mut journal,
 ///     Path::new("photos"),
 ///     Path::new("photos/archive")
 /// ).unwrap();
 /// ```
-pub fn move_album_fs(album_path: &Path, destination_path: &Path) -> Result<(), Error> {
+pub fn move_album_fs(journal: &mut FsJournal, album_path: &Path, destination_path: &Path) -> Result<(), Error> {
     let storage_root = PathBuf::from(std::env::var("STORAGE_ROOT").unwrap());
-    let src_path = album_path.prefix(&storage_root);
-    let dest_path = destination_path.prefix(&storage_root);
+    let src_path = album_path.prefix_within(&storage_root)?;
+    let dest_path = destination_path.prefix_within(&storage_root)?;
 
     // Make sure the source exists and is a directory
     if !src_path.is_dir() {
@@ -131,7 +185,7 @@ pub fn move_album_fs(album_path: &Path, destination_path: &Path) -> Result<(), E
     }
 
     println!("Moving album {} to {}", src_path.display(), dest_path.display());
-    fs::rename(src_path, dest_path)?;
+    journal.rename(&src_path, &dest_path)?;
 
     Ok(())
 }
@@ -0,0 +1,195 @@
+use serde::Deserialize;
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// How `FsJournal::copy_file` duplicates a file
+///
+/// Deserialized in lowercase (`copy`, `hardlink` or `reflink`).
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum CopyMode {
+    /// A full, independent copy of the data
+    #[default]
+    Copy,
+    /// A second directory entry for the same data; edits to one show up in the other
+    Hardlink,
+    /// A copy-on-write clone sharing the data until either is changed (Btrfs, XFS, APFS, ...)
+    Reflink,
+}
+
+/// A filesystem change that has already been applied, and how to undo it
+#[derive(Debug)]
+enum FsAction {
+    Renamed { from: PathBuf, to: PathBuf },
+    CreatedDir(PathBuf),
+    CreatedFile(PathBuf),
+}
+
+/// A deletion that is held back until the journal is committed
+#[derive(Debug)]
+enum PendingDelete {
+    File(PathBuf),
+    DirAll(PathBuf),
+    DirIfEmpty(PathBuf),
+}
+
+/// Records the filesystem side of a unit of work, so it can be undone if the unit of work fails.
+///
+/// Renames, copies and creations are applied immediately and undone in reverse order by `rollback`.
+/// Deletions are only carried out by `commit`, so a rolled back unit of work never has to recreate
+/// deleted data. All paths are full paths; the `fs_operations` helpers resolve them against
+/// $STORAGE_ROOT / $THUMBNAIL_ROOT before calling into the journal.
+///
+/// A journal that is dropped without being committed is rolled back.
+#[derive(Debug, Default)]
+pub struct FsJournal {
+    applied: Vec<FsAction>,
+    pending_deletes: Vec<PendingDelete>,
+    finished: bool,
+}
+
+impl FsJournal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Renames `from` to `to`, recording it so it can be moved back
+    pub fn rename(&mut self, from: &Path, to: &Path) -> Result<(), Error> {
+        fs::rename(from, to)?;
+        self.applied.push(FsAction::Renamed { from: from.to_path_buf(), to: to.to_path_buf() });
+        Ok(())
+    }
+
+    /// Creates a directory and any missing parents, recording each created directory
+    pub fn create_dir_all(&mut self, path: &Path) -> Result<(), Error> {
+        // Collect the missing ancestors from the outermost inwards
+        let mut missing: Vec<&Path> = path.ancestors().take_while(|dir| !dir.exists()).collect();
+        missing.reverse();
+
+        for dir in missing {
+            fs::create_dir(dir)?;
+            self.applied.push(FsAction::CreatedDir(dir.to_path_buf()));
+        }
+        Ok(())
+    }
+
+    /// Creates a single directory, failing if it already exists
+    pub fn create_dir(&mut self, path: &Path) -> Result<(), Error> {
+        fs::create_dir(path)?;
+        self.applied.push(FsAction::CreatedDir(path.to_path_buf()));
+        Ok(())
+    }
+
+    /// Copies `from` to `to`, recording it so the copy can be removed again. Fails if `to` already exists.
+    pub fn copy_file(&mut self, from: &Path, to: &Path, mode: CopyMode) -> Result<(), Error> {
+        if to.exists() {
+            return Err(Error::new(std::io::ErrorKind::AlreadyExists, format!("{} already exists", to.display())));
+        }
+
+        match mode {
+            CopyMode::Copy => fs::copy(from, to).map(|_| ())?,
+            CopyMode::Hardlink => fs::hard_link(from, to)?,
+            CopyMode::Reflink => {
+                let output = Command::new("cp").arg("--reflink=always").arg(from).arg(to).output()?;
+                if !output.status.success() {
+                    return Err(Error::other(format!(
+                        "Failed to reflink {} to {}: {}",
+                        from.display(),
+                        to.display(),
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    )));
+                }
+            }
+        }
+        self.applied.push(FsAction::CreatedFile(to.to_path_buf()));
+        Ok(())
+    }
+
+    /// Schedules a file for deletion on commit. Fails immediately if it isn't a file.
+    pub fn remove_file(&mut self, path: &Path) -> Result<(), Error> {
+        if !path.is_file() {
+            return Err(Error::new(std::io::ErrorKind::NotFound, format!("{} is not a file", path.display())));
+        }
+
+        self.pending_deletes.push(PendingDelete::File(path.to_path_buf()));
+        Ok(())
+    }
+
+    /// Schedules a directory and everything still in it for deletion on commit
+    pub fn remove_dir_all(&mut self, path: &Path) {
+        self.pending_deletes.push(PendingDelete::DirAll(path.to_path_buf()));
+    }
+
+    /// Schedules a directory for deletion on commit, if it is empty by then
+    pub fn remove_dir_if_empty(&mut self, path: &Path) {
+        self.pending_deletes.push(PendingDelete::DirIfEmpty(path.to_path_buf()));
+    }
+
+    /// Makes the recorded changes permanent by carrying out the scheduled deletions, in the order
+    /// they were scheduled.
+    ///
+    /// The database side has already been committed at this point, so failed deletions cannot be
+    /// undone; they are returned so the caller can report them (`fsck` picks up the leftovers).
+    pub fn commit(mut self) -> Vec<Error> {
+        self.finished = true;
+
+        let mut errors = Vec::new();
+        for pending in self.pending_deletes.drain(..) {
+            let result = match &pending {
+                PendingDelete::File(path) => fs::remove_file(path),
+                PendingDelete::DirAll(path) if path.exists() => fs::remove_dir_all(path),
+                PendingDelete::DirIfEmpty(path) => {
+                    // `remove_dir` only succeeds on empty directories, which is exactly what is wanted
+                    let _ = fs::remove_dir(path);
+                    Ok(())
+                }
+                PendingDelete::DirAll(_) => Ok(()),
+            };
+            if let Err(e) = result {
+                errors.push(e);
+            }
+        }
+        errors
+    }
+
+    /// Undoes all applied changes in reverse order and drops the scheduled deletions
+    ///
+    /// # Returns
+    /// Ok if everything was restored, or the first error encountered (restoring continues past errors)
+    pub fn rollback(mut self) -> Result<(), Error> {
+        self.undo()
+    }
+
+    fn undo(&mut self) -> Result<(), Error> {
+        self.finished = true;
+        self.pending_deletes.clear();
+
+        let mut first_error = None;
+        while let Some(action) = self.applied.pop() {
+            let result = match &action {
+                FsAction::Renamed { from, to } => fs::rename(to, from),
+                FsAction::CreatedDir(path) => fs::remove_dir(path),
+                FsAction::CreatedFile(path) => fs::remove_file(path),
+            };
+            if let Err(e) = result {
+                println!("Error rolling back {action:?}: {e}");
+                first_error.get_or_insert(e);
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for FsJournal {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.undo();
+        }
+    }
+}
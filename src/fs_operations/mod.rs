@@ -1,2 +1,4 @@
 pub mod album;
+pub mod journal;
 pub mod photo;
+pub mod xmp;
\ No newline at end of file
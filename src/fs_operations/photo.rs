@@ -1,54 +1,52 @@
 use crate::_utils::path_prefix::PathPrefix;
+use crate::db::operations::paths::trashed_photo_dir;
+use crate::fs_operations::journal::{CopyMode, FsJournal};
+use chrono::NaiveDateTime;
 use std::fs;
 use std::io::Error;
 use std::path::{Path, PathBuf};
+use std::process::Command;
 
-/// Deletes a photo, its thumbnail, andassociated files from the filesystem and clearing any empty
+/// Deletes a photo, its thumbnail, and associated files from the filesystem and clears any empty
 /// thumbnail directories.
 ///
+/// The deletions are scheduled in `journal` and only carried out when it is committed.
+///
 /// # Arguments
+/// * `journal` - Journal of the current unit of work
 /// * `photo_path` - Path to the photo, relative to $STORAGE_ROOT
-/// * `thumb_path` - Path to the thumbnail, relative to $THUMBNAIL_ROOT
+/// * `thumb_path` - Path to the thumbnail as stored in the database, or `None` if the photo has none
 ///
 /// # Returns
-/// Ok if all files were deleted successfully, or an error if deletion failed.
+/// Ok if all deletions were scheduled, or an error if the photo could not be found.
 /// Also removes empty parent directories from the thumbnail path.
-pub fn delete_photo_fs(photo_path: &Path, thumb_path: &Path) -> Result<(), Error> {
+pub fn delete_photo_fs(journal: &mut FsJournal, photo_path: &Path, thumb_path: Option<&Path>) -> Result<(), Error> {
     let storage_root = PathBuf::from(std::env::var("STORAGE_ROOT").unwrap());
     let thumbnail_root = PathBuf::from(std::env::var("THUMBNAIL_ROOT").unwrap());
 
-    let full_photo_path = photo_path.prefix(&storage_root);
-    let mut full_thumb_path = thumb_path.prefix(&storage_root);
-
-    // Delete photo & thumbnail from hard drive
-    fs::remove_file(&full_photo_path)?;
-    fs::remove_file(&full_thumb_path)?;
-    full_thumb_path.pop();
+    let full_photo_path = photo_path.prefix_within(&storage_root)?;
 
-    // Also delete other associated files (e.g. exports, editor metadata, etc.)
-    // First extract the base name (without extension) from the photo filename
-    let base_name = full_photo_path
-        .file_prefix()
-        .unwrap_or(full_photo_path.file_name().unwrap());
+    // Delete photo & associated files (e.g. exports, editor metadata, etc.) from hard drive
+    for path in associated_files(&full_photo_path)? {
+        journal.remove_file(&path)?;
+    }
 
-    // Find all files in the parent directory that match the pattern <base_name>* and deletes them
-    fs::read_dir(full_photo_path.parent().unwrap())?
-        .flatten()
-        // Filter out non-files
-        .filter(|entry| entry.file_type().map(|ft| ft.is_file()).unwrap_or(false))
-        // Filter out files that don't match the same file prefix (e.g. "IMG_20210101_123456" vs "IMG_20210101_123456.jpg"
-        .filter(|entry| entry.path().file_prefix().map(|prefix| prefix == base_name).unwrap_or(false))
-        // Move files to the new directory
-        .try_for_each(|entry| {
-            fs::remove_file(entry.path())
-        })?;
-
-    // Delete empty thumbnail directories
-    while full_thumb_path != thumbnail_root {
-        if fs::remove_dir(&full_thumb_path).is_err() {
-            break;
+    // Delete thumbnail, and any thumbnail directories left empty by it.
+    // Thumbnail paths are stored in full, but also accept ones relative to $THUMBNAIL_ROOT
+    if let Some(thumb_path) = thumb_path {
+        let mut full_thumb_path = match thumb_path.strip_prefix(&thumbnail_root) {
+            Ok(relative_path) if thumb_path.is_absolute() => relative_path.prefix_within(&thumbnail_root)?,
+            _ => thumb_path.prefix_within(&thumbnail_root)?,
+        };
+        if full_thumb_path.is_file() {
+            journal.remove_file(&full_thumb_path)?;
         }
+
         full_thumb_path.pop();
+        while full_thumb_path.starts_with(&thumbnail_root) && full_thumb_path != thumbnail_root {
+            journal.remove_dir_if_empty(&full_thumb_path);
+            full_thumb_path.pop();
+        }
     }
 
     Ok(())
@@ -59,35 +57,286 @@ pub fn delete_photo_fs(photo_path: &Path, thumb_path: &Path) -> Result<(), Error
 /// Move a photo and its associated files to a new album
 ///
 /// # Arguments
+/// * `journal` - Journal of the current unit of work
+/// * `photo_path` - Path to the photo, relative to $STORAGE_ROOT
+/// * `dest_path` - Path to the destination album, relative to $STORAGE_ROOT
+///
+/// # Returns
+/// Ok if all files were moved successfully, or an error if something failed (`NotFound` if the photo
+/// itself is missing).
+pub fn move_photo_fs(journal: &mut FsJournal, photo_path: &Path, dest_path: &Path) -> Result<(), Error> {
+    let storage_root = PathBuf::from(std::env::var("STORAGE_ROOT").unwrap());
+    let full_photo_path = photo_path.prefix_within(&storage_root)?;
+    let full_dest_path = dest_path.prefix_within(&storage_root)?;
+
+    let files = associated_files(&full_photo_path)?;
+    if !files.contains(&full_photo_path) {
+        return Err(Error::new(std::io::ErrorKind::NotFound, format!("{} does not exist", full_photo_path.display())));
+    }
+
+    // Move the photo and all files that share its base name to the new directory
+    for path in files {
+        let dest = full_dest_path.join(path.file_name().unwrap_or_default());
+        if dest.exists() {
+            return Err(Error::new(std::io::ErrorKind::AlreadyExists, format!("{} already exists", dest.display())));
+        }
+        journal.rename(&path, &dest)?;
+    }
+
+    Ok(())
+}
+
+
+/// Move a photo and its associated files to a new album, giving them a new base name
+/// (e.g. `IMG_0001_2.CR3` and `IMG_0001_2.xmp` for `IMG_0001.CR3` and `IMG_0001.xmp` with base name `IMG_0001_2`)
+///
+/// # Arguments
+/// * `journal` - Journal of the current unit of work
+/// * `photo_path` - Path to the photo, relative to $STORAGE_ROOT
+/// * `dest_path` - Path to the destination album, relative to $STORAGE_ROOT
+/// * `base_name` - New base name of the files, without extension
+///
+/// # Returns
+/// Ok if all files were moved successfully, or an error if something failed (`NotFound` if the photo
+/// itself is missing).
+pub fn move_photo_as_fs(journal: &mut FsJournal, photo_path: &Path, dest_path: &Path, base_name: &str) -> Result<(), Error> {
+    let storage_root = PathBuf::from(std::env::var("STORAGE_ROOT").unwrap());
+    let full_photo_path = photo_path.prefix_within(&storage_root)?;
+
+    let files = associated_files(&full_photo_path)?;
+    if !files.contains(&full_photo_path) {
+        return Err(Error::new(std::io::ErrorKind::NotFound, format!("{} does not exist", full_photo_path.display())));
+    }
+
+    for path in files {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let prefix_len = path.file_prefix().map_or(0, |prefix| prefix.len());
+        let dest = dest_path.join(format!("{base_name}{}", &file_name[prefix_len..])).prefix_within(&storage_root)?;
+        if dest.exists() {
+            return Err(Error::new(std::io::ErrorKind::AlreadyExists, format!("{} already exists", dest.display())));
+        }
+        journal.rename(&path, &dest)?;
+    }
+
+    Ok(())
+}
+
+/// Copies a photo and its associated files into another album, keeping their names
+///
+/// # Arguments
+/// * `journal` - Journal of the current unit of work
 /// * `photo_path` - Path to the photo, relative to $STORAGE_ROOT
 /// * `dest_path` - Path to the destination album, relative to $STORAGE_ROOT
+/// * `mode` - Whether to copy, hardlink or reflink the files
+///
+/// # Returns
+/// Ok if all files were copied successfully, or an error if something failed (`AlreadyExists` if a file of
+/// the same name is in the way).
+pub fn copy_photo_fs(journal: &mut FsJournal, photo_path: &Path, dest_path: &Path, mode: CopyMode) -> Result<(), Error> {
+    let storage_root = PathBuf::from(std::env::var("STORAGE_ROOT").unwrap());
+    let full_photo_path = photo_path.prefix_within(&storage_root)?;
+    let full_dest_path = dest_path.prefix_within(&storage_root)?;
+
+    for path in associated_files(&full_photo_path)? {
+        journal.copy_file(&path, &full_dest_path.join(path.file_name().unwrap_or_default()), mode)?;
+    }
+
+    Ok(())
+}
+
+/// Moves a photo and its associated files to the trash (`trashed_photo_dir`). The thumbnail stays
+/// in place so the trash can still be browsed.
+///
+/// If other photos in the same directory share the photo's base name (e.g. the RAW of a RAW+JPEG
+/// pair), only the photo's own file is moved: their files and the shared associated files stay with them.
+///
+/// # Arguments
+/// * `journal` - Journal of the current unit of work
+/// * `photo_path` - Path to the photo, relative to $STORAGE_ROOT
+/// * `photo_id` - ID of the photo
+/// * `shared` - Whether other photos outside the trash share the photo's directory and base name
 ///
 /// # Returns
 /// Ok if all files were moved successfully, or an error if something failed.
-pub fn move_photo_fs(photo_path: &Path, dest_path: &Path) -> Result<(), Error> {
+pub fn trash_photo_fs(journal: &mut FsJournal, photo_path: &Path, photo_id: i64, shared: bool) -> Result<(), Error> {
     let storage_root = PathBuf::from(std::env::var("STORAGE_ROOT").unwrap());
-    let full_photo_path = photo_path.prefix(&storage_root);
-    let full_dest_path = dest_path.prefix(&storage_root);
+    let full_trash_dir = trashed_photo_dir(photo_id).prefix_within(&storage_root)?;
+
+    journal.create_dir_all(&full_trash_dir)?;
+    move_to_trash_fs(journal, &photo_path.prefix_within(&storage_root)?, &full_trash_dir, shared)
+}
 
+/// Moves the files of a photo into its trash directory, see `trash_photo_fs`
+///
+/// # Arguments
+/// * `journal` - Journal of the current unit of work
+/// * `full_photo_path` - Full path to the photo
+/// * `full_trash_dir` - Full path to the photo's trash directory
+/// * `shared` - Whether to leave the associated files in place for the other photos sharing them
+///
+/// # Returns
+/// Ok if all files were moved successfully, or an error if something failed.
+fn move_to_trash_fs(journal: &mut FsJournal, full_photo_path: &Path, full_trash_dir: &Path, shared: bool) -> Result<(), Error> {
+    let files = match shared {
+        true => vec![full_photo_path.to_path_buf()],
+        false => associated_files(full_photo_path)?,
+    };
+
+    for path in files {
+        journal.rename(&path, &full_trash_dir.join(path.file_name().unwrap_or_default()))?;
+    }
+
+    Ok(())
+}
+
+/// Moves a trashed photo and its associated files back into an album (or `unfiled`)
+///
+/// # Arguments
+/// * `journal` - Journal of the current unit of work
+/// * `photo_id` - ID of the photo
+/// * `file_name` - File name of the photo
+/// * `dest_path` - Path to the destination album, relative to $STORAGE_ROOT
+///
+/// # Returns
+/// Ok if all files were moved successfully, or an error if something failed.
+pub fn restore_photo_fs(journal: &mut FsJournal, photo_id: i64, file_name: &str, dest_path: &Path) -> Result<(), Error> {
+    let storage_root = PathBuf::from(std::env::var("STORAGE_ROOT").unwrap());
+    let trash_dir = trashed_photo_dir(photo_id);
+
+    move_photo_fs(journal, &trash_dir.join(file_name), dest_path)?;
+    journal.remove_dir_if_empty(&trash_dir.prefix_within(&storage_root)?);
+    Ok(())
+}
+
+/// Permanently deletes a trashed photo, its associated files and its thumbnail.
+/// The deletions are carried out when `journal` is committed.
+///
+/// # Arguments
+/// * `journal` - Journal of the current unit of work
+/// * `photo_id` - ID of the photo
+/// * `file_name` - File name of the photo
+/// * `thumb_path` - Path to the thumbnail as stored in the database, or `None` if the photo has none
+///
+/// # Returns
+/// Ok if all deletions were scheduled, or an error if the trashed files could not be read.
+pub fn purge_photo_fs(journal: &mut FsJournal, photo_id: i64, file_name: &str, thumb_path: Option<&Path>) -> Result<(), Error> {
+    let storage_root = PathBuf::from(std::env::var("STORAGE_ROOT").unwrap());
+    let trash_dir = trashed_photo_dir(photo_id);
+
+    delete_photo_fs(journal, &trash_dir.join(file_name), thumb_path)?;
+    journal.remove_dir_if_empty(&trash_dir.prefix_within(&storage_root)?);
+    Ok(())
+}
+
+
+/// Finds a photo and all files in its directory that share its base name
+/// (e.g. `IMG_0001.xmp` and `IMG_0001.jpg` for `IMG_0001.CR3`)
+///
+/// # Arguments
+/// * `full_photo_path` - Full path to the photo
+///
+/// # Returns
+/// Full paths of the photo and its associated files, or an error if the directory cannot be read
+pub fn associated_files(full_photo_path: &Path) -> Result<Vec<PathBuf>, Error> {
     // Extract the base name (without extension) from the photo filename
     let base_name = full_photo_path
         .file_prefix()
-        .unwrap_or(photo_path.file_name().unwrap());
+        .or(full_photo_path.file_name())
+        .ok_or_else(|| Error::new(std::io::ErrorKind::InvalidInput, format!("{} has no file name", full_photo_path.display())))?;
 
-    // Find all files in the album directory that match the pattern <base_name>* and move them to the unfiled directory
-    fs::read_dir(full_photo_path.parent().unwrap())?
+    let parent = full_photo_path
+        .parent()
+        .ok_or_else(|| Error::new(std::io::ErrorKind::InvalidInput, format!("{} has no parent directory", full_photo_path.display())))?;
+
+    let files = fs::read_dir(parent)?
         .flatten()
         // Filter out non-files
         .filter(|entry| entry.file_type().map(|ft| ft.is_file()).unwrap_or(false))
         // Filter out files that don't match the same file prefix (e.g. "IMG_20210101_123456" vs "IMG_20210101_123456.jpg"
         .filter(|entry| entry.path().file_prefix().map(|prefix| prefix == base_name).unwrap_or(false))
-        // Move files to the new directory
-        .try_for_each(|entry| {
-            fs::rename(
-                entry.path(),  // Already the full path
-                full_dest_path.join(entry.file_name())
-            )
-        })?;
+        .map(|entry| entry.path())
+        .collect();
+
+    Ok(files)
+}
+
+
+/// Writes a corrected capture time into the metadata of the original photo file itself.
+///
+/// This rewrites the original, so its hash changes and must be updated in the database afterwards.
+///
+/// # Arguments
+/// * `photo_path` - Path to the photo, relative to $STORAGE_ROOT
+/// * `date` - New capture time, in the photo's local time
+/// * `timezone` - New UTC offset, e.g. `+09:00`
+///
+/// # Returns
+/// Ok if the file was rewritten, or an error if `exiftool` failed.
+pub fn write_photo_date_fs(photo_path: &Path, date: NaiveDateTime, timezone: &str) -> Result<(), Error> {
+    let storage_root = PathBuf::from(std::env::var("STORAGE_ROOT").unwrap());
+    let full_photo_path = photo_path.prefix_within(&storage_root)?;
+
+    let output = Command::new("exiftool")
+        .arg("-overwrite_original")
+        .arg(format!("-DateTimeOriginal={}", date.format("%Y:%m:%d %H:%M:%S")))
+        .arg(format!("-OffsetTimeOriginal={timezone}"))
+        .arg(&full_photo_path)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(Error::other(format!(
+            "Failed to write capture time to {}: {}",
+            full_photo_path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
 
     Ok(())
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates an empty scratch directory holding the given (empty) files
+    fn scratch_dir(name: &str, files: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("suisai-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("trash")).unwrap();
+        for file in files {
+            fs::write(dir.join(file), b"").unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn trashing_a_shared_base_name_keeps_the_other_files() {
+        let dir = scratch_dir("trash-shared", &["IMG_0001.CR3", "IMG_0001.JPG", "IMG_0001.xmp"]);
+
+        let mut journal = FsJournal::new();
+        move_to_trash_fs(&mut journal, &dir.join("IMG_0001.JPG"), &dir.join("trash"), true).unwrap();
+        assert!(journal.commit().is_empty());
+
+        assert!(dir.join("IMG_0001.CR3").is_file());
+        assert!(dir.join("IMG_0001.xmp").is_file());
+        assert!(!dir.join("IMG_0001.JPG").exists());
+        assert!(dir.join("trash/IMG_0001.JPG").is_file());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn trashing_an_unshared_base_name_takes_the_associated_files() {
+        let dir = scratch_dir("trash-unshared", &["IMG_0002.CR3", "IMG_0002.xmp"]);
+
+        let mut journal = FsJournal::new();
+        move_to_trash_fs(&mut journal, &dir.join("IMG_0002.CR3"), &dir.join("trash"), false).unwrap();
+        assert!(journal.commit().is_empty());
+
+        assert!(dir.join("trash/IMG_0002.CR3").is_file());
+        assert!(dir.join("trash/IMG_0002.xmp").is_file());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,122 @@
+use crate::_utils::path_prefix::PathPrefix;
+use chrono::NaiveDateTime;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The DB-side edits of a photo that are mirrored into its XMP sidecar
+///
+/// # Fields
+/// * `rating`: Star rating, from 0 to 5
+/// * `pick_flag`: -1 if rejected (written as an XMP rating of -1), 0 if unflagged, 1 if picked
+/// * `color_label`: Color label, or `None` to clear it
+/// * `keywords`: Full hierarchical keywords, e.g. `Places|Japan|Kyoto`
+/// * `photo_date`: Capture time, in the photo's local time
+/// * `photo_timezone`: UTC offset of `photo_date`, e.g. `+09:00`
+#[derive(Debug)]
+pub struct XmpSidecar {
+    pub rating: i8,
+    pub pick_flag: i8,
+    pub color_label: Option<String>,
+    pub keywords: Vec<String>,
+    pub photo_date: NaiveDateTime,
+    pub photo_timezone: String,
+}
+
+/// Gets the path of a photo's XMP sidecar (`<basename>.xmp` in the same directory)
+///
+/// # Arguments
+/// * `photo_path` - Path to the photo
+///
+/// # Returns
+/// Path to the sidecar, relative to the same base as `photo_path`
+pub fn xmp_sidecar_path(photo_path: &Path) -> PathBuf {
+    let base_name = photo_path
+        .file_prefix()
+        .unwrap_or(photo_path.file_name().unwrap_or_default());
+
+    let mut sidecar_name = base_name.to_os_string();
+    sidecar_name.push(".xmp");
+    photo_path.with_file_name(sidecar_name)
+}
+
+/// Writes DB-side edits into a photo's `<basename>.xmp` sidecar.
+///
+/// An existing sidecar is updated in place, so tags written by other applications (e.g. darktable
+/// history or Lightroom develop settings) are preserved. Only the tags in `XmpSidecar` are replaced.
+/// If there is no sidecar yet, one is created from the photo's own metadata.
+///
+/// Since the sidecar shares the photo's base name, `move_photo_fs` and `delete_photo_fs` carry it along.
+///
+/// # Arguments
+/// * `photo_path` - Path to the photo, relative to $STORAGE_ROOT
+/// * `sidecar` - Values to write
+///
+/// # Returns
+/// Ok if the sidecar was written, or an error if `exiftool` failed.
+pub fn write_xmp_sidecar_fs(photo_path: &Path, sidecar: &XmpSidecar) -> Result<(), Error> {
+    let storage_root = PathBuf::from(std::env::var("STORAGE_ROOT").unwrap());
+    let full_photo_path = photo_path.prefix_within(&storage_root)?;
+    let full_sidecar_path = xmp_sidecar_path(&full_photo_path);
+
+    let mut args: Vec<String> = vec!["-m".into()];
+
+    // Rejected photos are stored as a rating of -1 by Lightroom, darktable and RawTherapee
+    let rating = if sidecar.pick_flag < 0 { -1 } else { sidecar.rating };
+    args.push(format!("-XMP-xmp:Rating={rating}"));
+
+    // Labels are conventionally capitalized (e.g. "Red")
+    let label = sidecar.color_label.as_deref().map(capitalize).unwrap_or_default();
+    args.push(format!("-XMP-xmp:Label={label}"));
+
+    // Repeated `=` assignments replace the whole list, and an empty one clears it,
+    // so keywords removed in the DB disappear from the sidecar
+    let mut leaves: Vec<&str> = sidecar.keywords.iter().filter_map(|keyword| keyword.rsplit('|').next()).collect();
+    leaves.sort_unstable();
+    leaves.dedup();
+    if sidecar.keywords.is_empty() {
+        args.push("-XMP-lr:HierarchicalSubject=".into());
+        args.push("-XMP-dc:Subject=".into());
+    }
+    for keyword in &sidecar.keywords {
+        args.push(format!("-XMP-lr:HierarchicalSubject={keyword}"));
+    }
+    for leaf in leaves {
+        args.push(format!("-XMP-dc:Subject={leaf}"));
+    }
+
+    let date = format!("{}{}", sidecar.photo_date.format("%Y:%m:%d %H:%M:%S"), sidecar.photo_timezone);
+    args.push(format!("-XMP-exif:DateTimeOriginal={date}"));
+    args.push(format!("-XMP-photoshop:DateCreated={date}"));
+
+    if full_sidecar_path.exists() {
+        // Merge into the existing sidecar
+        args.push("-overwrite_original".into());
+        args.push(full_sidecar_path.to_string_lossy().to_string());
+    } else {
+        // Create a new sidecar, seeded with the metadata of the photo itself
+        args.push("-o".into());
+        args.push(full_sidecar_path.to_string_lossy().to_string());
+        args.push(full_photo_path.to_string_lossy().to_string());
+    }
+
+    // Invoked without a shell, since keywords and labels are user-supplied
+    let output = Command::new("exiftool").args(&args).output()?;
+    if !output.status.success() {
+        return Err(Error::other(format!(
+            "Failed to write XMP sidecar {}: {}",
+            full_sidecar_path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(())
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -1,11 +1,13 @@
-use crate::db::operations::photo::{check_hash, create_photo};
-use crate::db::operations::thumbnail::create_thumbnail;
-use crate::ingest::extract_thumbnail::extract_thumbnail_full;
+use crate::_utils::actor::Actor;
+use crate::audit::record_operation;
+use crate::db::operations::photo::check_hash;
+use crate::db::pool::establish_connection;
 use crate::ingest::get_image_paths::get_image_paths;
+use crate::ingest::register_photo::register_photo;
 use crate::ingest::trait_suisai_image_path::SuisaiImagePath;
-use crate::models::thumbnail::Thumbnail;
-use crate::DB_POOL;
-use chrono::Datelike;
+use crate::maintenance::stacks::{create_stacks, find_stacks_among, DEFAULT_STACK_GAP_SECONDS};
+use crate::models::audit::AuditAction;
+use chrono::TimeDelta;
 use rocket::serde::json::serde_json;
 use std::env;
 use std::fs::{copy, create_dir_all, rename};
@@ -30,9 +32,11 @@ pub fn ingest(path: String, dry: bool, no_preserve: bool) {
     }
 
     // Initialize DB connection and set up storage paths
-    let mut conn = DB_POOL.get().expect("Failed to get connection from pool");
+    let mut conn = establish_connection();
     let raw_storage_dir = format!("{}/", env::var("STORAGE_ROOT").unwrap());
     let raw_storage_path = Path::new(&raw_storage_dir);
+    let actor = Actor::cli();
+    let mut ingested: Vec<i64> = Vec::new();
 
     // Iterate over all found paths
     for path in paths {
@@ -66,41 +70,32 @@ pub fn ingest(path: String, dry: bool, no_preserve: bool) {
             }
         }
 
-        // Generate and store a JPEG thumbnail at `THUMBNAIL_ROOT/yyyymm/FILENAME.jpeg`
-        let date = new_path.get_photo_date();
-        let thumbnail_dir = format!("{}/{}{:02}/", env::var("THUMBNAIL_ROOT").unwrap(), date.year(), date.month());
-        let thumbnail_filename = format!("{}.jpeg", new_path.file_stem().unwrap().to_string_lossy());
-        let mut thumbnail_path = format!("{thumbnail_dir}{thumbnail_filename}");
-        // Create Thumbnail
-        match extract_thumbnail_full(new_path.to_str().unwrap(), &thumbnail_dir, &thumbnail_filename) {
-            Ok(()) => println!("Thumbnail created at {thumbnail_path}"),
+        // Create thumbnail and database records
+        let result = register_photo(&mut conn, &new_path);
+        let photo_ids: Vec<i64> = result.iter().copied().collect();
+        record_operation(&mut conn, &actor, AuditAction::Ingest, &photo_ids, &[], None, &result);
+        match result {
+            Ok(photo_id) => ingested.push(photo_id),
             Err(e) => {
-                thumbnail_path = String::new();
-                println!("Error creating thumbnail for {filename}: {e}");
+                // Stop here, but still stack the photos registered so far
+                println!("Error: {e}");
+                break;
             }
         }
 
-        // Create a database record for the image
-        let photo = new_path.to_db_entry();
-        println!("{}", serde_json::to_string_pretty(&photo).unwrap());
+        println!("Done");
 
-        println!("Adding {} to database", photo.file_name);
-        let photo_id = match create_photo(&mut conn, photo) {
-            Err(e) => {
-                println!("Error: {e}");
-                return;
-            },
-            Ok(id) => id
-        };
+    }
 
-        // Create a database record for the thumbnail, if any
-        if !thumbnail_path.is_empty() {
-            let thumbnail = Thumbnail { id: photo_id, thumbnail_path };
-            create_thumbnail(&mut conn, &thumbnail).unwrap_or_else(|e| println!("Error: {e}"));
+    // Group the new photos into bursts and brackets. The files are in place already, so a failure is only reported.
+    match find_stacks_among(&mut conn, &ingested, TimeDelta::seconds(DEFAULT_STACK_GAP_SECONDS)) {
+        Ok(stacks) => {
+            let failed = create_stacks(&mut conn, &actor, stacks, false);
+            if failed > 0 {
+                println!("Failed to stack {failed} bursts and brackets");
+            }
         }
-
-        println!("Done");
-
+        Err(e) => println!("Error finding bursts and brackets: {e}"),
     }
 
     println!("Finished");
@@ -1,4 +1,6 @@
 pub mod main;
 mod get_image_paths;
-mod trait_suisai_image_path;
-mod extract_thumbnail;
\ No newline at end of file
+pub mod register_photo;
+pub mod trait_suisai_image_path;
+pub mod extract_thumbnail;
+pub mod phash;
@@ -0,0 +1,104 @@
+use anyhow::anyhow;
+use std::path::Path;
+use std::process::Command;
+
+/// Width of the grid the image is reduced to. Each row yields `HASH_WIDTH - 1` bits.
+const HASH_WIDTH: usize = 9;
+
+/// Height of the grid the image is reduced to
+const HASH_HEIGHT: usize = 8;
+
+/// Computes a 64-bit difference hash (dHash) of a JPEG, e.g. a generated thumbnail.
+///
+/// The image is reduced to a 9x8 grayscale grid, and each bit records whether a cell is darker than
+/// its right neighbour. Unlike the xxh3 content hash, it survives resizing, re-encoding and format
+/// conversion, so copies of the same frame end up only a few bits apart.
+///
+/// # Arguments
+/// * `jpeg_path` - Full path to the JPEG
+///
+/// # Returns
+/// The hash, bit for bit in an `i64` (as stored in `photo_phashes`), or an error if the JPEG could not be decoded
+///
+/// # External Dependencies
+/// Requires `djpeg` (libjpeg)
+pub fn compute_phash(jpeg_path: &Path) -> anyhow::Result<i64> {
+    // Decoding at 1/8 scale is much faster, and the grid is tiny anyway
+    let output = Command::new("djpeg")
+        .args(["-grayscale", "-scale", "1/8", "-pnm"])
+        .arg(jpeg_path)
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!("Error decoding {}: {}", jpeg_path.display(), String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    let (width, height, pixels) = parse_pgm(&output.stdout)?;
+    let grid = reduce(width, height, pixels);
+
+    let mut hash: u64 = 0;
+    for row in grid.chunks(HASH_WIDTH) {
+        for pair in row.windows(2) {
+            hash = (hash << 1) | (pair[0] < pair[1]) as u64;
+        }
+    }
+    Ok(hash as i64)
+}
+
+/// Parses a binary 8-bit PGM (`P5`), as written by `djpeg -grayscale -pnm`
+///
+/// # Returns
+/// Width, height and the row-major pixel data
+fn parse_pgm(data: &[u8]) -> anyhow::Result<(usize, usize, &[u8])> {
+    // The header consists of the magic number, width, height and maximum value, separated by whitespace
+    let mut fields: Vec<&[u8]> = Vec::with_capacity(4);
+    let mut pos = 0;
+    while fields.len() < 4 {
+        while data.get(pos).is_some_and(u8::is_ascii_whitespace) {
+            pos += 1;
+        }
+        let start = pos;
+        while data.get(pos).is_some_and(|byte| !byte.is_ascii_whitespace()) {
+            pos += 1;
+        }
+        if start == pos {
+            return Err(anyhow!("Truncated PGM header"));
+        }
+        fields.push(&data[start..pos]);
+    }
+    // Exactly one whitespace byte separates the header from the pixels
+    pos += 1;
+
+    let number = |field: &[u8]| -> anyhow::Result<usize> { Ok(std::str::from_utf8(field)?.parse::<usize>()?) };
+    if fields[0] != b"P5" {
+        return Err(anyhow!("Expected a grayscale PGM"));
+    }
+    let (width, height, max_value) = (number(fields[1])?, number(fields[2])?, number(fields[3])?);
+    if max_value > 255 || width == 0 || height == 0 {
+        return Err(anyhow!("Unsupported PGM ({width}x{height}, max value {max_value})"));
+    }
+
+    let pixels = data.get(pos..pos + width * height).ok_or_else(|| anyhow!("Truncated PGM data"))?;
+    Ok((width, height, pixels))
+}
+
+/// Reduces a grayscale image to a `HASH_WIDTH` x `HASH_HEIGHT` grid, averaging the pixels of each cell
+fn reduce(width: usize, height: usize, pixels: &[u8]) -> Vec<u32> {
+    let mut grid = Vec::with_capacity(HASH_WIDTH * HASH_HEIGHT);
+
+    for cell_y in 0..HASH_HEIGHT {
+        // Images smaller than the grid repeat pixels across cells
+        let y_start = cell_y * height / HASH_HEIGHT;
+        let y_end = ((cell_y + 1) * height / HASH_HEIGHT).max(y_start + 1);
+        for cell_x in 0..HASH_WIDTH {
+            let x_start = cell_x * width / HASH_WIDTH;
+            let x_end = ((cell_x + 1) * width / HASH_WIDTH).max(x_start + 1);
+
+            let mut sum: u32 = 0;
+            for y in y_start..y_end {
+                sum += pixels[y * width + x_start..y * width + x_end].iter().map(|&pixel| pixel as u32).sum::<u32>();
+            }
+            grid.push(sum / ((y_end - y_start) * (x_end - x_start)) as u32);
+        }
+    }
+    grid
+}
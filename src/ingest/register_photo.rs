@@ -0,0 +1,88 @@
+use crate::db::operations::join_photo_tag::add_tag_to_photo;
+use crate::db::operations::metadata::{add_metadata_issues, set_photo_metadata};
+use crate::db::operations::phash::set_photo_phash;
+use crate::db::operations::photo::create_photo;
+use crate::db::operations::tag::find_or_create_tag_path;
+use crate::db::operations::thumbnail::create_thumbnail;
+use crate::ingest::extract_thumbnail::extract_thumbnail_full;
+use crate::ingest::phash::compute_phash;
+use crate::ingest::trait_suisai_image_path::SuisaiImagePath;
+use crate::models::metadata::PhotoMetadata;
+use crate::models::phash::PhotoPhash;
+use crate::models::thumbnail::Thumbnail;
+use chrono::Datelike;
+use diesel::result::Error;
+use diesel::MysqlConnection;
+use rocket::serde::json::serde_json;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Registers an image that already sits in its final location under $STORAGE_ROOT: generates its
+/// thumbnail and creates its `photos` row, metadata blob, metadata issues, keyword tags and thumbnail row.
+///
+/// Only the `photos` row is required; failures in the other steps are printed and skipped.
+/// Linking the photo to an album is left to the caller.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `photo_path` - Full path to the image
+///
+/// # Returns
+/// Ok(id) with the ID of the new photo, or an error if the `photos` row could not be created
+pub fn register_photo(conn: &mut MysqlConnection, photo_path: &PathBuf) -> Result<i64, Error> {
+    let filename = photo_path.file_name().unwrap_or_default().to_string_lossy();
+
+    // Generate and store a JPEG thumbnail at `THUMBNAIL_ROOT/yyyymm/FILENAME.jpeg`
+    let date = photo_path.get_photo_date();
+    let thumbnail_dir = format!("{}/{}{:02}/", env::var("THUMBNAIL_ROOT").unwrap(), date.year(), date.month());
+    let thumbnail_filename = format!("{}.jpeg", photo_path.file_stem().unwrap().to_string_lossy());
+    let mut thumbnail_path = format!("{thumbnail_dir}{thumbnail_filename}");
+    // Create Thumbnail
+    match extract_thumbnail_full(photo_path.to_str().unwrap(), &thumbnail_dir, &thumbnail_filename) {
+        Ok(()) => println!("Thumbnail created at {thumbnail_path}"),
+        Err(e) => {
+            thumbnail_path = String::new();
+            println!("Error creating thumbnail for {filename}: {e}");
+        }
+    }
+
+    // Create a database record for the image
+    let photo = photo_path.to_db_entry();
+    let issues = photo_path.get_metadata_issues(&photo);
+    println!("{}", serde_json::to_string_pretty(&photo).unwrap());
+
+    println!("Adding {} to database", photo.file_name);
+    let photo_id = create_photo(conn, photo)?;
+
+    // Store the full metadata blob alongside the photo
+    let metadata = PhotoMetadata { id: photo_id, metadata: photo_path.get_metadata().to_string() };
+    set_photo_metadata(conn, &metadata).unwrap_or_else(|e| { println!("Error: {e}"); 0 });
+
+    // Record any fields that fell back to default values, so they can be fixed later
+    if !issues.is_empty() {
+        println!("Metadata issues: {}", issues.iter().map(|issue| issue.as_str()).collect::<Vec<_>>().join(", "));
+        add_metadata_issues(conn, photo_id, &issues).unwrap_or_else(|e| { println!("Error: {e}"); 0 });
+    }
+
+    // Import keywords from XMP as tags
+    for keyword in photo_path.get_keywords() {
+        match find_or_create_tag_path(conn, &keyword).and_then(|tag_id| add_tag_to_photo(conn, &[tag_id], &[photo_id])) {
+            Ok(_) => println!("Tagged with {}", keyword.join("|")),
+            Err(e) => println!("Error tagging with {}: {e}", keyword.join("|")),
+        }
+    }
+
+    // Create a database record for the thumbnail, if any, and hash it to find near-duplicates later
+    if !thumbnail_path.is_empty() {
+        let phash = compute_phash(Path::new(&thumbnail_path))
+            .and_then(|phash| Ok(set_photo_phash(conn, &PhotoPhash { id: photo_id, phash })?));
+        if let Err(e) = phash {
+            println!("Error storing perceptual hash: {e}");
+        }
+
+        let thumbnail = Thumbnail { id: photo_id, thumbnail_path };
+        create_thumbnail(conn, &thumbnail).unwrap_or_else(|e| println!("Error: {e}"));
+    }
+
+    Ok(photo_id)
+}
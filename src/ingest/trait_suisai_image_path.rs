@@ -1,12 +1,26 @@
 use crate::_utils::run_command::ShellReturn;
-use crate::models::photo::NewPhoto;
+use crate::models::metadata::MetadataIssue;
+use crate::models::photo::{NewPhoto, COLOR_LABELS};
+use crate::models::tag::split_tag_path;
 use crate::sh;
 use chrono::NaiveDateTime;
+use serde_json::{Map, Value};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use xxhash_rust::xxh3::xxh3_128;
 
+/// Timezone assumed when the photo has no `OffsetTimeOriginal` tag (JST)
+pub const DEFAULT_TIMEZONE: &str = "+09:00";
+/// Stored as the MIME type when it cannot be determined
+pub const UNKNOWN_MIME_TYPE: &str = "application/octet-stream";
+/// Stored as the camera model when it cannot be determined
+pub const UNKNOWN_CAMERA: &str = "Unknown Camera";
+/// Stored as the lens model when it cannot be determined
+pub const UNKNOWN_LENS: &str = "Unknown Lens";
+/// Stored as the shutter speed when it cannot be determined
+pub const UNKNOWN_SHUTTER_SPEED: &str = "Unknown";
+
 /// A trait providing methods to extract metadata from an image file path
 /// and convert it into a database-compatible format.
 ///
@@ -60,6 +74,39 @@ pub trait SuisaiImagePath {
     /// The aperture setting (f-stop) used to take the photo
     fn get_aperture(&self) -> f32;
 
+    /// GPS latitude in signed decimal degrees (south is negative), if the photo is geotagged
+    fn get_latitude(&self) -> Option<f64>;
+
+    /// GPS longitude in signed decimal degrees (west is negative), if the photo is geotagged
+    fn get_longitude(&self) -> Option<f64>;
+
+    /// GPS altitude in metres (below sea level is negative), if recorded
+    fn get_altitude(&self) -> Option<f32>;
+
+    /// Star rating (0-5) set in camera or by another application via XMP/EXIF `Rating`.
+    /// Rejected photos (rating -1) are reported as unrated; see `get_pick_flag`.
+    fn get_rating(&self) -> i8;
+
+    /// Color label from XMP `xmp:Label`, if it is one of `COLOR_LABELS`
+    fn get_color_label(&self) -> Option<String>;
+
+    /// -1 if the photo was rejected (XMP/EXIF rating of -1), 0 otherwise
+    fn get_pick_flag(&self) -> i8;
+
+    /// Keywords from XMP `lr:HierarchicalSubject` and `dc:Subject`, each split into its levels
+    /// (e.g. `["Places", "Japan", "Kyoto"]`). Flat `dc:Subject` keywords that already appear as the
+    /// leaf of a hierarchical keyword are not repeated.
+    fn get_keywords(&self) -> Vec<Vec<String>>;
+
+    /// Every metadata tag `ExifTool` can read from the image, as a JSON object keyed by `Group:Tag`.
+    /// Filesystem-level tags (`System:*`) and `ExifTool`'s own tags are left out, since they describe
+    /// the copy being read rather than the photo itself.
+    fn get_metadata(&self) -> Value;
+
+    /// Lists every field of `entry` (as produced by `to_db_entry`) that holds a fallback value
+    /// because the metadata could not be read
+    fn get_metadata_issues(&self, entry: &NewPhoto) -> Vec<MetadataIssue>;
+
     /// Returns a `crate::db::models::NewPhoto`. Does not populate the `thumbnail` field
     fn to_db_entry(&self) -> NewPhoto;
 }
@@ -91,19 +138,7 @@ impl SuisaiImagePath for PathBuf {
     }
 
     fn get_photo_timezone(&self) -> String {
-        let result = sh!("exiftool -s3 -fast2 -OffsetTimeOriginal {}", self.to_string_lossy());
-
-        match result.err_code {
-            0 => {
-                let tz = result.stdout.trim();
-                if tz.len() == 6 && (tz.starts_with('+') || tz.starts_with('-')) {
-                    tz.to_string()
-                } else {
-                    "+09:00".to_string()
-                }
-            }
-            _ => "+09:00".to_string(),
-        }
+        read_offset_time_original(self).unwrap_or_else(|| DEFAULT_TIMEZONE.to_string())
     }
 
 
@@ -128,7 +163,7 @@ impl SuisaiImagePath for PathBuf {
 
         match result.err_code {
             0 => result.stdout.trim().to_string(),
-            _ => "application/octet-stream".to_string(),
+            _ => UNKNOWN_MIME_TYPE.to_string(),
         }
     }
 
@@ -137,7 +172,7 @@ impl SuisaiImagePath for PathBuf {
 
         match result.err_code {
             0 => result.stdout.trim().to_string(),
-            _ => "Unknown Camera".to_string(),
+            _ => UNKNOWN_CAMERA.to_string(),
         }
     }
 
@@ -156,7 +191,7 @@ impl SuisaiImagePath for PathBuf {
         let result = sh!("exiftool -s3 -fast2 -Lens {}", self.to_string_lossy());
         match result.err_code {
             0 => result.stdout.trim().to_string(),
-            _ => "Unknown Lens".to_string(),
+            _ => UNKNOWN_LENS.to_string(),
         }
     }
 
@@ -204,7 +239,7 @@ impl SuisaiImagePath for PathBuf {
 
         match result.err_code {
             0 => result.stdout.trim().to_string(),
-            _ => "Unknown".to_string(),
+            _ => UNKNOWN_SHUTTER_SPEED.to_string(),
         }
     }
 
@@ -217,6 +252,128 @@ impl SuisaiImagePath for PathBuf {
         }
     }
 
+    fn get_latitude(&self) -> Option<f64> {
+        // The composite tag combines `GPSLatitude` and `GPSLatitudeRef` into a signed value when using `-n`
+        let result = sh!("exiftool -s3 -fast2 -n -Composite:GPSLatitude {}", self.to_string_lossy());
+
+        match result.err_code {
+            0 => result.stdout.trim().parse::<f64>().ok().filter(|lat| (-90.0..=90.0).contains(lat)),
+            _ => None,
+        }
+    }
+
+    fn get_longitude(&self) -> Option<f64> {
+        let result = sh!("exiftool -s3 -fast2 -n -Composite:GPSLongitude {}", self.to_string_lossy());
+
+        match result.err_code {
+            0 => result.stdout.trim().parse::<f64>().ok().filter(|lon| (-180.0..=180.0).contains(lon)),
+            _ => None,
+        }
+    }
+
+    fn get_altitude(&self) -> Option<f32> {
+        let result = sh!("exiftool -s3 -fast2 -n -Composite:GPSAltitude {}", self.to_string_lossy());
+
+        match result.err_code {
+            0 => result.stdout.trim().parse::<f32>().ok(),
+            _ => None,
+        }
+    }
+
+    fn get_rating(&self) -> i8 {
+        let result = sh!("exiftool -s3 -fast2 -n -Rating {}", self.to_string_lossy());
+
+        match result.err_code {
+            0 => result.stdout.trim().parse::<f32>().map(|rating| rating.round().clamp(0.0, 5.0) as i8).unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    fn get_color_label(&self) -> Option<String> {
+        let result = sh!("exiftool -s3 -fast2 -XMP-xmp:Label {}", self.to_string_lossy());
+
+        match result.err_code {
+            0 => Some(result.stdout.trim().to_lowercase()).filter(|label| COLOR_LABELS.contains(&label.as_str())),
+            _ => None,
+        }
+    }
+
+    fn get_pick_flag(&self) -> i8 {
+        let result = sh!("exiftool -s3 -fast2 -n -Rating {}", self.to_string_lossy());
+
+        match result.err_code {
+            0 if result.stdout.trim().parse::<f32>().is_ok_and(|rating| rating < 0.0) => -1,
+            _ => 0,
+        }
+    }
+
+    fn get_keywords(&self) -> Vec<Vec<String>> {
+        let result = sh!("exiftool -json -fast2 -XMP-lr:HierarchicalSubject -XMP-dc:Subject {}", self.to_string_lossy());
+
+        let entry = match result.err_code {
+            0 => serde_json::from_str::<Vec<Map<String, Value>>>(&result.stdout).ok().and_then(|mut entries| entries.pop()),
+            _ => None,
+        }.unwrap_or_default();
+
+        // Both tags are lists, but ExifTool prints single-item lists as a plain value
+        let as_list = |value: Option<&Value>| -> Vec<String> {
+            match value {
+                Some(Value::Array(items)) => items.iter().map(|item| item.as_str().map(String::from).unwrap_or_else(|| item.to_string())).collect(),
+                Some(Value::String(item)) => vec![item.clone()],
+                Some(item) => vec![item.to_string()],
+                None => vec![],
+            }
+        };
+
+        let mut keywords: Vec<Vec<String>> = as_list(entry.get("HierarchicalSubject"))
+            .iter()
+            .filter_map(|keyword| split_tag_path(keyword).ok())
+            .collect();
+
+        for subject in as_list(entry.get("Subject")) {
+            let Ok(segments) = split_tag_path(&subject) else { continue };
+            if !keywords.iter().any(|keyword| keyword.last() == segments.last()) {
+                keywords.push(segments);
+            }
+        }
+
+        keywords
+    }
+
+    fn get_metadata(&self) -> Value {
+        let result = sh!("exiftool -json -a -G1 -struct {}", self.to_string_lossy());
+
+        // ExifTool returns an array with one object per file read
+        let entry = match result.err_code {
+            0 => serde_json::from_str::<Vec<Map<String, Value>>>(&result.stdout).ok().and_then(|mut entries| entries.pop()),
+            _ => None,
+        };
+
+        let metadata: Map<String, Value> = entry
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(key, _)| key != "SourceFile" && !key.starts_with("System:") && !key.starts_with("ExifTool:"))
+            .collect();
+
+        Value::Object(metadata)
+    }
+
+    fn get_metadata_issues(&self, entry: &NewPhoto) -> Vec<MetadataIssue> {
+        let checks = [
+            (entry.photo_date.and_utc().timestamp() == 0, MetadataIssue::MissingDate),
+            (read_offset_time_original(self).is_none(), MetadataIssue::MissingTimezone),
+            (entry.resolution_width == 0 || entry.resolution_height == 0, MetadataIssue::MissingResolution),
+            (entry.mime_type.is_empty() || entry.mime_type == UNKNOWN_MIME_TYPE, MetadataIssue::UnknownMimeType),
+            (entry.camera_model.is_empty() || entry.camera_model == UNKNOWN_CAMERA, MetadataIssue::UnknownCamera),
+            (entry.lens_model.is_empty() || entry.lens_model == UNKNOWN_LENS, MetadataIssue::UnknownLens),
+            (entry.iso == 0, MetadataIssue::MissingIso),
+            (entry.shutter_speed.is_empty() || entry.shutter_speed == UNKNOWN_SHUTTER_SPEED, MetadataIssue::MissingShutterSpeed),
+            (entry.aperture == 0.0, MetadataIssue::MissingAperture),
+        ];
+
+        checks.into_iter().filter(|(failed, _)| *failed).map(|(_, issue)| issue).collect()
+    }
+
     fn to_db_entry(&self) -> NewPhoto {
         NewPhoto {
             hash: self.get_hash(),
@@ -234,6 +391,23 @@ impl SuisaiImagePath for PathBuf {
             iso: self.get_iso(),
             shutter_speed: self.get_shutter_speed(),
             aperture: self.get_aperture(),
+            latitude: self.get_latitude(),
+            longitude: self.get_longitude(),
+            altitude: self.get_altitude(),
+            rating: self.get_rating(),
+            color_label: self.get_color_label(),
+            pick_flag: self.get_pick_flag(),
+            original_id: None,
         }
     }
-}
\ No newline at end of file
+}
+
+/// Reads the `OffsetTimeOriginal` tag of an image, if present and well-formed (e.g. `+09:00`)
+fn read_offset_time_original(path: &Path) -> Option<String> {
+    let result = sh!("exiftool -s3 -fast2 -OffsetTimeOriginal {}", path.to_string_lossy());
+
+    match result.err_code {
+        0 => Some(result.stdout.trim().to_string()).filter(|tz| tz.len() == 6 && (tz.starts_with('+') || tz.starts_with('-'))),
+        _ => None,
+    }
+}
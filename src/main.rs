@@ -1,10 +1,6 @@
 use crate::cli::run_cli;
 use crate::preflight::check_directories;
-use diesel::r2d2::ConnectionManager;
-use diesel::MysqlConnection;
 use dotenvy::dotenv;
-use std::env;
-use std::sync::LazyLock;
 
 mod db;
 mod endpoints;
@@ -14,23 +10,11 @@ mod _utils;
 mod preflight;
 mod models;
 mod fs_operations;
-
-type Pool = r2d2::Pool<ConnectionManager<MysqlConnection>>;
-
-static DB_POOL: LazyLock<Pool> = LazyLock::new(|| {
-    establish_connection_pool()
-});
-
-
-/// Function to establish database connection pool
-fn establish_connection_pool() -> Pool {
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let manager = ConnectionManager::<MysqlConnection>::new(database_url);
-    Pool::builder()
-        .build(manager)
-        .expect("Failed to create pool")
-}
-
+mod maintenance;
+mod sidecar;
+mod audit;
+mod trash;
+mod album_merge;
 
 #[rocket::main]
 async fn main() {
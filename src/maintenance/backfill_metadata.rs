@@ -0,0 +1,52 @@
+use crate::_utils::path_prefix::PathPrefix;
+use crate::db::operations::metadata::{get_photos_without_metadata, set_photo_metadata};
+use crate::db::operations::paths::get_photo_path;
+use crate::db::pool::establish_connection;
+use crate::ingest::trait_suisai_image_path::SuisaiImagePath;
+use crate::models::metadata::PhotoMetadata;
+use std::env;
+use std::path::PathBuf;
+
+/// Reads and stores the full metadata blob for every photo in the library that does not have one yet,
+/// e.g. photos ingested before metadata blobs were stored
+pub fn backfill_metadata(dry: bool) {
+    let mut conn = establish_connection();
+    let storage_root = PathBuf::from(env::var("STORAGE_ROOT").unwrap());
+
+    let photo_ids = get_photos_without_metadata(&mut conn).expect("Failed to query photos without metadata");
+    println!("Found {} photos without stored metadata", photo_ids.len());
+
+    let mut failed = 0;
+    for photo_id in photo_ids {
+        let photo_path = match get_photo_path(&mut conn, photo_id).map_err(anyhow::Error::from).and_then(|path| Ok(path.prefix_within(&storage_root)?)) {
+            Ok(path) => path,
+            Err(e) => {
+                println!("Error resolving path of photo {photo_id}: {e}");
+                failed += 1;
+                continue;
+            }
+        };
+
+        if !photo_path.is_file() {
+            println!("Photo {photo_id} not found at {}, skipping", photo_path.display());
+            failed += 1;
+            continue;
+        }
+
+        let metadata = photo_path.get_metadata();
+        if dry {
+            println!("Would store {} tags for {}", metadata.as_object().map(|tags| tags.len()).unwrap_or(0), photo_path.display());
+            continue;
+        }
+
+        match set_photo_metadata(&mut conn, &PhotoMetadata { id: photo_id, metadata: metadata.to_string() }) {
+            Ok(_) => println!("Stored metadata for {}", photo_path.display()),
+            Err(e) => {
+                println!("Error storing metadata for {}: {e}", photo_path.display());
+                failed += 1;
+            }
+        }
+    }
+
+    println!("Finished ({failed} failed)");
+}
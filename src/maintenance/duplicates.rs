@@ -0,0 +1,132 @@
+use crate::db::operations::phash::{get_photos_without_phash, set_photo_phash};
+use crate::db::operations::thumbnail::get_thumbnail;
+use crate::db::pool::establish_connection;
+use crate::ingest::phash::compute_phash;
+use crate::models::phash::{phash_distance, PhotoPhash};
+use crate::models::photo::Photo;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Mime type of JPEGs, which lose out against raws and other formats when picking the best copy
+const JPEG_MIME_TYPE: &str = "image/jpeg";
+
+/// Groups photos whose perceptual hashes are at most `max_distance` bits apart.
+///
+/// Groups are transitive: if A is close to B and B is close to C, all three end up in one group.
+/// To avoid comparing every pair, hashes are split into `max_distance + 1` bands. Two hashes within
+/// `max_distance` bits must agree exactly on at least one band, so only hashes sharing a band are compared.
+///
+/// # Arguments
+/// * `phashes` - Perceptual hashes of the photos to group
+/// * `max_distance` - Largest number of differing bits (at most 63)
+///
+/// # Returns
+/// Groups of at least two photo IDs, each sorted by ID, ordered by their first ID
+pub fn cluster_phashes(phashes: &[PhotoPhash], max_distance: u32) -> Vec<Vec<i64>> {
+    let bands = max_distance.min(63) + 1;
+    let band_bits = 64 / bands;
+
+    // Union-find over indices into `phashes`
+    let mut parent: Vec<usize> = (0..phashes.len()).collect();
+    fn find(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    for band in 0..bands {
+        // The last band takes the bits left over by the integer division
+        let shift = band * band_bits;
+        let bits = if band == bands - 1 { 64 - shift } else { band_bits };
+        let mask = if bits == 64 { u64::MAX } else { (1u64 << bits) - 1 };
+
+        let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (i, phash) in phashes.iter().enumerate() {
+            buckets.entry((phash.phash as u64 >> shift) & mask).or_default().push(i);
+        }
+
+        for members in buckets.values() {
+            for (n, &a) in members.iter().enumerate() {
+                for &b in &members[n + 1..] {
+                    if phash_distance(phashes[a].phash, phashes[b].phash) <= max_distance {
+                        let (root_a, root_b) = (find(&mut parent, a), find(&mut parent, b));
+                        parent[root_a] = root_b;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<i64>> = HashMap::new();
+    for (i, phash) in phashes.iter().enumerate() {
+        let root = find(&mut parent, i);
+        groups.entry(root).or_default().push(phash.id);
+    }
+
+    let mut clusters: Vec<Vec<i64>> = groups.into_values().filter(|ids| ids.len() > 1).collect();
+    for ids in &mut clusters {
+        ids.sort_unstable();
+    }
+    clusters.sort_unstable_by_key(|ids| ids[0]);
+    clusters
+}
+
+/// Picks the copy to keep out of a group of duplicates: anything over a JPEG (raws and DNGs keep
+/// the most data), then the most pixels, then the largest file, then the oldest photo.
+///
+/// # Returns
+/// The best photo, or `None` if `photos` is empty
+pub fn best_photo(photos: &[Photo]) -> Option<&Photo> {
+    photos.iter().max_by_key(|photo| (
+        photo.mime_type != JPEG_MIME_TYPE,
+        photo.resolution_width as i64 * photo.resolution_height as i64,
+        photo.size_on_disk,
+        -photo.id,
+    ))
+}
+
+/// Computes and stores the perceptual hash of every photo that has a thumbnail but no hash yet,
+/// e.g. photos ingested before perceptual hashes were stored
+pub fn backfill_phash(dry: bool) {
+    let mut conn = establish_connection();
+
+    let photo_ids = get_photos_without_phash(&mut conn).expect("Failed to query photos without perceptual hash");
+    println!("Found {} photos without a perceptual hash", photo_ids.len());
+
+    let mut failed = 0;
+    for photo_id in photo_ids {
+        let thumbnail = match get_thumbnail(&mut conn, photo_id) {
+            Ok(thumbnail) => thumbnail,
+            Err(e) => {
+                println!("Error querying thumbnail of photo {photo_id}: {e}");
+                failed += 1;
+                continue;
+            }
+        };
+
+        let phash = match compute_phash(Path::new(&thumbnail.thumbnail_path)) {
+            Ok(phash) => phash,
+            Err(e) => {
+                println!("Error hashing photo {photo_id}: {e}");
+                failed += 1;
+                continue;
+            }
+        };
+        if dry {
+            println!("Would store perceptual hash {:016x} for photo {photo_id}", phash as u64);
+            continue;
+        }
+
+        match set_photo_phash(&mut conn, &PhotoPhash { id: photo_id, phash }) {
+            Ok(_) => println!("Stored perceptual hash for photo {photo_id}"),
+            Err(e) => {
+                println!("Error storing perceptual hash for photo {photo_id}: {e}");
+                failed += 1;
+            }
+        }
+    }
+
+    println!("Finished ({failed} failed)");
+}
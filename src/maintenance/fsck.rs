@@ -0,0 +1,424 @@
+use crate::_utils::path_prefix::PathPrefix;
+use crate::db::operations::album::get_all_albums;
+use crate::db::operations::join_album_album::{get_album_links, remove_album_link};
+use crate::db::operations::join_album_photo::{add_photo_to_album, remove_photo_from_album};
+use crate::db::operations::paths::{get_album_path, get_photo_path};
+use crate::db::operations::photo::{delete_photo, get_all_photos};
+use crate::db::operations::thumbnail::{delete_thumbnail, get_all_thumbnails};
+use crate::db::pool::establish_connection;
+use crate::db::unit_of_work::unit_of_work;
+use crate::fs_operations::journal::FsJournal;
+use crate::fs_operations::photo::move_photo_fs;
+use crate::ingest::extract_thumbnail::extract_thumbnail_full;
+use crate::maintenance::rescan::adopt_file;
+use crate::models::join::AlbumAlbum;
+use crate::models::photo::Photo;
+use clap::ValueEnum;
+use diesel::MysqlConnection;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory under $STORAGE_ROOT that orphan files are moved into by `--orphans quarantine`
+pub const QUARANTINE_DIR: &str = ".quarantine";
+
+/// What to do with photos whose file cannot be found anywhere under $STORAGE_ROOT
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MissingStrategy {
+    /// Only report them
+    Report,
+    /// Delete the photo (and its thumbnail) from the database
+    Forget,
+}
+
+/// What to do with files under $STORAGE_ROOT that don't belong to any photo
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OrphanStrategy {
+    /// Only report them
+    Report,
+    /// Move them into `$STORAGE_ROOT/.quarantine`, keeping their relative path
+    Quarantine,
+    /// Register orphan images as photos of the album matching their directory (like `rescan`)
+    Adopt,
+    /// Delete them
+    Delete,
+}
+
+/// What to do with photos found on disk somewhere other than where the database places them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MisplacedStrategy {
+    /// Only report them
+    Report,
+    /// Move the file (and its associated files) to where the database places it
+    MoveFile,
+    /// Move the photo in the database to the album matching the directory it was found in
+    UpdateDb,
+}
+
+/// What to do with thumbnail entries whose file is missing, and thumbnail files without an entry
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ThumbnailStrategy {
+    /// Only report them
+    Report,
+    /// Re-extract missing thumbnails from their photo, and delete unreferenced thumbnail files
+    Regenerate,
+    /// Delete entries of missing thumbnails, and delete unreferenced thumbnail files
+    Drop,
+}
+
+/// What to do with cycles in the album relation table
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CycleStrategy {
+    /// Only report them
+    Report,
+    /// Remove the parent association that closes each cycle
+    Detach,
+}
+
+/// Options for `fsck`. Strategies are only applied if `repair` is set.
+#[derive(Debug)]
+pub struct FsckOptions {
+    pub repair: bool,
+    pub missing: MissingStrategy,
+    pub orphans: OrphanStrategy,
+    pub misplaced: MisplacedStrategy,
+    pub thumbnails: ThumbnailStrategy,
+    pub cycles: CycleStrategy,
+}
+
+/// Number of problems found per class
+#[derive(Default, Debug)]
+struct FsckReport {
+    cycles: usize,
+    missing: usize,
+    misplaced: usize,
+    orphans: usize,
+    dangling_thumbnails: usize,
+    orphan_thumbnails: usize,
+    failed_repairs: usize,
+}
+
+/// Walks $STORAGE_ROOT and $THUMBNAIL_ROOT and compares them with the database, reporting (and, with
+/// `repair`, fixing) album cycles, missing photos, misplaced photos, orphan files and dangling thumbnails.
+///
+/// Album cycles are checked first, since photo paths cannot be resolved through a cycle.
+/// Hidden files and directories (e.g. `.quarantine`) are skipped.
+pub fn fsck(options: FsckOptions) {
+    let mut conn = establish_connection();
+    let storage_root = PathBuf::from(env::var("STORAGE_ROOT").unwrap());
+    let thumbnail_root = PathBuf::from(env::var("THUMBNAIL_ROOT").unwrap());
+    let mut report = FsckReport::default();
+
+    if !options.repair {
+        println!("Running in report-only mode, pass --repair to fix problems");
+    }
+
+    check_album_cycles(&mut conn, &options, &mut report);
+    check_photos(&mut conn, &storage_root, &thumbnail_root, &options, &mut report);
+    check_thumbnails(&mut conn, &thumbnail_root, &options, &mut report);
+
+    println!(
+        "Finished: {} album cycles, {} missing photos, {} misplaced photos, {} orphan files, {} dangling thumbnails, {} orphan thumbnails ({} repairs failed)",
+        report.cycles, report.missing, report.misplaced, report.orphans, report.dangling_thumbnails, report.orphan_thumbnails, report.failed_repairs
+    );
+}
+
+/// Finds the album associations that close a cycle, and detaches them if requested
+fn check_album_cycles(conn: &mut MysqlConnection, options: &FsckOptions, report: &mut FsckReport) {
+    let links = get_album_links(conn).expect("Failed to query album relations");
+
+    let mut children: HashMap<i32, Vec<i32>> = HashMap::new();
+    for link in &links {
+        children.entry(link.parent_id).or_default().push(link.album_id);
+    }
+
+    // Depth-first search; an edge into an album that is still on the stack closes a cycle
+    let mut finished: HashSet<i32> = HashSet::new();
+    let mut cycle_links: Vec<AlbumAlbum> = Vec::new();
+    let mut roots: Vec<i32> = children.keys().copied().collect();
+    roots.sort_unstable();
+    for root in roots {
+        let mut stack: Vec<i32> = Vec::new();
+        find_back_edges(root, &children, &mut stack, &mut finished, &mut cycle_links);
+    }
+
+    for link in cycle_links {
+        report.cycles += 1;
+        println!("Album cycle: album {} is a descendant of its own child {}", link.parent_id, link.album_id);
+
+        if options.repair && options.cycles == CycleStrategy::Detach {
+            match remove_album_link(conn, link.parent_id, link.album_id) {
+                Ok(_) => println!("  Detached album {} from parent {}", link.album_id, link.parent_id),
+                Err(e) => {
+                    println!("  Error detaching album {}: {e}", link.album_id);
+                    report.failed_repairs += 1;
+                }
+            }
+        }
+    }
+}
+
+fn find_back_edges(album_id: i32, children: &HashMap<i32, Vec<i32>>, stack: &mut Vec<i32>, finished: &mut HashSet<i32>, cycle_links: &mut Vec<AlbumAlbum>) {
+    if finished.contains(&album_id) {
+        return;
+    }
+
+    stack.push(album_id);
+    for &child in children.get(&album_id).map(Vec::as_slice).unwrap_or_default() {
+        if stack.contains(&child) {
+            cycle_links.push(AlbumAlbum { parent_id: album_id, album_id: child });
+        } else {
+            find_back_edges(child, children, stack, finished, cycle_links);
+        }
+    }
+    stack.pop();
+    finished.insert(album_id);
+}
+
+/// Compares every photo's expected location with the files under $STORAGE_ROOT, then handles
+/// missing photos, misplaced photos and orphan files in turn
+fn check_photos(conn: &mut MysqlConnection, storage_root: &Path, thumbnail_root: &Path, options: &FsckOptions, report: &mut FsckReport) {
+    // Index every file on disk by name, relative to $STORAGE_ROOT
+    let mut disk_files: Vec<PathBuf> = Vec::new();
+    walk_files(storage_root, thumbnail_root, &mut disk_files).expect("Failed to walk $STORAGE_ROOT");
+    let disk_files: Vec<PathBuf> = disk_files
+        .into_iter()
+        .filter_map(|path| path.strip_prefix(storage_root).ok().map(Path::to_path_buf))
+        .collect();
+
+    let mut by_name: HashMap<OsString, Vec<&PathBuf>> = HashMap::new();
+    for path in &disk_files {
+        by_name.entry(path.file_name().unwrap_or_default().to_os_string()).or_default().push(path);
+    }
+
+    let photos = get_all_photos(conn).expect("Failed to query photos");
+    let mut accounted: HashSet<PathBuf> = HashSet::new();
+    let mut missing: Vec<&Photo> = Vec::new();
+    let mut misplaced: Vec<(&Photo, PathBuf, PathBuf)> = Vec::new();
+
+    for photo in &photos {
+        let expected = match get_photo_path(conn, photo.id) {
+            Ok(path) => path,
+            Err(e) => {
+                println!("Error resolving path of photo {}: {e}", photo.id);
+                continue;
+            }
+        };
+
+        let full_expected = match expected.prefix_within(storage_root) {
+            Ok(path) => path,
+            Err(e) => {
+                println!("Error resolving path of photo {}: {e}", photo.id);
+                continue;
+            }
+        };
+        if full_expected.is_file() {
+            accounted.insert(expected);
+            continue;
+        }
+
+        let found = by_name
+            .get(std::ffi::OsStr::new(&photo.file_name))
+            .and_then(|paths| paths.iter().find(|path| !accounted.contains(**path)));
+        match found {
+            Some(&found) => {
+                accounted.insert(found.clone());
+                misplaced.push((photo, found.clone(), expected));
+            }
+            None => missing.push(photo),
+        }
+    }
+
+    for photo in missing {
+        report.missing += 1;
+        println!("Missing photo: {} ({}) was not found anywhere under $STORAGE_ROOT", photo.id, photo.file_name);
+
+        if options.repair && options.missing == MissingStrategy::Forget {
+            match delete_photo(conn, &[photo.id]) {
+                Ok(_) => println!("  Deleted photo {} from the database", photo.id),
+                Err(e) => {
+                    println!("  Error deleting photo {}: {e}", photo.id);
+                    report.failed_repairs += 1;
+                }
+            }
+        }
+    }
+
+    let album_ids = if options.repair && options.misplaced == MisplacedStrategy::UpdateDb { album_ids_by_path(conn) } else { HashMap::new() };
+    for (photo, found, expected) in misplaced {
+        report.misplaced += 1;
+        println!("Misplaced photo: {} is at {}, but expected at {}", photo.id, found.display(), expected.display());
+
+        if !options.repair {
+            continue;
+        }
+        let result = match options.misplaced {
+            MisplacedStrategy::Report => continue,
+            MisplacedStrategy::MoveFile => move_misplaced_file(storage_root, &found, &expected),
+            MisplacedStrategy::UpdateDb => move_misplaced_entry(conn, photo.id, &found, &album_ids),
+        };
+        match result {
+            Ok(()) => println!("  Repaired photo {}", photo.id),
+            Err(e) => {
+                println!("  Error repairing photo {}: {e}", photo.id);
+                report.failed_repairs += 1;
+            }
+        }
+    }
+
+    // Files sharing a photo's base name in the same directory (sidecars, exports, ...) belong to it
+    let photo_prefixes: HashSet<(PathBuf, OsString)> = accounted
+        .iter()
+        .filter_map(|path| Some((path.parent()?.to_path_buf(), path.file_prefix()?.to_os_string())))
+        .collect();
+
+    for path in &disk_files {
+        if accounted.contains(path) {
+            continue;
+        }
+        let parent = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let prefix = path.file_prefix().unwrap_or_default().to_os_string();
+        if photo_prefixes.contains(&(parent, prefix)) {
+            continue;
+        }
+
+        report.orphans += 1;
+        println!("Orphan file: {}", path.display());
+
+        if !options.repair {
+            continue;
+        }
+        let result = match options.orphans {
+            OrphanStrategy::Report => continue,
+            OrphanStrategy::Quarantine => quarantine_file(storage_root, path).map_err(anyhow::Error::from),
+            OrphanStrategy::Delete => path.prefix_within(storage_root).and_then(fs::remove_file).map_err(anyhow::Error::from),
+            OrphanStrategy::Adopt => adopt_file(conn, path).map(|photo_id| println!("  Adopted as photo {photo_id}")),
+        };
+        if let Err(e) = result {
+            println!("  Error repairing {}: {e}", path.display());
+            report.failed_repairs += 1;
+        }
+    }
+}
+
+/// Compares thumbnail entries with the files under $THUMBNAIL_ROOT
+fn check_thumbnails(conn: &mut MysqlConnection, thumbnail_root: &Path, options: &FsckOptions, report: &mut FsckReport) {
+    let thumbnails = get_all_thumbnails(conn).expect("Failed to query thumbnails");
+    let repair = options.repair && options.thumbnails != ThumbnailStrategy::Report;
+
+    for thumbnail in &thumbnails {
+        let thumbnail_path = PathBuf::from(&thumbnail.thumbnail_path);
+        if thumbnail_path.is_file() {
+            continue;
+        }
+
+        report.dangling_thumbnails += 1;
+        println!("Dangling thumbnail: photo {} has no thumbnail at {}", thumbnail.id, thumbnail_path.display());
+
+        if !repair {
+            continue;
+        }
+        let result = match options.thumbnails {
+            ThumbnailStrategy::Regenerate => regenerate_thumbnail(conn, thumbnail.id, &thumbnail_path),
+            _ => delete_thumbnail(conn, &[thumbnail.id]).map(|_| ()).map_err(anyhow::Error::from),
+        };
+        if let Err(e) = result {
+            println!("  Error repairing thumbnail of photo {}: {e}", thumbnail.id);
+            report.failed_repairs += 1;
+        }
+    }
+
+    let referenced: HashSet<PathBuf> = thumbnails.iter().map(|thumbnail| PathBuf::from(&thumbnail.thumbnail_path)).collect();
+    let mut thumbnail_files: Vec<PathBuf> = Vec::new();
+    walk_files(thumbnail_root, Path::new(""), &mut thumbnail_files).expect("Failed to walk $THUMBNAIL_ROOT");
+
+    for path in thumbnail_files.iter().filter(|path| !referenced.contains(*path)) {
+        report.orphan_thumbnails += 1;
+        println!("Orphan thumbnail: {}", path.display());
+
+        if repair && let Err(e) = fs::remove_file(path) {
+            println!("  Error deleting {}: {e}", path.display());
+            report.failed_repairs += 1;
+        }
+    }
+}
+
+/// Recursively collects all files under `dir`, skipping hidden entries and the `skip` directory
+pub fn walk_files(dir: &Path, skip: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') || path == skip {
+            continue;
+        }
+
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk_files(&path, skip, files)?;
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Maps the path of every album (relative to $STORAGE_ROOT) to its ID
+fn album_ids_by_path(conn: &mut MysqlConnection) -> HashMap<PathBuf, i32> {
+    let albums = get_all_albums(conn).expect("Failed to query albums");
+    albums
+        .into_iter()
+        .filter_map(|album| get_album_path(conn, album.id).ok().map(|path| (path, album.id)))
+        .collect()
+}
+
+fn move_misplaced_file(storage_root: &Path, found: &Path, expected: &Path) -> anyhow::Result<()> {
+    let dest_dir = expected.parent().unwrap_or(Path::new(""));
+
+    // Dropping the journal on error moves everything back
+    let mut journal = FsJournal::new();
+    journal.create_dir_all(&dest_dir.prefix_within(storage_root)?)?;
+    move_photo_fs(&mut journal, found, dest_dir)?;
+    for e in journal.commit() {
+        println!("  Error cleaning up: {e}");
+    }
+    Ok(())
+}
+
+fn move_misplaced_entry(conn: &mut MysqlConnection, photo_id: i64, found: &Path, album_ids: &HashMap<PathBuf, i32>) -> anyhow::Result<()> {
+    let found_dir = found.parent().unwrap_or(Path::new(""));
+
+    if found_dir == Path::new("unfiled") {
+        remove_photo_from_album(conn, &[photo_id])?;
+        return Ok(());
+    }
+
+    let album_id = *album_ids
+        .get(found_dir)
+        .ok_or_else(|| anyhow::anyhow!("No album corresponds to directory {}", found_dir.display()))?;
+    unit_of_work(conn, |conn, _| {
+        remove_photo_from_album(conn, &[photo_id])?;
+        add_photo_to_album(conn, album_id, &[photo_id])?;
+        Ok(())
+    })
+}
+
+fn quarantine_file(storage_root: &Path, path: &Path) -> std::io::Result<()> {
+    let dest = path.prefix_within(storage_root.join(QUARANTINE_DIR))?;
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(path.prefix_within(storage_root)?, dest)
+}
+
+fn regenerate_thumbnail(conn: &mut MysqlConnection, photo_id: i64, thumbnail_path: &Path) -> anyhow::Result<()> {
+    let storage_root = PathBuf::from(env::var("STORAGE_ROOT").unwrap());
+    let photo_path = get_photo_path(conn, photo_id)?.prefix_within(&storage_root)?;
+    if !photo_path.is_file() {
+        return Err(anyhow::anyhow!("Photo file {} not found", photo_path.display()));
+    }
+
+    let output_dir = thumbnail_path.parent().unwrap_or(Path::new(""));
+    let file_name = thumbnail_path.file_name().unwrap_or_default();
+    extract_thumbnail_full(&photo_path.to_string_lossy(), &output_dir.to_string_lossy(), &file_name.to_string_lossy())
+}
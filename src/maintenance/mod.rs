@@ -0,0 +1,7 @@
+pub mod backfill_metadata;
+pub mod fsck;
+pub mod verify;
+pub mod rescan;
+pub mod purge_trash;
+pub mod duplicates;
+pub mod stacks;
@@ -0,0 +1,134 @@
+use crate::db::operations::album::{delete_album, get_trashed_albums};
+use crate::db::operations::photo::{delete_photo, get_trashed_photos};
+use crate::db::operations::thumbnail::{get_thumbnail, is_thumbnail_shared};
+use crate::db::pool::{establish_connection, Pool};
+use crate::db::unit_of_work::unit_of_work;
+use crate::fs_operations::album::purge_album_fs;
+use crate::fs_operations::photo::purge_photo_fs;
+use chrono::{Local, NaiveDateTime, TimeDelta};
+use diesel::{MysqlConnection, OptionalExtension};
+use rocket::tokio;
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Number of days trashed photos and albums are kept, unless `$TRASH_RETENTION_DAYS` is set
+const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+
+/// How often the scheduled purge runs
+const PURGE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Totals of a purge run
+#[derive(Default, Debug)]
+pub struct PurgeSummary {
+    pub photos: usize,
+    pub albums: usize,
+    pub failed: usize,
+}
+
+/// Gets how long trashed photos and albums are kept, from `$TRASH_RETENTION_DAYS`
+pub fn trash_retention() -> TimeDelta {
+    let days = env::var("TRASH_RETENTION_DAYS")
+        .ok()
+        .and_then(|days| days.trim().parse::<i64>().ok())
+        .filter(|days| *days >= 0)
+        .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS);
+    TimeDelta::days(days)
+}
+
+/// Permanently deletes trashed photos and albums, including their files and thumbnails.
+///
+/// Every photo and album is purged in its own unit of work, so one failure doesn't keep the rest in the trash.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `cutoff` - Only purge entries trashed before this time, or `None` to empty the whole trash
+///
+/// # Returns
+/// Totals of the run, or an error if the trash could not be queried
+pub fn purge_trash(conn: &mut MysqlConnection, cutoff: Option<NaiveDateTime>) -> anyhow::Result<PurgeSummary> {
+    let mut summary = PurgeSummary::default();
+    let expired = |deleted_at: Option<NaiveDateTime>| match (deleted_at, cutoff) {
+        (Some(deleted_at), Some(cutoff)) => deleted_at < cutoff,
+        (deleted_at, None) => deleted_at.is_some(),
+        (None, _) => false,
+    };
+
+    for photo in get_trashed_photos(conn)?.into_iter().filter(|photo| expired(photo.deleted_at)) {
+        let result = unit_of_work(conn, |conn, journal| {
+            // Copies share their original's thumbnail, which is only deleted with the last of them
+            let thumb_path = match get_thumbnail(conn, photo.id).optional()? {
+                Some(thumbnail) if !is_thumbnail_shared(conn, &thumbnail)? => Some(PathBuf::from(thumbnail.thumbnail_path)),
+                _ => None,
+            };
+            delete_photo(conn, &[photo.id])?;
+            purge_photo_fs(journal, photo.id, &photo.file_name, thumb_path.as_deref())?;
+            Ok(())
+        });
+        match result {
+            Ok(()) => summary.photos += 1,
+            Err(e) => {
+                println!("Error purging photo {}: {e}", photo.id);
+                summary.failed += 1;
+            }
+        }
+    }
+
+    for album in get_trashed_albums(conn)?.into_iter().filter(|album| expired(album.deleted_at)) {
+        let result = unit_of_work(conn, |conn, journal| {
+            delete_album(conn, album.id)?;
+            purge_album_fs(journal, album.id)?;
+            Ok(())
+        });
+        match result {
+            Ok(()) => summary.albums += 1,
+            Err(e) => {
+                println!("Error purging album {}: {e}", album.id);
+                summary.failed += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Entry point of the `purge-trash` subcommand
+///
+/// # Arguments
+/// * `all` - Empty the whole trash instead of only the entries older than `$TRASH_RETENTION_DAYS`
+pub fn purge(all: bool) {
+    let mut conn = establish_connection();
+
+    let cutoff = if all { None } else { Some(Local::now().naive_local() - trash_retention()) };
+    let summary = purge_trash(&mut conn, cutoff).expect("Failed to purge trash");
+    println!("Finished: purged {} photos and {} albums, {} failed", summary.photos, summary.albums, summary.failed);
+}
+
+/// Purges entries older than `$TRASH_RETENTION_DAYS` from the trash once a day, starting at server start.
+/// Never returns; spawn it as a background task.
+///
+/// # Arguments
+/// * `pool` - Connection pool of the web server
+pub async fn run_scheduled_purge(pool: Pool) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        // Deleting files is blocking I/O, so keep it off the async workers
+        let pool = pool.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get()?;
+            purge_trash(&mut conn, Some(Local::now().naive_local() - trash_retention()))
+        }).await;
+
+        match result {
+            Ok(Ok(summary)) => println!(
+                "Scheduled trash purge: purged {} photos and {} albums, {} failed",
+                summary.photos, summary.albums, summary.failed
+            ),
+            Ok(Err(e)) => println!("Scheduled trash purge failed: {e}"),
+            Err(e) => println!("Scheduled trash purge panicked: {e}"),
+        }
+    }
+}
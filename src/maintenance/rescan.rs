@@ -0,0 +1,155 @@
+use crate::_utils::path_prefix::PathPrefix;
+use crate::db::operations::album::find_or_create_album_path;
+use crate::db::operations::join_album_photo::add_photo_to_album;
+use crate::db::operations::paths::get_photo_path;
+use crate::db::operations::photo::{check_hash, get_all_photos};
+use crate::db::pool::establish_connection;
+use crate::ingest::register_photo::register_photo;
+use crate::ingest::trait_suisai_image_path::SuisaiImagePath;
+use crate::maintenance::fsck::walk_files;
+use diesel::MysqlConnection;
+use infer::MatcherType::Image;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::env;
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+
+/// Totals of a rescan
+///
+/// # Fields
+/// * `adopted`: Images registered as new photos (or that would be, in dry mode)
+/// * `duplicates`: Untracked images skipped because their hash is already in the library
+/// * `failed`: Images that could not be registered
+#[derive(Serialize, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RescanSummary {
+    pub adopted: usize,
+    pub duplicates: usize,
+    pub failed: usize,
+}
+
+/// Finds images under $STORAGE_ROOT that aren't tracked in the database (e.g. copied in over SMB)
+/// and registers them as photos of the album matching the directory they sit in.
+///
+/// Albums are created for directories that don't have one yet. Images already in the library
+/// under a different path (same hash) are skipped, as are files that share a tracked photo's base
+/// name in its directory (e.g. the JPEG of a tracked RAW). Hidden directories and $THUMBNAIL_ROOT are ignored.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `dry` - If `true`, only report what would be adopted
+///
+/// # Returns
+/// Totals of the rescan, or an error if the database or $STORAGE_ROOT could not be read
+pub fn rescan_library(conn: &mut MysqlConnection, dry: bool) -> anyhow::Result<RescanSummary> {
+    let storage_root = PathBuf::from(env::var("STORAGE_ROOT").unwrap());
+    let thumbnail_root = PathBuf::from(env::var("THUMBNAIL_ROOT").unwrap());
+    let mut summary = RescanSummary::default();
+
+    // Paths of all tracked photos, so they don't need to be hashed again
+    let mut tracked: HashSet<PathBuf> = HashSet::new();
+    for photo in get_all_photos(conn)? {
+        if let Ok(path) = get_photo_path(conn, photo.id) {
+            tracked.insert(path);
+        }
+    }
+
+    // Files sharing a tracked photo's base name in the same directory (sidecars, exports, ...) belong to it, as in `fsck`
+    let photo_prefixes: HashSet<(PathBuf, OsString)> = tracked
+        .iter()
+        .filter_map(|path| Some((path.parent()?.to_path_buf(), path.file_prefix()?.to_os_string())))
+        .collect();
+
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk_files(&storage_root, &thumbnail_root, &mut files)?;
+
+    for full_path in files {
+        let Ok(path) = full_path.strip_prefix(&storage_root).map(Path::to_path_buf) else { continue };
+        if tracked.contains(&path) {
+            continue;
+        }
+        let parent = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let prefix = path.file_prefix().unwrap_or_default().to_os_string();
+        if photo_prefixes.contains(&(parent, prefix)) {
+            continue;
+        }
+        if Some(Image) != infer::get_from_path(&full_path).ok().flatten().map(|t| t.matcher_type()) {
+            continue;
+        }
+
+        let hash = full_path.get_hash();
+        if let Some(photo) = check_hash(conn, &hash)? {
+            println!("{} is a duplicate of photo {} ({}), skipping", path.display(), photo.id, photo.file_name);
+            summary.duplicates += 1;
+            continue;
+        }
+
+        if dry {
+            println!("Would adopt {}", path.display());
+            summary.adopted += 1;
+            continue;
+        }
+
+        match adopt_file(conn, &path) {
+            Ok(photo_id) => {
+                println!("Adopted {} as photo {photo_id}", path.display());
+                summary.adopted += 1;
+            }
+            Err(e) => {
+                println!("Error adopting {}: {e}", path.display());
+                summary.failed += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Registers an image already under $STORAGE_ROOT as a photo, and links it to the album matching
+/// its directory, creating the album (and its ancestors) if necessary. Images in `unfiled` stay unfiled.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `path` - Path to the image, relative to $STORAGE_ROOT
+///
+/// # Returns
+/// Ok(id) with the ID of the new photo, or an error if the image sits directly in $STORAGE_ROOT or registering failed
+pub fn adopt_file(conn: &mut MysqlConnection, path: &Path) -> anyhow::Result<i64> {
+    let storage_root = PathBuf::from(env::var("STORAGE_ROOT").unwrap());
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    let segments: Vec<String> = directory
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(segment) => Some(segment.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect();
+    if segments.is_empty() {
+        return Err(anyhow::anyhow!("Images directly in $STORAGE_ROOT can't be adopted, move them into an album directory or `unfiled`"));
+    }
+
+    let album_id = match segments.as_slice() {
+        [unfiled] if unfiled == "unfiled" => None,
+        _ => Some(find_or_create_album_path(conn, &segments)?),
+    };
+
+    let photo_id = register_photo(conn, &path.prefix_within(&storage_root)?)?;
+    if let Some(album_id) = album_id {
+        add_photo_to_album(conn, album_id, &[photo_id])?;
+    }
+
+    Ok(photo_id)
+}
+
+/// Adopts untracked images under $STORAGE_ROOT into the library
+pub fn rescan(dry: bool) {
+    let mut conn = establish_connection();
+    if dry {
+        println!("Running in dry mode");
+    }
+
+    let summary = rescan_library(&mut conn, dry).expect("Failed to rescan library");
+    println!("Finished: {} adopted, {} duplicates skipped, {} failed", summary.adopted, summary.duplicates, summary.failed);
+}
@@ -0,0 +1,209 @@
+use crate::_utils::actor::Actor;
+use crate::audit::record_operation;
+use crate::db::operations::metadata::get_photo_metadata;
+use crate::db::operations::stack::{create_stack, get_unstacked_photos};
+use crate::db::pool::establish_connection;
+use crate::ingest::trait_suisai_image_path::UNKNOWN_CAMERA;
+use crate::models::audit::AuditAction;
+use crate::models::photo::Photo;
+use crate::models::stack::{pick_cover, StackKind};
+use chrono::TimeDelta;
+use diesel::result::Error;
+use diesel::{Connection, MysqlConnection};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Largest gap between two frames of a burst, in seconds, when no `--gap` is given
+pub const DEFAULT_STACK_GAP_SECONDS: i64 = 1;
+
+/// Largest gap, in seconds, between two bracket frames the camera numbered consecutively.
+/// Brackets of long exposures take much longer than a burst.
+const MAX_BRACKET_GAP_SECONDS: i64 = 60;
+
+/// Tags numbering the frames of a burst or bracket, in any metadata group (they are maker notes)
+const SEQUENCE_TAGS: [&str; 2] = ["SequenceNumber", "BracketShotNumber"];
+
+/// Tags that are set when a photo was shot as part of an exposure bracket, in any metadata group
+const BRACKET_TAGS: [&str; 5] = ["BracketMode", "AEBBracketValue", "AutoBracketing", "BracketSequence", "ExposureBracketValue"];
+
+/// Values of `BRACKET_TAGS` (lowercased) meaning that bracketing was off
+const BRACKET_OFF_VALUES: [&str; 5] = ["", "off", "0", "none", "n/a"];
+
+/// What the camera recorded about a photo being part of a burst or bracket
+#[derive(Clone, Copy, Default, Debug)]
+struct StackHints {
+    sequence_number: Option<i64>,
+    bracketed: bool,
+}
+
+/// Reads the `StackHints` from a stored metadata blob (see `PhotoMetadata`)
+fn stack_hints(metadata: &str) -> StackHints {
+    let mut hints = StackHints::default();
+    let Ok(Value::Object(tags)) = serde_json::from_str::<Value>(metadata) else { return hints };
+
+    for (key, value) in &tags {
+        // Keys are `Group:Tag`, and the group depends on the camera maker
+        let tag = key.rsplit(':').next().unwrap_or(key);
+        let text = value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string());
+
+        if SEQUENCE_TAGS.contains(&tag) && hints.sequence_number.is_none() {
+            hints.sequence_number = text.trim().parse::<i64>().ok();
+        }
+        if BRACKET_TAGS.contains(&tag) && !BRACKET_OFF_VALUES.contains(&text.trim().to_lowercase().as_str()) {
+            hints.bracketed = true;
+        }
+    }
+    hints
+}
+
+/// Gets the `StackHints` of a photo, reading its metadata only once
+fn load_hints(conn: &mut MysqlConnection, cache: &mut HashMap<i64, StackHints>, photo_id: i64) -> Result<StackHints, Error> {
+    if let Some(hints) = cache.get(&photo_id) {
+        return Ok(*hints);
+    }
+
+    let hints = match get_photo_metadata(conn, photo_id) {
+        Ok(metadata) => stack_hints(&metadata.metadata),
+        Err(Error::NotFound) => StackHints::default(),
+        Err(e) => return Err(e),
+    };
+    cache.insert(photo_id, hints);
+    Ok(hints)
+}
+
+/// Whether `photo` continues the burst or bracket that `previous` (the frame shot before it) is part of
+fn continues_stack(conn: &mut MysqlConnection, cache: &mut HashMap<i64, StackHints>, previous: &Photo, photo: &Photo, max_gap: TimeDelta) -> Result<bool, Error> {
+    if photo.camera_model != previous.camera_model || photo.camera_model == UNKNOWN_CAMERA {
+        return Ok(false);
+    }
+    // Photos without a capture date all sit at the Unix epoch
+    if previous.photo_date.and_utc().timestamp() == 0 {
+        return Ok(false);
+    }
+    let gap = photo.photo_date - previous.photo_date;
+    if gap > max_gap.max(TimeDelta::seconds(MAX_BRACKET_GAP_SECONDS)) {
+        return Ok(false);
+    }
+
+    let previous_hints = load_hints(conn, cache, previous.id)?;
+    let hints = load_hints(conn, cache, photo.id)?;
+    Ok(match (previous_hints.sequence_number, hints.sequence_number) {
+        // The camera started a new sequence, however close in time
+        (Some(previous_number), Some(number)) if number <= previous_number => false,
+        (Some(previous_number), Some(number)) if number == previous_number + 1 && previous_hints.bracketed && hints.bracketed => true,
+        _ => gap <= max_gap,
+    })
+}
+
+/// Groups the photos that are not stacked yet into bursts and brackets.
+///
+/// Photos from the same camera model end up in one stack if they were shot at most `max_gap` apart,
+/// one after the other. Sequence numbers from the maker notes split stacks where the camera started a
+/// new sequence, and keep consecutively numbered bracket frames together even if they are further apart.
+/// A stack is a bracket if any of its frames was shot with bracketing on, and a burst otherwise.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `max_gap` - Largest gap between two frames of a burst
+///
+/// # Returns
+/// The planned stacks, each with at least two photos in capture order
+pub fn find_stacks(conn: &mut MysqlConnection, max_gap: TimeDelta) -> Result<Vec<(StackKind, Vec<Photo>)>, Error> {
+    let photos = get_unstacked_photos(conn)?;
+    group_stacks(conn, photos, max_gap)
+}
+
+/// Same as `find_stacks`, but only considers the given photos (e.g. the ones ingested in this run),
+/// so stacks dissolved elsewhere in the library are not re-created
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `photo_ids` - IDs of the photos to group; stacked ones are skipped
+/// * `max_gap` - Largest gap between two frames of a burst
+///
+/// # Returns
+/// The planned stacks, each with at least two photos in capture order
+pub fn find_stacks_among(conn: &mut MysqlConnection, photo_ids: &[i64], max_gap: TimeDelta) -> Result<Vec<(StackKind, Vec<Photo>)>, Error> {
+    let photos = get_unstacked_photos(conn)?.into_iter().filter(|photo| photo_ids.contains(&photo.id)).collect();
+    group_stacks(conn, photos, max_gap)
+}
+
+/// Splits unstacked photos, ordered as by `get_unstacked_photos`, into stacks (see `find_stacks`)
+fn group_stacks(conn: &mut MysqlConnection, photos: Vec<Photo>, max_gap: TimeDelta) -> Result<Vec<(StackKind, Vec<Photo>)>, Error> {
+    let mut cache: HashMap<i64, StackHints> = HashMap::new();
+    let mut stacks = Vec::new();
+    let mut current: Vec<Photo> = Vec::new();
+
+    let mut finish = |cache: &HashMap<i64, StackHints>, frames: Vec<Photo>| {
+        if frames.len() < 2 {
+            return;
+        }
+        // Every frame of a stack had its hints loaded when it was compared to its neighbour
+        let bracketed = frames.iter().any(|photo| cache.get(&photo.id).is_some_and(|hints| hints.bracketed));
+        stacks.push((if bracketed { StackKind::Bracket } else { StackKind::Burst }, frames));
+    };
+
+    for photo in photos {
+        let continues = match current.last() {
+            Some(previous) => continues_stack(conn, &mut cache, previous, &photo, max_gap)?,
+            None => false,
+        };
+        if !continues {
+            finish(&cache, std::mem::take(&mut current));
+        }
+        current.push(photo);
+    }
+    finish(&cache, current);
+
+    Ok(stacks)
+}
+
+/// Creates the planned stacks, each in its own transaction, and records them in the audit log
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `actor` - Who is stacking the photos
+/// * `stacks` - Stacks planned by `find_stacks` or `find_stacks_among`
+/// * `dry` - Only print the stacks that would be created
+///
+/// # Returns
+/// The number of stacks that could not be created
+pub fn create_stacks(conn: &mut MysqlConnection, actor: &Actor, stacks: Vec<(StackKind, Vec<Photo>)>, dry: bool) -> usize {
+    let mut failed = 0;
+    for (kind, photos) in stacks {
+        let photo_ids: Vec<i64> = photos.iter().map(|photo| photo.id).collect();
+        let cover_id = pick_cover(kind.as_str(), &photos);
+        if dry {
+            println!("Would stack photos {photo_ids:?} as a {} with cover {cover_id:?}", kind.as_str());
+            continue;
+        }
+
+        let result = conn.transaction(|conn| create_stack(conn, kind, &photo_ids, cover_id));
+        record_operation(conn, actor, AuditAction::StackCreate, &photo_ids, &[], None, &result);
+        match result {
+            Ok(stack_id) => println!("Created {} {stack_id} from photos {photo_ids:?}", kind.as_str()),
+            Err(e) => {
+                println!("Error stacking photos {photo_ids:?}: {e}");
+                failed += 1;
+            }
+        }
+    }
+    failed
+}
+
+/// Runs the stacking pass: groups bursts and brackets among the photos that are not stacked yet
+/// and picks a cover for each. Existing stacks are left alone.
+///
+/// # Arguments
+/// * `gap_seconds` - Largest gap between two frames of a burst, in seconds
+/// * `dry` - Only print the stacks that would be created
+pub fn stack(gap_seconds: i64, dry: bool) {
+    let mut conn = establish_connection();
+    let actor = Actor::cli();
+
+    let stacks = find_stacks(&mut conn, TimeDelta::seconds(gap_seconds)).expect("Failed to find bursts and brackets");
+    println!("Found {} bursts and brackets", stacks.len());
+
+    let failed = create_stacks(&mut conn, &actor, stacks, dry);
+    println!("Finished ({failed} failed)");
+}
@@ -0,0 +1,147 @@
+use crate::_utils::path_prefix::PathPrefix;
+use crate::db::operations::paths::get_photo_path;
+use crate::db::operations::photo::get_photo;
+use crate::db::operations::verification::{get_photos_by_verification_age, set_photo_verification};
+use crate::db::pool::{establish_connection, Pool};
+use crate::ingest::trait_suisai_image_path::SuisaiImagePath;
+use crate::models::verification::{PhotoVerification, VerificationStatus};
+use chrono::{Local, TimeDelta};
+use diesel::MysqlConnection;
+use rocket::tokio;
+use std::env;
+use std::path::PathBuf;
+
+/// Hour of the day (server local time) at which the scheduled verification runs, unless `$VERIFY_HOUR` is set
+const DEFAULT_VERIFY_HOUR: u32 = 3;
+
+const BYTES_PER_GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Totals of a verification run
+#[derive(Default, Debug)]
+pub struct VerifySummary {
+    pub checked: usize,
+    pub bytes: u64,
+    pub mismatched: usize,
+    pub missing: usize,
+}
+
+/// Re-hashes originals, least recently verified first, and records the outcome per photo.
+///
+/// Stops once `byte_budget` bytes have been read, so that a nightly run covers the library bit by bit.
+/// At least one photo is checked per run, even if it is larger than the budget.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `byte_budget` - Number of bytes to read before stopping
+///
+/// # Returns
+/// Totals of the run, or an error if the database could not be queried
+pub fn verify_photos(conn: &mut MysqlConnection, byte_budget: u64) -> anyhow::Result<VerifySummary> {
+    let storage_root = PathBuf::from(env::var("STORAGE_ROOT").unwrap());
+    let mut summary = VerifySummary::default();
+
+    for photo_id in get_photos_by_verification_age(conn)? {
+        if summary.checked > 0 && summary.bytes >= byte_budget {
+            break;
+        }
+
+        let Some(photo) = get_photo(conn, &[photo_id])?.pop() else { continue };
+        let photo_path = match get_photo_path(conn, photo_id)?.prefix_within(&storage_root) {
+            Ok(path) => path,
+            Err(e) => {
+                println!("Skipping photo {photo_id}: {e}");
+                continue;
+            }
+        };
+
+        let status = match photo_path.metadata() {
+            Ok(metadata) if metadata.is_file() => {
+                summary.bytes += metadata.len();
+                if photo_path.get_hash() == photo.hash { VerificationStatus::Ok } else { VerificationStatus::Mismatch }
+            }
+            _ => VerificationStatus::Missing,
+        };
+
+        match status {
+            VerificationStatus::Ok => {}
+            VerificationStatus::Mismatch => {
+                summary.mismatched += 1;
+                println!("Hash mismatch: photo {photo_id} at {}", photo_path.display());
+            }
+            VerificationStatus::Missing => {
+                summary.missing += 1;
+                println!("Missing: photo {photo_id} not found at {}", photo_path.display());
+            }
+        }
+
+        set_photo_verification(conn, &PhotoVerification {
+            id: photo_id,
+            verified_at: Local::now().naive_local(),
+            status: status.as_str().to_string(),
+        })?;
+        summary.checked += 1;
+    }
+
+    Ok(summary)
+}
+
+/// Verifies up to `gb` gigabytes of originals against their stored hashes
+pub fn verify(gb: f64) {
+    let mut conn = establish_connection();
+
+    let summary = verify_photos(&mut conn, (gb * BYTES_PER_GB) as u64).expect("Failed to verify photos");
+    println!(
+        "Finished: verified {} photos ({:.2} GB), {} mismatched, {} missing",
+        summary.checked, summary.bytes as f64 / BYTES_PER_GB, summary.mismatched, summary.missing
+    );
+}
+
+/// The nightly verification budget in bytes, from `$VERIFY_GB_PER_NIGHT`.
+/// Returns `None` (scheduled verification disabled) if it is unset, invalid or zero.
+pub fn scheduled_verification_budget() -> Option<u64> {
+    env::var("VERIFY_GB_PER_NIGHT")
+        .ok()
+        .and_then(|gb| gb.trim().parse::<f64>().ok())
+        .filter(|gb| *gb > 0.0)
+        .map(|gb| (gb * BYTES_PER_GB) as u64)
+}
+
+/// Runs `verify_photos` once a day at `$VERIFY_HOUR` (0-23, server local time, defaults to 3),
+/// reading `byte_budget` bytes per run. Never returns; spawn it as a background task.
+///
+/// # Arguments
+/// * `pool` - Connection pool of the web server
+/// * `byte_budget` - Number of bytes of originals to re-hash per run
+pub async fn run_scheduled_verification(pool: Pool, byte_budget: u64) {
+    let verify_hour = env::var("VERIFY_HOUR")
+        .ok()
+        .and_then(|hour| hour.trim().parse::<u32>().ok())
+        .filter(|hour| *hour < 24)
+        .unwrap_or(DEFAULT_VERIFY_HOUR);
+
+    loop {
+        // Sleep until the next occurrence of `verify_hour`
+        let now = Local::now().naive_local();
+        let mut next_run = now.date().and_hms_opt(verify_hour, 0, 0).unwrap();
+        if next_run <= now {
+            next_run += TimeDelta::days(1);
+        }
+        tokio::time::sleep((next_run - now).to_std().unwrap_or_default()).await;
+
+        // Hashing is blocking I/O, so keep it off the async workers
+        let pool = pool.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get()?;
+            verify_photos(&mut conn, byte_budget)
+        }).await;
+
+        match result {
+            Ok(Ok(summary)) => println!(
+                "Scheduled verification: verified {} photos, {} mismatched, {} missing",
+                summary.checked, summary.mismatched, summary.missing
+            ),
+            Ok(Err(e)) => println!("Scheduled verification failed: {e}"),
+            Err(e) => println!("Scheduled verification panicked: {e}"),
+        }
+    }
+}
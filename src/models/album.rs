@@ -1,6 +1,9 @@
 use crate::db::schema::albums;
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 use rocket::serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Deref;
 
 
 /// Represents an album with a unique identifier, name, and associated photos.
@@ -9,6 +12,11 @@ use rocket::serde::{Deserialize, Serialize};
 /// * `id`: Album's unique ID, serialized as `albumId` in JSON
 /// * `directory`: Directory that the album corresponds to on disk
 /// * `album_name`: Album name
+/// * `deleted_at`: When the album was moved to the trash; omitted from JSON if it isn't trashed
+/// * `description`: Free-form description of the album
+/// * `cover_id`: Photo chosen as the album's cover, or `None` to use the earliest photo
+/// * `created_at` / `updated_at`: When the album was created and when its name or metadata last changed
+/// * `sort_position`: Position among its sibling albums, or `None` to sort it by name after the others
 ///
 /// # Example
 /// ```
@@ -16,14 +24,60 @@ use rocket::serde::{Deserialize, Serialize};
 ///     id: 1,
 ///     directory: "/home/user/Pictures/Vacation".into(),
 ///     album_name: "Vacation".into(),
+///     deleted_at: None,
+///     description: Some("Two weeks in Japan".into()),
+///     cover_id: None,
+///     created_at: Local::now().naive_local(),
+///     updated_at: Local::now().naive_local(),
+///     sort_position: Some(0),
 /// };
 /// ```
-#[derive(Queryable, Selectable, AsChangeset, Serialize, Deserialize, Debug)]
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Album {
     #[serde(rename = "albumId")]
     pub id: i32,
-    pub album_name: String
+    pub album_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<NaiveDateTime>,
+    pub description: Option<String>,
+    pub cover_id: Option<i64>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub sort_position: Option<i32>,
+}
+
+/// An `Album` along with what is derived from its photos, as returned by the album listings
+///
+/// # Fields
+/// * `album`: The album itself, flattened into the same JSON object
+/// * `effective_cover_id`: The chosen cover if it is still a (non-trashed) photo of the album, otherwise
+///   the album's earliest photo; `None` for an album without photos
+/// * `first_photo_date` / `last_photo_date`: Capture dates of the album's earliest and latest photos
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumDetails {
+    #[serde(flatten)]
+    pub album: Album,
+    pub effective_cover_id: Option<i64>,
+    pub first_photo_date: Option<NaiveDateTime>,
+    pub last_photo_date: Option<NaiveDateTime>,
+}
+
+/// Changes to the metadata of an album. Fields left `None` are kept as they are; `Some(None)` clears them.
+///
+/// # Fields
+/// * `description`: New description
+/// * `cover_id`: New cover photo, `Some(None)` to go back to the earliest photo
+/// * `sort_position`: New position among the sibling albums
+/// * `updated_at`: When the change is made
+#[derive(AsChangeset, Debug)]
+#[diesel(table_name = albums)]
+pub struct AlbumChanges {
+    pub description: Option<Option<String>>,
+    pub cover_id: Option<Option<i64>>,
+    pub sort_position: Option<Option<i32>>,
+    pub updated_at: NaiveDateTime,
 }
 
 /// A variant of `Album` without photos or ID, used for creating new album instances.
@@ -34,11 +88,14 @@ pub struct Album {
 ///
 /// # Fields
 /// * `album_name`: The name for the new album
+/// * `created_at` / `updated_at`: When the album is created
 ///
 /// # Example
 /// ```
 /// let new_album = NewAlbum {
-///     album_name: "Vacation Photos".into()
+///     album_name: "Vacation Photos".into(),
+///     created_at: Local::now().naive_local(),
+///     updated_at: Local::now().naive_local(),
 /// };
 /// ```
 #[derive(Insertable, Deserialize, Debug)]
@@ -46,4 +103,91 @@ pub struct Album {
 #[diesel(table_name = albums)]
 pub struct NewAlbum {
     pub album_name: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Longest album name, in bytes, that fits both the `albums` table and a directory name
+pub const MAX_ALBUM_NAME_LENGTH: usize = 255;
+
+/// Names that can't be used for an album, compared case-insensitively: `unfiled` is the directory of photos
+/// without an album, the others are device names Windows clients can't open over SMB.
+const RESERVED_ALBUM_NAMES: [&str; 23] = [
+    "unfiled", "con", "prn", "aux", "nul",
+    "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8", "com9",
+    "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// An album name that is safe to use as a directory name directly under its parent album.
+///
+/// Deserializing or converting a `String` validates it, so `input.get_value::<AlbumName>("album_name")`
+/// rejects invalid names with a message saying why. Leading and trailing whitespace is trimmed.
+/// A name is rejected if it:
+/// * is empty, or longer than `MAX_ALBUM_NAME_LENGTH` bytes
+/// * contains a path separator (`/` or `\`) or a control character
+/// * is `.` or `..`, or starts with a `.` (hidden directories such as `.trash` are skipped by fsck)
+/// * is reserved (`unfiled`, or a Windows device name such as `CON` or `NUL.txt`)
+#[derive(Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(try_from = "String")]
+pub struct AlbumName(String);
+
+impl TryFrom<String> for AlbumName {
+    type Error = anyhow::Error;
+
+    fn try_from(album_name: String) -> Result<Self, Self::Error> {
+        let album_name = album_name.trim();
+
+        if album_name.is_empty() {
+            return Err(anyhow::anyhow!("Album names must not be empty"));
+        }
+        if album_name.len() > MAX_ALBUM_NAME_LENGTH {
+            return Err(anyhow::anyhow!("Album names must be at most {} bytes long", MAX_ALBUM_NAME_LENGTH));
+        }
+        if album_name.contains(['/', '\\']) {
+            return Err(anyhow::anyhow!("Album names must not contain path separators (/ or \\)"));
+        }
+        if album_name.chars().any(char::is_control) {
+            return Err(anyhow::anyhow!("Album names must not contain control characters"));
+        }
+        if album_name == "." || album_name == ".." {
+            return Err(anyhow::anyhow!("{} is not a valid album name", album_name));
+        }
+        if album_name.starts_with('.') {
+            return Err(anyhow::anyhow!("Album names must not start with a dot"));
+        }
+        let stem = album_name.split('.').next().unwrap_or_default().trim_end();
+        if RESERVED_ALBUM_NAMES.iter().any(|reserved| stem.eq_ignore_ascii_case(reserved)) {
+            return Err(anyhow::anyhow!("{} is a reserved name", album_name));
+        }
+
+        Ok(AlbumName(album_name.to_string()))
+    }
+}
+
+impl TryFrom<&str> for AlbumName {
+    type Error = anyhow::Error;
+
+    fn try_from(album_name: &str) -> Result<Self, Self::Error> {
+        AlbumName::try_from(album_name.to_string())
+    }
+}
+
+impl Deref for AlbumName {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<std::path::Path> for AlbumName {
+    fn as_ref(&self) -> &std::path::Path {
+        std::path::Path::new(&self.0)
+    }
+}
+
+impl fmt::Display for AlbumName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
 }
@@ -0,0 +1,213 @@
+use crate::db::schema::audit_log;
+use crate::models::filter::QueryDateTime;
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+use rocket::{FromForm, FromFormField};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// A kind of mutating operation recorded in the audit log
+///
+/// Serialized in `snake_case` (e.g. `photo_reassign`), which is also how it is stored in the database.
+#[derive(Serialize, FromFormField, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    #[field(value = "album_new")]
+    AlbumNew,
+    #[field(value = "album_rename")]
+    AlbumRename,
+    #[field(value = "album_update")]
+    AlbumUpdate,
+    #[field(value = "album_order")]
+    AlbumOrder,
+    #[field(value = "album_merge")]
+    AlbumMerge,
+    #[field(value = "album_delete")]
+    AlbumDelete,
+    #[field(value = "album_unfile")]
+    AlbumUnfile,
+    #[field(value = "album_reassign")]
+    AlbumReassign,
+    #[field(value = "photo_delete")]
+    PhotoDelete,
+    #[field(value = "photo_unfile")]
+    PhotoUnfile,
+    #[field(value = "photo_reassign")]
+    PhotoReassign,
+    #[field(value = "photo_copy")]
+    PhotoCopy,
+    #[field(value = "trash_restore")]
+    TrashRestore,
+    #[field(value = "duplicate_merge")]
+    DuplicateMerge,
+    #[field(value = "stack_create")]
+    StackCreate,
+    #[field(value = "stack_cover")]
+    StackCover,
+    #[field(value = "stack_delete")]
+    StackDelete,
+    #[field(value = "ingest")]
+    Ingest,
+    #[field(value = "undo")]
+    Undo,
+}
+
+impl AuditAction {
+    /// The name used for this action in the database and in JSON
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::AlbumNew => "album_new",
+            AuditAction::AlbumRename => "album_rename",
+            AuditAction::AlbumUpdate => "album_update",
+            AuditAction::AlbumOrder => "album_order",
+            AuditAction::AlbumMerge => "album_merge",
+            AuditAction::AlbumDelete => "album_delete",
+            AuditAction::AlbumUnfile => "album_unfile",
+            AuditAction::AlbumReassign => "album_reassign",
+            AuditAction::PhotoDelete => "photo_delete",
+            AuditAction::PhotoUnfile => "photo_unfile",
+            AuditAction::PhotoReassign => "photo_reassign",
+            AuditAction::PhotoCopy => "photo_copy",
+            AuditAction::TrashRestore => "trash_restore",
+            AuditAction::DuplicateMerge => "duplicate_merge",
+            AuditAction::StackCreate => "stack_create",
+            AuditAction::StackCover => "stack_cover",
+            AuditAction::StackDelete => "stack_delete",
+            AuditAction::Ingest => "ingest",
+            AuditAction::Undo => "undo",
+        }
+    }
+}
+
+/// Outcome of an operation recorded in the audit log
+#[derive(Serialize, FromFormField, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditResult {
+    #[field(value = "success")]
+    Success,
+    #[field(value = "failure")]
+    Failure,
+}
+
+impl AuditResult {
+    /// The name used for this result in the database and in JSON
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditResult::Success => "success",
+            AuditResult::Failure => "failure",
+        }
+    }
+}
+
+/// Where a photo was at the time of a snapshot
+///
+/// # Fields
+/// * `file_name`: File name of the photo
+/// * `path`: Path to the photo, relative to $STORAGE_ROOT
+/// * `album_ids`: Albums the photo was linked to
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PhotoLocation {
+    pub file_name: String,
+    pub path: PathBuf,
+    pub album_ids: Vec<i32>,
+}
+
+/// Where an album was at the time of a snapshot
+///
+/// # Fields
+/// * `album_name`: Name of the album
+/// * `path`: Path to the album directory, relative to $STORAGE_ROOT
+/// * `parent_id`: Parent album, or `None` for a root album
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumLocation {
+    pub album_name: String,
+    pub path: PathBuf,
+    pub parent_id: Option<i32>,
+}
+
+/// The names, paths and album links of the photos and albums affected by an operation, taken before
+/// and after it ran. Stored as JSON in `before_state` / `after_state`.
+///
+/// Photos and albums that don't exist (anymore) are left out.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct AuditSnapshot {
+    pub photos: BTreeMap<i64, PhotoLocation>,
+    pub albums: BTreeMap<i32, AlbumLocation>,
+}
+
+/// An entry of the audit log
+///
+/// # Fields
+/// * `id`: ID of the entry, which also identifies the operation
+/// * `created_at`: When the operation finished (local time of the server)
+/// * `actor`: Who performed the operation (see `Actor`)
+/// * `action`: An `AuditAction`, as stored in the database
+/// * `photo_ids` / `album_ids`: JSON arrays of the affected IDs
+/// * `before_state` / `after_state`: JSON `AuditSnapshot`s, `after_state` is `None` for failed operations
+/// * `result`: An `AuditResult`, as stored in the database
+/// * `error`: Error message of a failed operation
+/// * `undone_by`: ID of the `undo` entry that reversed this operation, if any
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = audit_log)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub created_at: NaiveDateTime,
+    pub actor: String,
+    pub action: String,
+    pub photo_ids: String,
+    pub album_ids: String,
+    pub before_state: Option<String>,
+    pub after_state: Option<String>,
+    pub result: String,
+    pub error: Option<String>,
+    pub undone_by: Option<i64>,
+}
+
+/// Data required to append an entry to the audit log. See `AuditEntry` for the fields.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = audit_log)]
+pub struct NewAuditEntry {
+    pub created_at: NaiveDateTime,
+    pub actor: String,
+    pub action: String,
+    pub photo_ids: String,
+    pub album_ids: String,
+    pub before_state: Option<String>,
+    pub after_state: Option<String>,
+    pub result: String,
+    pub error: Option<String>,
+}
+
+/// Filters and pagination for the audit log, parsed from the query string.
+///
+/// Every filter left out of the query string matches all entries.
+///
+/// # Fields
+/// * `actor`: Only entries by this actor
+/// * `action`: Only entries of this `AuditAction` (e.g. `photo_reassign`)
+/// * `result`: Only `success` or `failure` entries
+/// * `photo_id` / `album_id`: Only entries affecting this photo / album, as `photoId` / `albumId`
+/// * `since` / `until`: Only entries created in this time range, as `YYYY-MM-DDTHH:MM:SS`
+/// * `page`: Page to return, starting at 1 (defaults to 1)
+/// * `per_page`: Entries per page, as `perPage` (defaults to 50, at most 500)
+///
+/// # Example
+/// `GET /audit?actor=alice&action=photo_delete&page=2`
+#[derive(FromForm, Default, Debug)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub action: Option<AuditAction>,
+    pub result: Option<AuditResult>,
+    #[field(name = "photoId")]
+    pub photo_id: Option<i64>,
+    #[field(name = "albumId")]
+    pub album_id: Option<i32>,
+    pub since: Option<QueryDateTime>,
+    pub until: Option<QueryDateTime>,
+    pub page: Option<i64>,
+    #[field(name = "perPage")]
+    pub per_page: Option<i64>,
+}
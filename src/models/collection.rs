@@ -0,0 +1,45 @@
+use crate::db::schema::collections;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rocket::serde::Serialize;
+
+/// A grouping of photos that exists only in the database, e.g. a client selection or a portfolio.
+///
+/// Unlike albums, collections don't map to directories: a photo can be in any number of collections,
+/// wherever its file is. The photos of a collection have a manual order.
+///
+/// # Fields
+/// * `id`: Collection's unique ID, serialized as `collectionId` in JSON
+/// * `collection_name`: Collection name, unique among collections
+/// * `created_at`: When the collection was created (local time of the server)
+#[derive(Queryable, Selectable, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Collection {
+    #[serde(rename = "collectionId")]
+    pub id: i32,
+    pub collection_name: String,
+    pub created_at: NaiveDateTime,
+}
+
+/// A variant of `Collection` without an ID, used for creating new collections.
+///
+/// # Fields
+/// * `collection_name`: Name of the new collection
+/// * `created_at`: When the collection was created
+#[derive(Insertable, Debug)]
+#[diesel(table_name = collections)]
+pub struct NewCollection {
+    pub collection_name: String,
+    pub created_at: NaiveDateTime,
+}
+
+/// Checks that a collection name is non-empty and fits in the database
+pub fn validate_collection_name(collection_name: &str) -> anyhow::Result<()> {
+    if collection_name.trim().is_empty() {
+        return Err(anyhow::anyhow!("Collection names must not be empty"));
+    }
+    if collection_name.len() > 255 {
+        return Err(anyhow::anyhow!("Collection names must be at most 255 bytes long"));
+    }
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use chrono::NaiveDateTime;
+use rocket::form::{self, FromFormField, ValueField};
+use rocket::FromForm;
+
+/// Number of photos per page when only `page` is given
+const DEFAULT_PER_PAGE: i64 = 100;
+
+/// Largest accepted `perPage`
+const MAX_PER_PAGE: i64 = 1000;
+
+/// Property photo listings can be sorted by
+#[derive(FromFormField, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PhotoSort {
+    /// Capture time (`photo_date`)
+    #[field(value = "date")]
+    Date,
+    /// File name
+    #[field(value = "name")]
+    Name,
+    /// Star rating
+    #[field(value = "rating")]
+    Rating,
+    /// Size on disk
+    #[field(value = "size")]
+    Size,
+    /// Photo ID, i.e. the order photos were added in
+    #[field(value = "id")]
+    Id,
+}
+
+/// Direction of a sort
+#[derive(FromFormField, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SortOrder {
+    #[field(value = "asc")]
+    Asc,
+    #[field(value = "desc")]
+    Desc,
+}
+
+/// Optional filters applied to photo listings, parsed from the query string.
+///
+/// Every field left out of the query string matches all photos.
+///
+/// # Fields
+/// * `min_rating`: Only photos rated at least this many stars, as `minRating`
+/// * `color_label`: Only photos with this color label, as `colorLabel`
+/// * `pick_flag`: Only photos with this pick flag (-1 rejected, 0 unflagged, 1 picked), as `pickFlag`
+/// * `collapse_stacks`: Show only the cover of each burst or bracket, as `collapseStacks` (defaults to false)
+/// * `sort`: A `PhotoSort` (`date`, `name`, `rating`, `size` or `id`). Defaults to the order of the listing
+///   (manual order for albums and collections, by ID for other listings), with ties broken by ID.
+/// * `order`: `asc` or `desc` (defaults to `asc`)
+/// * `page`: Page to return, starting at 1. Without `page` and `perPage`, all photos are returned.
+/// * `per_page`: Photos per page, as `perPage` (defaults to 100, at most 1000)
+///
+/// # Example
+/// `GET /album/3/photos?minRating=4&pickFlag=1&collapseStacks=true&sort=date&order=desc&page=2`
+#[derive(FromForm, Default, Debug)]
+pub struct PhotoFilter {
+    #[field(name = "minRating")]
+    pub min_rating: Option<i8>,
+    #[field(name = "colorLabel")]
+    pub color_label: Option<String>,
+    #[field(name = "pickFlag")]
+    pub pick_flag: Option<i8>,
+    #[field(name = "collapseStacks")]
+    pub collapse_stacks: Option<bool>,
+    pub sort: Option<PhotoSort>,
+    pub order: Option<SortOrder>,
+    pub page: Option<i64>,
+    #[field(name = "perPage")]
+    pub per_page: Option<i64>,
+}
+
+impl PhotoFilter {
+    /// Offset and limit of the requested page, or `None` if no page was requested
+    pub fn page_bounds(&self) -> Option<(i64, i64)> {
+        if self.page.is_none() && self.per_page.is_none() {
+            return None;
+        }
+        let page = self.page.unwrap_or(1).max(1);
+        let per_page = self.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE);
+        Some(((page - 1) * per_page, per_page))
+    }
+}
+
+/// Options for album listings, parsed from the query string
+///
+/// # Fields
+/// * `include_smart`: Also list smart albums, after the regular ones, as `includeSmart` (defaults to false)
+///
+/// # Example
+/// `GET /album/root?includeSmart=true`
+#[derive(FromForm, Default, Debug)]
+pub struct AlbumListing {
+    #[field(name = "includeSmart")]
+    pub include_smart: Option<bool>,
+}
+
+/// A date and time in a query string, as `YYYY-MM-DDTHH:MM:SS` (local time of the server)
+#[derive(Clone, Copy, Debug)]
+pub struct QueryDateTime(pub NaiveDateTime);
+
+impl<'v> FromFormField<'v> for QueryDateTime {
+    fn from_value(field: ValueField<'v>) -> form::Result<'v, Self> {
+        field.value
+            .parse::<NaiveDateTime>()
+            .map(QueryDateTime)
+            .map_err(|e| form::Error::validation(e.to_string()).into())
+    }
+}
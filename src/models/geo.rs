@@ -0,0 +1,100 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::str::FromStr;
+
+/// A geographic bounding box in WGS84 decimal degrees.
+///
+/// Parsed from the `minLon,minLat,maxLon,maxLat` format used by GeoJSON and most map libraries.
+/// A box with `min_lon > max_lon` crosses the antimeridian.
+///
+/// # Fields
+/// * `min_lon`: Western edge of the box
+/// * `min_lat`: Southern edge of the box
+/// * `max_lon`: Eastern edge of the box
+/// * `max_lat`: Northern edge of the box
+///
+/// # Example
+/// ```
+/// let kyoto: BoundingBox = "135.6,34.9,135.9,35.1".parse()?;
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct BoundingBox {
+    pub min_lon: f64,
+    pub min_lat: f64,
+    pub max_lon: f64,
+    pub max_lat: f64,
+}
+
+impl BoundingBox {
+    /// Whether the box wraps around the antimeridian (i.e. its western edge is east of its eastern edge)
+    pub fn crosses_antimeridian(&self) -> bool {
+        self.min_lon > self.max_lon
+    }
+}
+
+impl FromStr for BoundingBox {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|v| v.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| anyhow::anyhow!("bbox must be four comma-separated numbers: minLon,minLat,maxLon,maxLat"))?;
+
+        let [min_lon, min_lat, max_lon, max_lat] = values[..] else {
+            return Err(anyhow::anyhow!("bbox must be four comma-separated numbers: minLon,minLat,maxLon,maxLat"));
+        };
+
+        if !(-180.0..=180.0).contains(&min_lon) || !(-180.0..=180.0).contains(&max_lon) {
+            return Err(anyhow::anyhow!("bbox longitudes must be between -180 and 180"));
+        }
+        if !(-90.0..=90.0).contains(&min_lat) || !(-90.0..=90.0).contains(&max_lat) || min_lat > max_lat {
+            return Err(anyhow::anyhow!("bbox latitudes must be between -90 and 90, with minLat <= maxLat"));
+        }
+
+        Ok(BoundingBox { min_lon, min_lat, max_lon, max_lat })
+    }
+}
+
+/// A GeoJSON `FeatureCollection` (RFC 7946)
+#[derive(Serialize, Debug)]
+pub struct FeatureCollection {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub features: Vec<Feature>,
+}
+
+impl FeatureCollection {
+    pub fn new(features: Vec<Feature>) -> Self {
+        FeatureCollection { kind: "FeatureCollection", features }
+    }
+}
+
+/// A GeoJSON `Feature` with a `Point` geometry and arbitrary properties
+#[derive(Serialize, Debug)]
+pub struct Feature {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub geometry: Point,
+    pub properties: Value,
+}
+
+impl Feature {
+    /// Creates a point feature. Note that GeoJSON orders coordinates as `[longitude, latitude]`.
+    pub fn point(longitude: f64, latitude: f64, properties: Value) -> Self {
+        Feature {
+            kind: "Feature",
+            geometry: Point { kind: "Point", coordinates: [longitude, latitude] },
+            properties,
+        }
+    }
+}
+
+/// A GeoJSON `Point` geometry
+#[derive(Serialize, Debug)]
+pub struct Point {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub coordinates: [f64; 2],
+}
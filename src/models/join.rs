@@ -1,8 +1,8 @@
-use crate::db::schema::{album_album_join, album_photo_join};
+use crate::db::schema::{album_album_join, album_photo_join, collection_photo_join, photo_tag_join};
 use diesel::{AsChangeset, Insertable, Queryable, Selectable};
 
 /// The `AlbumPhoto` struct corresponds to the `album_photos` table, a join table between
-/// `Album` and `Photo` in the database.
+/// `Album` and `Photo` in the database, holding the place of each photo in the album.
 ///
 /// It exists exclusively for internal use within `crate::db::operations`
 #[derive(Queryable, Selectable, Insertable, AsChangeset, Debug)]
@@ -10,6 +10,7 @@ use diesel::{AsChangeset, Insertable, Queryable, Selectable};
 pub struct AlbumPhoto {
     pub parent_id: i32,
     pub photo_id: i64,
+    pub position: i32,
 }
 
 /// The `AlbumAlbum` struct corresponds to the `album_album` table, a join table between
@@ -21,4 +22,27 @@ pub struct AlbumPhoto {
 pub struct AlbumAlbum {
     pub parent_id: i32,
     pub album_id: i32,
-}
\ No newline at end of file
+}
+
+/// The `PhotoTag` struct corresponds to the `photo_tag_join` table, a join table between
+/// `Tag` and `Photo` in the database.
+///
+/// It exists exclusively for internal use within `crate::db::operations`
+#[derive(Queryable, Selectable, Insertable, Debug)]
+#[diesel(table_name = photo_tag_join)]
+pub struct PhotoTag {
+    pub tag_id: i32,
+    pub photo_id: i64,
+}
+
+/// The `CollectionPhoto` struct corresponds to the `collection_photo_join` table, a join table between
+/// `Collection` and `Photo` in the database, holding the place of each photo in the collection.
+///
+/// It exists exclusively for internal use within `crate::db::operations`
+#[derive(Queryable, Selectable, Insertable, Debug)]
+#[diesel(table_name = collection_photo_join)]
+pub struct CollectionPhoto {
+    pub collection_id: i32,
+    pub photo_id: i64,
+    pub position: i32,
+}
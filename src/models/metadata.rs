@@ -0,0 +1,65 @@
+use crate::db::schema::{photo_metadata, photo_metadata_issues};
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+/// The complete EXIF/XMP metadata of a photo, as extracted by `exiftool` at ingest.
+///
+/// # Fields
+/// * `id`: ID of the photo the metadata belongs to
+/// * `metadata`: JSON object keyed by `Group:Tag` (e.g. `"ExifIFD:WhiteBalance"`), stored as a string
+#[derive(Queryable, Selectable, Insertable, Debug)]
+#[diesel(table_name = photo_metadata)]
+pub struct PhotoMetadata {
+    pub id: i64,
+    pub metadata: String,
+}
+
+
+/// A metadata field that could not be read at ingest and was stored with a fallback value instead
+/// (e.g. the Unix epoch for a missing capture date).
+///
+/// A shutter count or focal length of 0 is not an issue: most cameras don't record the former, and
+/// manual lenses don't report the latter.
+///
+/// Serialized in `snake_case` (e.g. `missing_date`), which is also how it is stored in the database.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataIssue {
+    MissingDate,
+    MissingTimezone,
+    MissingResolution,
+    UnknownMimeType,
+    UnknownCamera,
+    UnknownLens,
+    MissingIso,
+    MissingShutterSpeed,
+    MissingAperture,
+}
+
+impl MetadataIssue {
+    /// The name used for this issue in the database and in JSON
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MetadataIssue::MissingDate => "missing_date",
+            MetadataIssue::MissingTimezone => "missing_timezone",
+            MetadataIssue::MissingResolution => "missing_resolution",
+            MetadataIssue::UnknownMimeType => "unknown_mime_type",
+            MetadataIssue::UnknownCamera => "unknown_camera",
+            MetadataIssue::UnknownLens => "unknown_lens",
+            MetadataIssue::MissingIso => "missing_iso",
+            MetadataIssue::MissingShutterSpeed => "missing_shutter_speed",
+            MetadataIssue::MissingAperture => "missing_aperture",
+        }
+    }
+}
+
+/// The `PhotoMetadataIssue` struct corresponds to the `photo_metadata_issues` table, which records
+/// each `MetadataIssue` found for a photo.
+///
+/// It exists exclusively for internal use within `crate::db::operations`
+#[derive(Queryable, Selectable, Insertable, Debug)]
+#[diesel(table_name = photo_metadata_issues)]
+pub struct PhotoMetadataIssue {
+    pub photo_id: i64,
+    pub issue: String,
+}
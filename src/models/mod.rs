@@ -1,4 +1,15 @@
 pub mod photo;
 pub mod album;
 pub mod join;
-pub mod thumbnail;
\ No newline at end of file
+pub mod thumbnail;
+pub mod geo;
+
+pub mod metadata;
+pub mod filter;
+pub mod tag;
+pub mod verification;
+pub mod audit;
+pub mod phash;
+pub mod stack;
+pub mod collection;
+pub mod smart_album;
@@ -0,0 +1,20 @@
+use crate::db::schema::photo_phashes;
+use diesel::{Insertable, Queryable, Selectable};
+
+/// The perceptual hash of a photo, used to find near-duplicates
+///
+/// # Fields
+/// * `id`: ID of the photo the hash belongs to
+/// * `phash`: 64-bit difference hash (dHash) of the photo's thumbnail, stored bit for bit in a signed
+///   column. Compare hashes with `phash_distance`.
+#[derive(Queryable, Selectable, Insertable, Debug)]
+#[diesel(table_name = photo_phashes)]
+pub struct PhotoPhash {
+    pub id: i64,
+    pub phash: i64,
+}
+
+/// Number of bits in which two perceptual hashes differ (0 = identical, 64 = inverted)
+pub fn phash_distance(a: i64, b: i64) -> u32 {
+    (a ^ b).count_ones()
+}
@@ -3,6 +3,10 @@ use chrono::NaiveDateTime;
 use diesel::prelude::*;
 use rocket::serde::{Deserialize, Serialize};
 
+/// Color labels a photo can be tagged with, matching the values used in XMP `xmp:Label`
+/// by Lightroom, darktable and RawTherapee (compared case-insensitively)
+pub const COLOR_LABELS: [&str; 5] = ["red", "yellow", "green", "blue", "purple"];
+
 /// Represents a photo entity with associated metadata stored in the database.
 ///
 /// The struct implements serialization for JSON responses and can be queried/updated using Diesel ORM.
@@ -24,6 +28,15 @@ use rocket::serde::{Deserialize, Serialize};
 /// - `iso` (`i32`): ISO sensitivity value 
 /// - `shutter_speed` (`String`): Exposure time as a string (e.g. "1/250")
 /// - `aperture` (`f32`): F-stop value used
+/// - `latitude` (`Option<f64>`): GPS latitude in decimal degrees, if the photo is geotagged
+/// - `longitude` (`Option<f64>`): GPS longitude in decimal degrees, if the photo is geotagged
+/// - `altitude` (`Option<f32>`): GPS altitude in metres above sea level, if recorded
+/// - `rating` (`i8`): Star rating, from 0 (unrated) to 5
+/// - `color_label` (`Option<String>`): Color label, one of `COLOR_LABELS`
+/// - `pick_flag` (`i8`): -1 if rejected, 0 if unflagged, 1 if picked
+/// - `deleted_at` (`Option<NaiveDateTime>`): When the photo was moved to the trash; omitted from JSON if it isn't trashed
+/// - `stack_id` (`Option<i32>`): Burst or bracket the photo belongs to; omitted from JSON if it isn't stacked
+/// - `original_id` (`Option<i64>`): Photo this one is a physical copy of; omitted from JSON if it is an original
 #[derive(Queryable, Selectable, AsChangeset, Serialize, Debug)]
 #[diesel(table_name = photos)]
 #[serde(rename_all = "camelCase")]
@@ -45,6 +58,18 @@ pub struct Photo {
     pub iso: i32,
     pub shutter_speed: String,
     pub aperture: f32,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub altitude: Option<f32>,
+    pub rating: i8,
+    pub color_label: Option<String>,
+    pub pick_flag: i8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack_id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_id: Option<i64>,
 }
 
 
@@ -69,6 +94,13 @@ pub struct Photo {
 /// - `iso` (`i32`): ISO sensitivity value
 /// - `shutter_speed` (`String`): Exposure time as a string (e.g. "1/250")
 /// - `aperture` (`f32`): F-stop value used
+/// - `latitude` (`Option<f64>`): GPS latitude in decimal degrees, if the photo is geotagged
+/// - `longitude` (`Option<f64>`): GPS longitude in decimal degrees, if the photo is geotagged
+/// - `altitude` (`Option<f32>`): GPS altitude in metres above sea level, if recorded
+/// - `rating` (`i8`): Star rating, from 0 (unrated) to 5
+/// - `color_label` (`Option<String>`): Color label, one of `COLOR_LABELS`
+/// - `pick_flag` (`i8`): -1 if rejected, 0 if unflagged, 1 if picked
+/// - `original_id` (`Option<i64>`): Photo this one is a physical copy of, `None` for ingested photos
 #[derive(Insertable, Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 #[diesel(table_name = photos)]
@@ -88,4 +120,86 @@ pub struct NewPhoto {
     pub iso: i32,
     pub shutter_speed: String,
     pub aperture: f32,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub altitude: Option<f32>,
+    pub rating: i8,
+    pub color_label: Option<String>,
+    pub pick_flag: i8,
+    #[serde(default)]
+    pub original_id: Option<i64>,
+}
+
+
+/// A partial update of a photo's culling flags, used for batch edits.
+///
+/// Fields left as `None` are not changed. `color_label` is doubly optional so that
+/// `Some(None)` can clear an existing label.
+///
+/// # Fields
+/// - `rating` (`Option<i8>`): New star rating, from 0 to 5
+/// - `color_label` (`Option<Option<String>>`): New color label, or `Some(None)` to remove it
+/// - `pick_flag` (`Option<i8>`): New pick flag (-1 rejected, 0 unflagged, 1 picked)
+#[derive(AsChangeset, Default, Debug)]
+#[diesel(table_name = photos)]
+pub struct PhotoFlags {
+    pub rating: Option<i8>,
+    pub color_label: Option<Option<String>>,
+    pub pick_flag: Option<i8>,
+}
+
+impl PhotoFlags {
+    /// Whether this update would not change anything
+    pub fn is_empty(&self) -> bool {
+        self.rating.is_none() && self.color_label.is_none() && self.pick_flag.is_none()
+    }
+
+    /// Checks that every set field is within its allowed range
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(rating) = self.rating && !(0..=5).contains(&rating) {
+            return Err(anyhow::anyhow!("rating must be between 0 and 5"));
+        }
+        if let Some(pick_flag) = self.pick_flag && !(-1..=1).contains(&pick_flag) {
+            return Err(anyhow::anyhow!("pickFlag must be -1 (rejected), 0 (unflagged) or 1 (picked)"));
+        }
+        if let Some(Some(label)) = &self.color_label && !COLOR_LABELS.contains(&label.as_str()) {
+            return Err(anyhow::anyhow!("colorLabel must be one of {:?}", COLOR_LABELS));
+        }
+        Ok(())
+    }
+}
+
+
+/// Whether a string is a UTC offset in the `±HH:MM` format used for `photo_timezone`
+pub fn is_utc_offset(tz: &str) -> bool {
+    let bytes = tz.as_bytes();
+    bytes.len() == 6
+        && (bytes[0] == b'+' || bytes[0] == b'-')
+        && bytes[1..3].iter().all(u8::is_ascii_digit)
+        && bytes[3] == b':'
+        && bytes[4..6].iter().all(u8::is_ascii_digit)
+}
+
+/// A planned or applied change to a photo's capture time, returned by time-shift operations
+///
+/// # Fields
+/// - `id` (`i64`): ID of the photo, serialized as "photoId" in JSON
+/// - `file_name` (`String`): Filename of the photo
+/// - `old_date` (`NaiveDateTime`): Capture time before the change
+/// - `new_date` (`NaiveDateTime`): Capture time after the change
+/// - `old_timezone` (`String`): UTC offset before the change
+/// - `new_timezone` (`String`): UTC offset after the change
+/// - `warning` (`Option<String>`): Why the photo's XMP sidecar could not be updated, if it couldn't
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PhotoTimeChange {
+    #[serde(rename = "photoId")]
+    pub id: i64,
+    pub file_name: String,
+    pub old_date: NaiveDateTime,
+    pub new_date: NaiveDateTime,
+    pub old_timezone: String,
+    pub new_timezone: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
 }
@@ -0,0 +1,209 @@
+use crate::db::schema::smart_albums;
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A photo property a smart album rule can test
+///
+/// Serialized in `snake_case` (e.g. `lens_model`), matching the column names of `photos`.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SmartField {
+    FileName,
+    MimeType,
+    CameraModel,
+    LensModel,
+    PhotoDate,
+    Iso,
+    FocalLength,
+    Aperture,
+    ShutterCount,
+    Rating,
+    PickFlag,
+    ColorLabel,
+}
+
+/// What kind of values a `SmartField` holds, which determines the operators it supports
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SmartFieldKind {
+    Text,
+    Integer,
+    Decimal,
+    Date,
+}
+
+impl SmartField {
+    /// The kind of values the field holds
+    pub fn kind(&self) -> SmartFieldKind {
+        match self {
+            SmartField::FileName | SmartField::MimeType | SmartField::CameraModel | SmartField::LensModel | SmartField::ColorLabel => SmartFieldKind::Text,
+            SmartField::Iso | SmartField::FocalLength | SmartField::ShutterCount | SmartField::Rating | SmartField::PickFlag => SmartFieldKind::Integer,
+            SmartField::Aperture => SmartFieldKind::Decimal,
+            SmartField::PhotoDate => SmartFieldKind::Date,
+        }
+    }
+}
+
+/// How a smart album rule compares a field with its value
+///
+/// # Variants
+/// * `Eq`, `Ne`, `Lt`, `Le`, `Gt`, `Ge`: Comparisons (`=`, `!=`, `<`, `<=`, `>`, `>=`), for all fields
+/// * `Contains`: Text fields containing the value
+/// * `Year`: Dates within the given year (e.g. `2025`)
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SmartOperator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    Year,
+}
+
+/// A single condition of a smart album, e.g. `{ "field": "iso", "op": "gt", "value": 6400 }`
+///
+/// Dates are given as `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SS` (capture time, as stored).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SmartRule {
+    pub field: SmartField,
+    pub op: SmartOperator,
+    pub value: Value,
+}
+
+impl SmartRule {
+    /// The value of a rule on a text field
+    pub fn text(&self) -> anyhow::Result<&str> {
+        self.value.as_str().ok_or_else(|| anyhow::anyhow!("{:?} needs a string value", self.field))
+    }
+
+    /// The value of a rule on an integer field
+    pub fn integer(&self) -> anyhow::Result<i64> {
+        self.value.as_i64().ok_or_else(|| anyhow::anyhow!("{:?} needs an integer value", self.field))
+    }
+
+    /// The value of a rule on a decimal field
+    pub fn decimal(&self) -> anyhow::Result<f64> {
+        self.value.as_f64().ok_or_else(|| anyhow::anyhow!("{:?} needs a numeric value", self.field))
+    }
+
+    /// The value of a rule on a date field. Dates without a time stand for midnight.
+    pub fn date(&self) -> anyhow::Result<NaiveDateTime> {
+        let text = self.value.as_str().ok_or_else(|| anyhow::anyhow!("{:?} needs a date value", self.field))?;
+        text.parse::<NaiveDateTime>()
+            .or_else(|_| text.parse::<NaiveDate>().map(|date| date.and_time(Default::default())))
+            .map_err(|_| anyhow::anyhow!("{text} is not a date (YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS)"))
+    }
+
+    /// The start and (exclusive) end of the year in a `Year` rule
+    pub fn year_range(&self) -> anyhow::Result<(NaiveDateTime, NaiveDateTime)> {
+        let year = i32::try_from(self.integer()?)?;
+        let start = NaiveDate::from_yo_opt(year, 1).ok_or_else(|| anyhow::anyhow!("{year} is not a valid year"))?;
+        let end = NaiveDate::from_yo_opt(year + 1, 1).ok_or_else(|| anyhow::anyhow!("{year} is not a valid year"))?;
+        Ok((start.and_time(Default::default()), end.and_time(Default::default())))
+    }
+
+    /// Checks that the operator applies to the field, and that the value has the field's type
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let kind = self.field.kind();
+        match self.op {
+            SmartOperator::Contains if kind != SmartFieldKind::Text => Err(anyhow::anyhow!("contains only applies to text fields")),
+            SmartOperator::Year if kind != SmartFieldKind::Date => Err(anyhow::anyhow!("year only applies to photo_date")),
+            SmartOperator::Year => self.year_range().map(|_| ()),
+            _ => match kind {
+                SmartFieldKind::Text => self.text().map(|_| ()),
+                SmartFieldKind::Integer => self.integer().map(|_| ()),
+                SmartFieldKind::Decimal => self.decimal().map(|_| ()),
+                SmartFieldKind::Date => self.date().map(|_| ()),
+            },
+        }
+    }
+}
+
+/// Whether a photo has to match all rules of a smart album, or any of them
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SmartMatch {
+    #[default]
+    All,
+    Any,
+}
+
+/// The saved query of a smart album. Stored as JSON in `smart_albums.query`.
+///
+/// # Example
+/// Photos shot with a given lens at high ISO in 2025:
+/// ```json
+/// {
+///   "match": "all",
+///   "rules": [
+///     { "field": "lens_model", "op": "eq", "value": "FE 24-70mm F2.8 GM II" },
+///     { "field": "iso", "op": "gt", "value": 6400 },
+///     { "field": "photo_date", "op": "year", "value": 2025 }
+///   ]
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SmartQuery {
+    #[serde(default, rename = "match")]
+    pub match_mode: SmartMatch,
+    pub rules: Vec<SmartRule>,
+}
+
+impl SmartQuery {
+    /// Checks that the query has at least one rule, and that every rule is valid
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.rules.is_empty() {
+            return Err(anyhow::anyhow!("A smart album needs at least one rule"));
+        }
+        for rule in &self.rules {
+            rule.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// An album whose photos are not linked to it, but found live by a saved query over their metadata
+///
+/// # Fields
+/// * `id`: Smart album's unique ID, serialized as `smartAlbumId` in JSON
+/// * `album_name`: Album name
+/// * `query`: The `SmartQuery`, stored as JSON
+/// * `created_at`: When the smart album was created (local time of the server)
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = smart_albums)]
+pub struct SmartAlbum {
+    pub id: i32,
+    pub album_name: String,
+    pub query: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl SmartAlbum {
+    /// Parses the stored query
+    pub fn parsed_query(&self) -> anyhow::Result<SmartQuery> {
+        Ok(serde_json::from_str(&self.query)?)
+    }
+
+    /// The smart album as JSON, embedding its query, as listed next to regular albums
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "smartAlbumId": self.id,
+            "albumName": self.album_name,
+            "query": serde_json::from_str::<Value>(&self.query).unwrap_or(Value::Null),
+            "createdAt": self.created_at,
+        })
+    }
+}
+
+/// Data required to create a smart album. See `SmartAlbum` for the fields.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = smart_albums)]
+pub struct NewSmartAlbum {
+    pub album_name: String,
+    pub query: String,
+    pub created_at: NaiveDateTime,
+}
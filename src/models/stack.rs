@@ -0,0 +1,75 @@
+use crate::db::schema::stacks;
+use crate::models::photo::Photo;
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+/// How the photos of a stack were shot
+///
+/// Serialized in `snake_case`, which is also how it is stored in the database.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum StackKind {
+    /// Continuous shooting: the frames are near-identical, and the first is a sensible cover
+    Burst,
+    /// Auto exposure bracketing (AEB): the middle frame is usually the metered exposure
+    Bracket,
+}
+
+impl StackKind {
+    /// The name used for this kind in the database and in JSON
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StackKind::Burst => "burst",
+            StackKind::Bracket => "bracket",
+        }
+    }
+}
+
+/// A burst or exposure bracket. Its photos point to it through `photos.stack_id`.
+///
+/// # Fields
+/// * `id`: ID of the stack, serialized as `stackId` in JSON
+/// * `kind`: A `StackKind`, as stored in the database
+/// * `cover_id`: The photo shown in place of the stack when listings collapse stacks
+/// * `created_at`: When the stack was created (local time of the server)
+#[derive(Queryable, Selectable, Serialize, Debug)]
+#[diesel(table_name = stacks)]
+#[serde(rename_all = "camelCase")]
+pub struct Stack {
+    #[serde(rename = "stackId")]
+    pub id: i32,
+    pub kind: String,
+    pub cover_id: Option<i64>,
+    pub created_at: NaiveDateTime,
+}
+
+/// Data required to create a stack. See `Stack` for the fields.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = stacks)]
+pub struct NewStack {
+    pub kind: String,
+    pub cover_id: Option<i64>,
+    pub created_at: NaiveDateTime,
+}
+
+/// Picks the cover of a stack: the best picked and rated photo. Ties go to the first frame of a
+/// burst, or the middle frame of a bracket.
+///
+/// # Arguments
+/// * `kind` - Kind of the stack, as stored in the database
+/// * `photos` - Photos of the stack in capture order, without trashed ones
+///
+/// # Returns
+/// The ID of the cover, or `None` if `photos` is empty
+pub fn pick_cover(kind: &str, photos: &[Photo]) -> Option<i64> {
+    let best = photos.iter().map(|photo| (photo.pick_flag, photo.rating)).max()?;
+    let candidates: Vec<&Photo> = photos.iter().filter(|photo| (photo.pick_flag, photo.rating) == best).collect();
+
+    let cover = if kind == StackKind::Bracket.as_str() {
+        candidates[candidates.len() / 2]
+    } else {
+        candidates[0]
+    };
+    Some(cover.id)
+}
@@ -0,0 +1,73 @@
+use crate::db::schema::tags;
+use diesel::prelude::*;
+use rocket::serde::{Deserialize, Serialize};
+
+/// Separator between levels of a hierarchical keyword, as used by XMP `lr:HierarchicalSubject`
+pub const TAG_SEPARATOR: char = '|';
+
+/// Represents a keyword in the tag hierarchy.
+///
+/// A full keyword like `Places|Japan|Kyoto` is stored as a chain of tags, each pointing to its parent.
+///
+/// # Fields
+/// * `id`: Tag's unique ID, serialized as `tagId` in JSON
+/// * `tag_name`: Name of this level of the keyword (e.g. `Kyoto`)
+/// * `parent_id`: ID of the parent tag (e.g. `Japan`), or `None` for root keywords
+///
+/// # Example
+/// ```
+/// let tag = Tag {
+///     id: 3,
+///     tag_name: "Kyoto".into(),
+///     parent_id: Some(2),
+/// };
+/// ```
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Tag {
+    #[serde(rename = "tagId")]
+    pub id: i32,
+    pub tag_name: String,
+    pub parent_id: Option<i32>,
+}
+
+/// A variant of `Tag` without an ID, used for creating new tags.
+///
+/// # Fields
+/// * `tag_name`: Name of the new tag
+/// * `parent_id`: ID of the parent tag, or `None` for a root keyword
+#[derive(Insertable, Debug)]
+#[diesel(table_name = tags)]
+pub struct NewTag {
+    pub tag_name: String,
+    pub parent_id: Option<i32>,
+}
+
+/// Splits a hierarchical keyword (e.g. `Places|Japan|Kyoto`) into its levels,
+/// trimming whitespace and validating each level.
+///
+/// # Returns
+/// The keyword's levels from root to leaf, or an error if any level is empty or too long
+pub fn split_tag_path(tag_path: &str) -> anyhow::Result<Vec<String>> {
+    let segments: Vec<String> = tag_path.split(TAG_SEPARATOR).map(|segment| segment.trim().to_string()).collect();
+
+    for segment in &segments {
+        validate_tag_name(segment)?;
+    }
+
+    Ok(segments)
+}
+
+/// Checks that a single level of a keyword is non-empty, fits in the database, and has no separator
+pub fn validate_tag_name(tag_name: &str) -> anyhow::Result<()> {
+    if tag_name.trim().is_empty() {
+        return Err(anyhow::anyhow!("Tag names must not be empty"));
+    }
+    if tag_name.len() > 255 {
+        return Err(anyhow::anyhow!("Tag names must be at most 255 bytes long"));
+    }
+    if tag_name.contains(TAG_SEPARATOR) {
+        return Err(anyhow::anyhow!("Tag names must not contain '{}'", TAG_SEPARATOR));
+    }
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use crate::db::schema::photo_verifications;
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+/// Outcome of re-hashing a photo's original and comparing it with its stored xxh3-128 hash
+///
+/// Serialized in `snake_case` (e.g. `mismatch`), which is also how it is stored in the database.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationStatus {
+    /// The file matches its stored hash
+    Ok,
+    /// The file exists, but its hash differs from the stored one (e.g. bit rot)
+    Mismatch,
+    /// The file could not be found at its expected location
+    Missing,
+}
+
+impl VerificationStatus {
+    /// The name used for this status in the database and in JSON
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerificationStatus::Ok => "ok",
+            VerificationStatus::Mismatch => "mismatch",
+            VerificationStatus::Missing => "missing",
+        }
+    }
+}
+
+/// The result of the last integrity check of a photo
+///
+/// # Fields
+/// * `id`: ID of the photo, serialized as `photoId` in JSON
+/// * `verified_at`: When the original was last re-hashed (local time of the server)
+/// * `status`: A `VerificationStatus`, as stored in the database
+#[derive(Queryable, Selectable, Insertable, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+#[diesel(table_name = photo_verifications)]
+pub struct PhotoVerification {
+    #[serde(rename = "photoId")]
+    pub id: i64,
+    pub verified_at: NaiveDateTime,
+    pub status: String,
+}
@@ -0,0 +1,91 @@
+use crate::db::operations::join_photo_tag::get_photo_tag_ids;
+use crate::db::operations::paths::get_photo_path;
+use crate::db::operations::photo::get_photo;
+use crate::db::operations::tag::get_tag_path;
+use crate::fs_operations::xmp::{write_xmp_sidecar_fs, XmpSidecar};
+use diesel::MysqlConnection;
+use rocket::serde::json::{Json, Value};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::env;
+
+/// Whether DB-side edits should be written back to XMP sidecars.
+///
+/// Enabled unless `$XMP_WRITEBACK` is set to `false`, `off`, `no` or `0`.
+pub fn xmp_writeback_enabled() -> bool {
+    match env::var("XMP_WRITEBACK") {
+        Ok(value) => !matches!(value.trim().to_lowercase().as_str(), "false" | "off" | "no" | "0"),
+        Err(_) => true,
+    }
+}
+
+/// Brings the XMP sidecars of the given photos in line with the database, so that ratings, labels,
+/// keywords and capture times edited here are visible to darktable, Lightroom and RawTherapee.
+///
+/// Call this after committing a DB-side edit. Does nothing if write-back is disabled. The edit is
+/// already committed by then, so a sidecar that can't be written doesn't stop the others; callers
+/// report the failures as warnings (see `with_sidecar_warnings`).
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `photo_ids` - IDs of the photos whose sidecars should be updated
+///
+/// # Returns
+/// The error of every sidecar that could not be written, by photo ID
+pub fn sync_xmp_sidecars(conn: &mut MysqlConnection, photo_ids: &[i64]) -> BTreeMap<i64, String> {
+    let mut failures = BTreeMap::new();
+    if !xmp_writeback_enabled() {
+        return failures;
+    }
+
+    let photos = match get_photo(conn, photo_ids) {
+        Ok(photos) => photos,
+        Err(e) => {
+            for &photo_id in photo_ids {
+                failures.insert(photo_id, format!("Could not update the XMP sidecar of photo {photo_id}: {e}"));
+            }
+            return failures;
+        }
+    };
+
+    for photo in &photos {
+        let mut sync = || -> anyhow::Result<()> {
+            let keywords = get_photo_tag_ids(conn, photo.id)?
+                .into_iter()
+                .map(|tag_id| get_tag_path(conn, tag_id))
+                .collect::<Result<Vec<String>, _>>()?;
+
+            let sidecar = XmpSidecar {
+                rating: photo.rating,
+                pick_flag: photo.pick_flag,
+                color_label: photo.color_label.clone(),
+                keywords,
+                photo_date: photo.photo_date,
+                photo_timezone: photo.photo_timezone.clone(),
+            };
+
+            write_xmp_sidecar_fs(&get_photo_path(conn, photo.id)?, &sidecar)?;
+            Ok(())
+        };
+        if let Err(e) = sync() {
+            failures.insert(photo.id, format!("Could not update the XMP sidecar of {}: {e}", photo.file_name));
+        }
+    }
+
+    failures
+}
+
+/// Adds the sidecars that `sync_xmp_sidecars` failed to write to a response, as a `warnings` array
+///
+/// # Arguments
+/// * `response` - JSON object to return
+/// * `failures` - Errors returned by `sync_xmp_sidecars`
+///
+/// # Returns
+/// The response, with `warnings` added if anything failed
+pub fn with_sidecar_warnings(mut response: Json<Value>, failures: BTreeMap<i64, String>) -> Json<Value> {
+    if !failures.is_empty() {
+        response["warnings"] = json!(failures.into_values().collect::<Vec<String>>());
+    }
+    response
+}
@@ -0,0 +1,45 @@
+use crate::db::operations::paths::get_photo_path;
+use crate::db::operations::photo::{get_photo, get_photos_by_base_name, set_photos_trashed};
+use crate::fs_operations::journal::FsJournal;
+use crate::fs_operations::photo::trash_photo_fs;
+use chrono::Local;
+use diesel::MysqlConnection;
+use std::collections::HashSet;
+
+/// Moves photos and their associated files to the trash and marks them as trashed.
+/// Photos that don't exist or are already trashed are skipped.
+///
+/// Files that share a base name with another photo staying in the same directory are left with it,
+/// so trashing the JPEG of a RAW+JPEG pair keeps the RAW and its sidecar in place.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `journal` - Journal of the current unit of work
+/// * `photo_ids` - IDs of the photos to trash
+///
+/// # Returns
+/// The IDs of the trashed photos, or an error if something failed
+pub fn trash_photos(conn: &mut MysqlConnection, journal: &mut FsJournal, photo_ids: &[i64]) -> anyhow::Result<Vec<i64>> {
+    // Resolve the paths before the photos are marked as trashed
+    let photos = get_photo(conn, photo_ids)?;
+    let mut trashed: HashSet<i64> = HashSet::new();
+    for photo in &photos {
+        let photo_path = get_photo_path(conn, photo.id)?;
+        trashed.insert(photo.id);
+
+        // Photos trashed earlier in this call have already left the directory
+        let mut shared = false;
+        let base_name = photo_path.file_prefix().unwrap_or_default().to_string_lossy();
+        for other in get_photos_by_base_name(conn, &base_name)? {
+            if !trashed.contains(&other.id) && get_photo_path(conn, other.id)?.parent() == photo_path.parent() {
+                shared = true;
+                break;
+            }
+        }
+        trash_photo_fs(journal, &photo_path, photo.id, shared)?;
+    }
+
+    let ids: Vec<i64> = photos.iter().map(|photo| photo.id).collect();
+    set_photos_trashed(conn, &ids, Some(Local::now().naive_local()))?;
+    Ok(ids)
+}